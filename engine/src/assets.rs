@@ -0,0 +1,280 @@
+//! Loading of all assets listed in a JSON manifest file, e.g.
+//!
+//! ```json
+//! {
+//!     "textures": { "smiley": "resources/smiley.png" },
+//!     "sprite_sheets": {
+//!         "smiley": { "texture": "resources/smiley.png", "json": "resources/smiley.json" }
+//!     },
+//!     "fonts": { "arial_16": { "path": "resources/font/arial.ttf", "size": 16 } },
+//!     "sounds": { "click": "resources/audio/click.wav" },
+//!     "music": { "theme": "resources/audio/music.wav" }
+//! }
+//! ```
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use sdl2::video::GLContext;
+use serde::Deserialize;
+
+use crate::{
+    audio::{self, MusicID, SoundID},
+    graphics::{
+        fonts::{self, FontID},
+        rendering::{self, TextureID},
+        sprites::{self, SpriteSheetID},
+    },
+    resources::resolve_resource_path,
+    Engine,
+};
+
+#[derive(Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct AssetManifest {
+    pub textures: HashMap<String, PathBuf>,
+    pub sprite_sheets: HashMap<String, SpriteSheetEntry>,
+    pub fonts: HashMap<String, FontEntry>,
+    pub sounds: HashMap<String, PathBuf>,
+    pub music: HashMap<String, PathBuf>,
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+pub struct SpriteSheetEntry {
+    pub texture: PathBuf,
+    /// Aseprite sprite sheet JSON
+    pub json: PathBuf,
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+pub struct FontEntry {
+    pub path: PathBuf,
+    pub size: u32,
+}
+
+/// The IDs of the loaded assets, by their key in the manifest
+#[derive(Debug, Default)]
+pub struct LoadedAssets {
+    pub textures: HashMap<String, TextureID>,
+    pub sprite_sheets: HashMap<String, SpriteSheetID>,
+    pub fonts: HashMap<String, FontID>,
+    pub sounds: HashMap<String, SoundID>,
+    pub music: HashMap<String, MusicID>,
+}
+
+#[derive(Debug)]
+pub enum ManifestError {
+    IoError(std::io::Error),
+    JsonError(serde_json::error::Error),
+}
+
+#[derive(Debug)]
+pub enum AssetLoadError {
+    MissingFile {
+        key: String,
+        path: PathBuf,
+    },
+    TextureError {
+        key: String,
+        error: rendering::LoadError,
+    },
+    SpriteSheetError {
+        key: String,
+        error: sprites::LoadError,
+    },
+    FontError {
+        key: String,
+        error: fonts::LoadError,
+    },
+    AudioError {
+        key: String,
+        error: audio::LoadError,
+    },
+}
+
+pub fn load_asset_manifest(path: &Path) -> Result<AssetManifest, ManifestError> {
+    let json =
+        std::fs::read_to_string(resolve_resource_path(path)).map_err(ManifestError::IoError)?;
+    parse_asset_manifest(&json).map_err(ManifestError::JsonError)
+}
+
+pub fn parse_asset_manifest(json: &str) -> Result<AssetManifest, serde_json::error::Error> {
+    serde_json::from_str(json)
+}
+
+impl AssetManifest {
+    /// Loads every asset in the manifest, stopping at the first asset that
+    /// can't be loaded.
+    pub fn load_assets(
+        &self,
+        gl: &GLContext,
+        engine: &mut Engine,
+    ) -> Result<LoadedAssets, AssetLoadError> {
+        let mut assets = LoadedAssets::default();
+
+        for (key, path) in &self.textures {
+            check_file_exists(key, path)?;
+            let texture_id =
+                engine
+                    .load_texture(gl, path)
+                    .map_err(|error| AssetLoadError::TextureError {
+                        key: key.clone(),
+                        error,
+                    })?;
+            assets.textures.insert(key.clone(), texture_id);
+        }
+
+        for (key, entry) in &self.sprite_sheets {
+            check_file_exists(key, &entry.texture)?;
+            check_file_exists(key, &entry.json)?;
+            let texture_id = engine.load_texture(gl, &entry.texture).map_err(|error| {
+                AssetLoadError::TextureError {
+                    key: key.clone(),
+                    error,
+                }
+            })?;
+            let sprite_sheet_data =
+                sprites::load_aseprite_sprite_sheet(&entry.json).map_err(|error| {
+                    AssetLoadError::SpriteSheetError {
+                        key: key.clone(),
+                        error,
+                    }
+                })?;
+            let frames = sprites::aseprite_sprite_sheet_frames(&sprite_sheet_data);
+            let sprite_sheet_id = engine.sprites.add_spritesheet(texture_id, &frames, None);
+            assets.sprite_sheets.insert(key.clone(), sprite_sheet_id);
+        }
+
+        for (key, entry) in &self.fonts {
+            check_file_exists(key, &entry.path)?;
+            let font_id = engine
+                .text
+                .load_font(gl, &mut engine.renderer, &entry.path, entry.size)
+                .map_err(|error| AssetLoadError::FontError {
+                    key: key.clone(),
+                    error,
+                })?;
+            assets.fonts.insert(key.clone(), font_id);
+        }
+
+        for (key, path) in &self.sounds {
+            check_file_exists(key, path)?;
+            let sound_id =
+                engine
+                    .audio
+                    .add_sound(path)
+                    .map_err(|error| AssetLoadError::AudioError {
+                        key: key.clone(),
+                        error,
+                    })?;
+            assets.sounds.insert(key.clone(), sound_id);
+        }
+
+        for (key, path) in &self.music {
+            check_file_exists(key, path)?;
+            let music_id =
+                engine
+                    .audio
+                    .add_music(path)
+                    .map_err(|error| AssetLoadError::AudioError {
+                        key: key.clone(),
+                        error,
+                    })?;
+            assets.music.insert(key.clone(), music_id);
+        }
+
+        Ok(assets)
+    }
+}
+
+fn check_file_exists(key: &str, path: &Path) -> Result<(), AssetLoadError> {
+    if resolve_resource_path(path).is_file() {
+        Ok(())
+    } else {
+        Err(AssetLoadError::MissingFile {
+            key: key.to_owned(),
+            path: path.to_owned(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST_JSON: &str = r#"{
+        "textures": {
+            "smiley": "resources/smiley.png"
+        },
+        "sprite_sheets": {
+            "smiley": { "texture": "resources/smiley.png", "json": "resources/smiley.json" }
+        },
+        "fonts": {
+            "arial_16": { "path": "resources/font/arial.ttf", "size": 16 }
+        },
+        "sounds": {
+            "click": "resources/audio/click.wav"
+        },
+        "music": {
+            "theme": "resources/audio/music.wav"
+        }
+    }"#;
+
+    #[test]
+    fn manifest_entries_are_parsed_by_key() {
+        let manifest = parse_asset_manifest(MANIFEST_JSON).unwrap();
+
+        assert_eq!(
+            manifest.textures["smiley"],
+            PathBuf::from("resources/smiley.png")
+        );
+        assert_eq!(
+            manifest.sprite_sheets["smiley"],
+            SpriteSheetEntry {
+                texture: PathBuf::from("resources/smiley.png"),
+                json: PathBuf::from("resources/smiley.json"),
+            }
+        );
+        assert_eq!(
+            manifest.fonts["arial_16"],
+            FontEntry {
+                path: PathBuf::from("resources/font/arial.ttf"),
+                size: 16,
+            }
+        );
+        assert_eq!(
+            manifest.sounds["click"],
+            PathBuf::from("resources/audio/click.wav")
+        );
+        assert_eq!(
+            manifest.music["theme"],
+            PathBuf::from("resources/audio/music.wav")
+        );
+    }
+
+    #[test]
+    fn missing_asset_kinds_default_to_empty() {
+        let manifest = parse_asset_manifest(r#"{ "sounds": {} }"#).unwrap();
+
+        assert_eq!(manifest, AssetManifest::default());
+    }
+
+    #[test]
+    fn font_without_size_is_an_error() {
+        let result = parse_asset_manifest(r#"{ "fonts": { "arial": { "path": "arial.ttf" } } }"#);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn missing_file_error_reports_asset_key() {
+        let result = check_file_exists("smiley", Path::new("does/not/exist.png"));
+
+        assert!(matches!(
+            result,
+            Err(AssetLoadError::MissingFile { key, .. }) if key == "smiley"
+        ));
+    }
+}
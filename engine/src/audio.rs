@@ -1,19 +1,86 @@
-use std::{collections::HashMap, path::Path};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
-use sdl2::mixer::{Chunk, Music};
+use sdl2::mixer::{Channel, Chunk, Group, Music, MAX_VOLUME};
+
+use crate::resources::resolve_resource_path;
 
 pub struct AudioSystem<'a> {
     sounds: HashMap<SoundID, Chunk>,
     tracks: HashMap<MusicID, Music<'a>>,
     next_sound_id: u32,
     next_music_id: u32,
+    /// The most recently played music track
+    current_music: Option<MusicID>,
+    /// The sound last played on each channel, until the channel is found
+    /// to have finished playing
+    playing_channels: HashMap<i32, SoundID>,
+    volumes: AudioVolumes,
+}
+
+/// Volume category that sounds are mixed in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bus {
+    Music,
+    Sfx,
+}
+
+/// Volumes from 0 to `MAX_VOLUME`, multiplied together when playing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioVolumes {
+    pub master: u8,
+    pub music: u8,
+    pub sfx: u8,
+    pub muted: bool,
+}
+
+impl Default for AudioVolumes {
+    fn default() -> Self {
+        AudioVolumes {
+            master: MAX_VOLUME as u8,
+            music: MAX_VOLUME as u8,
+            sfx: MAX_VOLUME as u8,
+            muted: false,
+        }
+    }
+}
+
+impl AudioVolumes {
+    /// The volume sounds in `bus` play at, combining the bus and master
+    /// volumes
+    pub fn effective_volume(&self, bus: Bus) -> i32 {
+        if self.muted {
+            return 0;
+        }
+        let bus_volume = match bus {
+            Bus::Music => self.music,
+            Bus::Sfx => self.sfx,
+        };
+        let scale = |volume: u8| i32::min(volume as i32, MAX_VOLUME);
+        scale(self.master) * scale(bus_volume) / MAX_VOLUME
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct SoundID(u32);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct MusicID(u32);
+pub struct MusicID(pub(crate) u32);
+
+/// How many times a sound is played on its channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Playback {
+    Once,
+    Looping,
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    /// SDL_mixer couldn't load the file, e.g. because it's missing
+    MixerError { path: PathBuf, message: String },
+}
 
 impl<'a> AudioSystem<'a> {
     pub fn new() -> Self {
@@ -22,48 +89,212 @@ impl<'a> AudioSystem<'a> {
             tracks: HashMap::new(),
             next_sound_id: 0,
             next_music_id: 0,
+            current_music: None,
+            playing_channels: HashMap::new(),
+            volumes: AudioVolumes::default(),
         }
     }
 
     /// Add a new sound to the system so that it can be played
     #[allow(dead_code)]
-    pub fn add_sound(&mut self, path: &Path) -> SoundID {
-        let id = self.generate_sound_id();
-        let mut chunk = sdl2::mixer::Chunk::from_file(path).unwrap();
+    pub fn add_sound(&mut self, path: &Path) -> Result<SoundID, LoadError> {
+        let mut chunk =
+            sdl2::mixer::Chunk::from_file(resolve_resource_path(path)).map_err(|message| {
+                LoadError::MixerError {
+                    path: path.to_owned(),
+                    message,
+                }
+            })?;
         chunk.set_volume(128 / 2);
+        let id = self.generate_sound_id();
         self.sounds.insert(id, chunk);
-        id
+        Ok(id)
+    }
+
+    /// Add several sounds, calling `on_progress` with the number of loaded
+    /// sounds and the total after each one, e.g. to show a loading screen.
+    /// A sound that fails to load doesn't stop the rest from loading.
+    pub fn add_sounds(
+        &mut self,
+        paths: &[PathBuf],
+        on_progress: impl FnMut(usize, usize),
+    ) -> Vec<Result<SoundID, LoadError>> {
+        load_batch(paths, |path| self.add_sound(path), on_progress)
     }
 
-    /// Load a new sound to an existing ID, used for hot reloading.
+    /// Load a new sound to an existing ID, used for hot reloading. If loading
+    /// fails the current sound is kept.
     #[allow(dead_code)]
-    pub fn reload_sound(&mut self, id: SoundID, path: &Path) {
+    pub fn reload_sound(&mut self, id: SoundID, path: &Path) -> Result<(), LoadError> {
         log::info!("Reloading sound from \"{}\"", path.display());
-        if let Some(sound) = self.sounds.get_mut(&id) {
-            *sound = sdl2::mixer::Chunk::from_file(path).unwrap();
-        } else {
+        if !self.sounds.contains_key(&id) {
             panic!("Trying to reload sound with non-registered id {}", id.0);
         }
+        let chunk =
+            sdl2::mixer::Chunk::from_file(resolve_resource_path(path)).map_err(|message| {
+                LoadError::MixerError {
+                    path: path.to_owned(),
+                    message,
+                }
+            })?;
+        self.sounds.insert(id, chunk);
+        Ok(())
     }
 
     #[allow(dead_code)]
-    pub fn add_music(&mut self, path: &Path) -> MusicID {
+    pub fn add_music(&mut self, path: &Path) -> Result<MusicID, LoadError> {
+        let music =
+            sdl2::mixer::Music::from_file(resolve_resource_path(path)).map_err(|message| {
+                LoadError::MixerError {
+                    path: path.to_owned(),
+                    message,
+                }
+            })?;
         let id = self.generate_music_id();
-        let music = sdl2::mixer::Music::from_file(path).unwrap();
         self.tracks.insert(id, music);
-        id
+        Ok(id)
     }
 
+    /// Load a new music track to an existing ID, used for hot reloading. If the
+    /// track is currently playing, it's restarted from the beginning.
+    pub fn reload_music(&mut self, id: MusicID, path: &Path) -> Result<(), LoadError> {
+        log::info!("Reloading music from \"{}\"", path.display());
+        if !self.tracks.contains_key(&id) {
+            panic!("Trying to reload music with non-registered id {}", id.0);
+        }
+        let music =
+            sdl2::mixer::Music::from_file(resolve_resource_path(path)).map_err(|message| {
+                LoadError::MixerError {
+                    path: path.to_owned(),
+                    message,
+                }
+            })?;
+
+        let was_playing = self.current_music == Some(id) && self.music_is_playing();
+        // dropping the old track halts it if it's playing
+        self.tracks.insert(id, music);
+        if was_playing {
+            self.play_music(id);
+        }
+        Ok(())
+    }
+
+    /// Plays the sound on the first free channel. If every channel is busy,
+    /// the sound that has played the longest is cut off.
     #[allow(dead_code)]
-    pub fn play_sound(&self, sound: SoundID) {
+    pub fn play_sound(&mut self, sound: SoundID) {
+        self.play_sound_panned(sound, 0.0);
+    }
+
+    /// Plays the sound panned between the left (-1.0) and right (1.0)
+    /// speaker, e.g. by the x position of its source relative to the player.
+    pub fn play_sound_panned(&mut self, sound: SoundID, pan: f32) {
+        self.play_on_free_channel(sound, pan, Playback::Once);
+    }
+
+    /// Plays the sound on repeat until stopped with `stop_channel`, e.g. for
+    /// ambient sounds like a crackling fire. Unlike music, any number of
+    /// looping sounds can play at once, and they're mixed in the sfx bus.
+    pub fn play_sound_looping(&mut self, sound: SoundID) -> Channel {
+        self.play_on_free_channel(sound, 0.0, Playback::Looping)
+    }
+
+    /// Stops whatever sound is playing on the channel
+    pub fn stop_channel(&mut self, channel: Channel) {
+        channel.halt();
+        self.playing_channels.remove(&channel.0);
+    }
+
+    fn play_on_free_channel(&mut self, sound: SoundID, pan: f32, playback: Playback) -> Channel {
         let chunk = &self.sounds[&sound];
-        sdl2::mixer::Channel::all().play(chunk, 0).unwrap();
+        let channel = match select_free_channel(self.channel_count(), |channel| {
+            Channel(channel).is_playing()
+        }) {
+            Some(channel) => Channel(channel),
+            None => Group::default().find_oldest().unwrap_or(Channel::all()),
+        };
+        let played_channel = channel.play(chunk, playback.loops()).unwrap();
+        // panning stays on the channel, so it's set for every sound played.
+        // The channel played on is used, since panning `Channel::all()` would
+        // pan all audio output.
+        let (left, right) = pan_volumes(pan);
+        if let Err(error) = played_channel.set_panning(left, right) {
+            log::warn!("Could not pan sound: {}", error);
+        }
+        played_channel.set_volume(self.volumes.effective_volume(Bus::Sfx));
+        prune_finished_channels(&mut self.playing_channels, |channel| {
+            Channel(channel).is_playing()
+        });
+        self.playing_channels.insert(played_channel.0, sound);
+        played_channel
+    }
+
+    /// Whether the sound is playing on any channel, e.g. to avoid restarting
+    /// an effect that's still playing
+    pub fn is_sound_playing(&mut self, sound: SoundID) -> bool {
+        prune_finished_channels(&mut self.playing_channels, |channel| {
+            Channel(channel).is_playing()
+        });
+        self.playing_channels
+            .values()
+            .any(|playing_sound| *playing_sound == sound)
+    }
+
+    /// Set how many sounds can play at the same time
+    pub fn set_channel_count(&mut self, count: i32) {
+        sdl2::mixer::allocate_channels(count);
+    }
+
+    pub fn channel_count(&self) -> i32 {
+        // a negative count queries the number of channels without changing it
+        sdl2::mixer::allocate_channels(-1)
+    }
+
+    /// How many channels are currently playing a sound
+    pub fn active_channel_count(&self) -> i32 {
+        sdl2::mixer::get_playing_channels_number()
     }
 
     #[allow(dead_code)]
-    pub fn play_music(&self, music: MusicID) {
+    pub fn play_music(&mut self, music: MusicID) {
+        self.current_music = Some(music);
         let music = &self.tracks[&music];
         music.play(-1).unwrap();
+        Music::set_volume(self.volumes.effective_volume(Bus::Music));
+    }
+
+    pub fn volumes(&self) -> AudioVolumes {
+        self.volumes
+    }
+
+    /// Replace all volumes at once, e.g. with volumes read from the config
+    pub fn set_volumes(&mut self, volumes: AudioVolumes) {
+        self.volumes = volumes;
+        self.apply_volumes();
+    }
+
+    pub fn set_bus_volume(&mut self, bus: Bus, volume: u8) {
+        match bus {
+            Bus::Music => self.volumes.music = volume,
+            Bus::Sfx => self.volumes.sfx = volume,
+        }
+        self.apply_volumes();
+    }
+
+    pub fn set_master_volume(&mut self, volume: u8) {
+        self.volumes.master = volume;
+        self.apply_volumes();
+    }
+
+    pub fn set_muted(&mut self, muted: bool) {
+        self.volumes.muted = muted;
+        self.apply_volumes();
+    }
+
+    /// Updates the volume of music and sounds that are already playing
+    fn apply_volumes(&self) {
+        Music::set_volume(self.volumes.effective_volume(Bus::Music));
+        Channel::all().set_volume(self.volumes.effective_volume(Bus::Sfx));
     }
 
     #[allow(dead_code)]
@@ -98,3 +329,272 @@ impl<'a> AudioSystem<'a> {
         MusicID(id)
     }
 }
+
+impl Playback {
+    /// Loop count passed to SDL_mixer, where -1 loops forever
+    fn loops(self) -> i32 {
+        match self {
+            Playback::Once => 0,
+            Playback::Looping => -1,
+        }
+    }
+}
+
+/// Loads each path in order, reporting progress after each one
+fn load_batch<T, E>(
+    paths: &[PathBuf],
+    mut load: impl FnMut(&Path) -> Result<T, E>,
+    mut on_progress: impl FnMut(usize, usize),
+) -> Vec<Result<T, E>> {
+    paths
+        .iter()
+        .enumerate()
+        .map(|(index, path)| {
+            let result = load(path);
+            on_progress(index + 1, paths.len());
+            result
+        })
+        .collect()
+}
+
+/// The lowest numbered channel that isn't playing, if any
+fn select_free_channel(channel_count: i32, is_playing: impl Fn(i32) -> bool) -> Option<i32> {
+    (0..channel_count).find(|channel| !is_playing(*channel))
+}
+
+/// Stops tracking the sounds of channels that are no longer playing
+fn prune_finished_channels(
+    playing_channels: &mut HashMap<i32, SoundID>,
+    is_playing: impl Fn(i32) -> bool,
+) {
+    playing_channels.retain(|channel, _| is_playing(*channel));
+}
+
+/// Left and right speaker volumes for a pan between -1.0 (left) and 1.0
+/// (right). Centered sounds play at full volume in both speakers.
+fn pan_volumes(pan: f32) -> (u8, u8) {
+    let pan = pan.clamp(-1.0, 1.0);
+    let left = f32::round(255.0 * f32::min(1.0, 1.0 - pan)) as u8;
+    let right = f32::round(255.0 * f32::min(1.0, 1.0 + pan)) as u8;
+    (left, right)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn volumes(master: u8, music: u8, sfx: u8) -> AudioVolumes {
+        AudioVolumes {
+            master,
+            music,
+            sfx,
+            muted: false,
+        }
+    }
+
+    #[test]
+    fn full_volumes_play_at_max_volume() {
+        let volumes = AudioVolumes::default();
+
+        assert_eq!(volumes.effective_volume(Bus::Music), MAX_VOLUME);
+        assert_eq!(volumes.effective_volume(Bus::Sfx), MAX_VOLUME);
+    }
+
+    #[parameterized(master = { 128, 64, 64, 0 }, sfx = { 64, 128, 64, 128 }, expected = { 64, 64, 32, 0 })]
+    fn master_and_bus_volumes_are_multiplied(master: u8, sfx: u8, expected: i32) {
+        let volumes = volumes(master, 128, sfx);
+
+        assert_eq!(volumes.effective_volume(Bus::Sfx), expected);
+    }
+
+    #[test]
+    fn bus_volume_only_affects_its_own_bus() {
+        let volumes = volumes(128, 32, 128);
+
+        assert_eq!(volumes.effective_volume(Bus::Music), 32);
+        assert_eq!(volumes.effective_volume(Bus::Sfx), 128);
+    }
+
+    #[test]
+    fn volumes_above_max_are_clamped() {
+        let volumes = volumes(255, 255, 255);
+
+        assert_eq!(volumes.effective_volume(Bus::Sfx), MAX_VOLUME);
+    }
+
+    #[test]
+    fn muted_audio_plays_at_zero_volume() {
+        let volumes = AudioVolumes {
+            muted: true,
+            ..AudioVolumes::default()
+        };
+
+        assert_eq!(volumes.effective_volume(Bus::Music), 0);
+        assert_eq!(volumes.effective_volume(Bus::Sfx), 0);
+    }
+
+    #[test]
+    fn looping_sounds_loop_forever() {
+        assert_eq!(Playback::Looping.loops(), -1);
+    }
+
+    #[test]
+    fn one_shot_sounds_play_once() {
+        assert_eq!(Playback::Once.loops(), 0);
+    }
+
+    #[test]
+    fn finished_channels_are_pruned() {
+        let mut playing_channels =
+            HashMap::from([(0, SoundID(0)), (1, SoundID(1)), (2, SoundID(0))]);
+        let busy_channels = HashSet::from([1, 2]);
+
+        prune_finished_channels(&mut playing_channels, |channel| {
+            busy_channels.contains(&channel)
+        });
+
+        assert_eq!(
+            playing_channels,
+            HashMap::from([(1, SoundID(1)), (2, SoundID(0))])
+        );
+    }
+
+    #[test]
+    fn all_channels_are_pruned_when_nothing_plays() {
+        let mut playing_channels = HashMap::from([(0, SoundID(0)), (3, SoundID(2))]);
+
+        prune_finished_channels(&mut playing_channels, |_| false);
+
+        assert!(playing_channels.is_empty());
+    }
+
+    #[test]
+    fn first_channel_is_selected_when_all_are_free() {
+        let busy_channels = HashSet::<i32>::new();
+
+        let channel = select_free_channel(8, |channel| busy_channels.contains(&channel));
+
+        assert_eq!(channel, Some(0));
+    }
+
+    #[test]
+    fn busy_channels_are_skipped() {
+        let busy_channels = HashSet::from([0, 1, 3]);
+
+        let channel = select_free_channel(8, |channel| busy_channels.contains(&channel));
+
+        assert_eq!(channel, Some(2));
+    }
+
+    #[test]
+    fn no_channel_is_selected_when_all_are_busy() {
+        let busy_channels = HashSet::from([0, 1, 2, 3]);
+
+        let channel = select_free_channel(4, |channel| busy_channels.contains(&channel));
+
+        assert_eq!(channel, None);
+    }
+
+    #[parameterized(pan = {
+        -1.0, 0.0, 1.0, -0.5, 0.5
+    }, expected_volumes = {
+        (255, 0), (255, 255), (0, 255), (255, 128), (128, 255)
+    })]
+    fn pan_maps_to_left_and_right_volumes(pan: f32, expected_volumes: (u8, u8)) {
+        assert_eq!(pan_volumes(pan), expected_volumes);
+    }
+
+    #[parameterized(pan = { -10.0, 10.0 }, expected_volumes = { (255, 0), (0, 255) })]
+    fn pan_outside_range_is_clamped(pan: f32, expected_volumes: (u8, u8)) {
+        assert_eq!(pan_volumes(pan), expected_volumes);
+    }
+
+    #[test]
+    fn batch_loading_reports_progress_after_each_item() {
+        let paths = [
+            PathBuf::from("a.wav"),
+            PathBuf::from("b.wav"),
+            PathBuf::from("c.wav"),
+        ];
+        let mut progress = Vec::new();
+
+        load_batch(
+            &paths,
+            |_| Ok::<(), ()>(()),
+            |loaded, total| progress.push((loaded, total)),
+        );
+
+        assert_eq!(progress, vec![(1, 3), (2, 3), (3, 3)]);
+    }
+
+    #[test]
+    fn batch_loading_assigns_ids_in_order_and_keeps_failures() {
+        let paths = [
+            PathBuf::from("a.wav"),
+            PathBuf::from("missing.wav"),
+            PathBuf::from("c.wav"),
+        ];
+        let mut next_id = 0;
+
+        let results = load_batch(
+            &paths,
+            |path| {
+                if path == Path::new("missing.wav") {
+                    return Err(path.to_owned());
+                }
+                next_id += 1;
+                Ok(SoundID(next_id - 1))
+            },
+            |_, _| {},
+        );
+
+        assert_eq!(
+            results,
+            vec![
+                Ok(SoundID(0)),
+                Err(PathBuf::from("missing.wav")),
+                Ok(SoundID(1))
+            ]
+        );
+    }
+
+    #[test]
+    fn adding_missing_sounds_reports_every_failure() {
+        let mut audio = AudioSystem::new();
+        let paths = [
+            PathBuf::from("does/not/exist.wav"),
+            PathBuf::from("does/not/exist_either.wav"),
+        ];
+        let mut progress_calls = 0;
+
+        let results = audio.add_sounds(&paths, |_, _| progress_calls += 1);
+
+        assert_eq!(progress_calls, 2);
+        assert!(results.iter().all(Result::is_err));
+    }
+
+    #[test]
+    fn adding_missing_sound_returns_error_with_path() {
+        let mut audio = AudioSystem::new();
+        let path = Path::new("does/not/exist.wav");
+
+        let result = audio.add_sound(path);
+
+        assert!(
+            matches!(result, Err(LoadError::MixerError { path: error_path, .. }) if error_path == path)
+        );
+    }
+
+    #[test]
+    fn adding_missing_music_returns_error_with_path() {
+        let mut audio = AudioSystem::new();
+        let path = Path::new("does/not/exist.wav");
+
+        let result = audio.add_music(path);
+
+        assert!(
+            matches!(result, Err(LoadError::MixerError { path: error_path, .. }) if error_path == path)
+        );
+    }
+}
@@ -0,0 +1,52 @@
+use super::Point;
+
+/// Samples a cubic bezier curve from `p0` to `p3` with control points `p1`
+/// and `p2` into `segments + 1` points, rounded to the nearest pixel.
+pub fn bezier_points(p0: Point, p1: Point, p2: Point, p3: Point, segments: u32) -> Vec<Point> {
+    let segments = segments.max(1);
+    let [p0, p1, p2, p3] = [p0, p1, p2, p3].map(|p| p.as_vec2());
+
+    (0..=segments)
+        .map(|i| {
+            let t = i as f32 / segments as f32;
+            let u = 1.0 - t;
+            let p = u * u * u * p0 + 3.0 * u * u * t * p1 + 3.0 * u * t * t * p2 + t * t * t * p3;
+            p.round().as_ivec2()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::point;
+
+    #[test]
+    fn sampled_curve_starts_and_ends_at_end_points() {
+        let (p0, p1, p2, p3) = (point(0, 0), point(5, 20), point(25, -10), point(30, 10));
+
+        let points = bezier_points(p0, p1, p2, p3, 16);
+
+        assert_eq!(points.len(), 17);
+        assert_eq!(points.first(), Some(&p0));
+        assert_eq!(points.last(), Some(&p3));
+    }
+
+    #[test]
+    fn straight_control_points_give_collinear_points() {
+        let (p0, p1, p2, p3) = (point(0, 0), point(10, 10), point(20, 20), point(30, 30));
+
+        let points = bezier_points(p0, p1, p2, p3, 8);
+
+        assert!(points.iter().all(|p| p.x == p.y));
+    }
+
+    #[test]
+    fn zero_segments_gives_end_points() {
+        let (p0, p1, p2, p3) = (point(0, 0), point(5, 20), point(25, -10), point(30, 10));
+
+        let points = bezier_points(p0, p1, p2, p3, 0);
+
+        assert_eq!(points, vec![p0, p3]);
+    }
+}
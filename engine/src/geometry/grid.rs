@@ -0,0 +1,77 @@
+use super::{Point, Rect};
+
+/// Rounds `point` to the nearest grid intersection. Points halfway between
+/// two grid lines are rounded up, also for negative coordinates.
+pub fn snap_to_grid(point: Point, grid_size: u32) -> Point {
+    Point::new(
+        snap_coordinate(point.x, grid_size),
+        snap_coordinate(point.y, grid_size),
+    )
+}
+
+/// Moves the top left corner of `rect` to the nearest grid intersection,
+/// keeping its size
+pub fn snap_rect_to_grid(rect: Rect, grid_size: u32) -> Rect {
+    Rect {
+        x: snap_coordinate(rect.x, grid_size),
+        y: snap_coordinate(rect.y, grid_size),
+        ..rect
+    }
+}
+
+fn snap_coordinate(coordinate: i32, grid_size: u32) -> i32 {
+    if grid_size == 0 {
+        return coordinate;
+    }
+    let grid_size = grid_size as i32;
+    // euclidean division rounds toward negative infinity rather than zero
+    (coordinate + grid_size / 2).div_euclid(grid_size) * grid_size
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::{point, rect};
+
+    #[parameterized(coordinate = {
+        0, 3, 4, 5, 9, 12, 38
+    }, expected = {
+        0, 0, 8, 8, 8, 16, 40
+    })]
+    fn positive_coordinates_snap_to_nearest_line_of_grid_8(coordinate: i32, expected: i32) {
+        assert_eq!(snap_to_grid(point(coordinate, 0), 8), point(expected, 0));
+    }
+
+    #[parameterized(coordinate = {
+        -1, -3, -4, -5, -9, -12, -38
+    }, expected = {
+        0, 0, 0, -8, -8, -8, -40
+    })]
+    fn negative_coordinates_snap_to_nearest_line_of_grid_8(coordinate: i32, expected: i32) {
+        assert_eq!(snap_to_grid(point(0, coordinate), 8), point(0, expected));
+    }
+
+    #[parameterized(coordinate = {
+        14, 16, 17, -14, -16, -17, -31
+    }, expected = {
+        0, 32, 32, 0, 0, -32, -32
+    })]
+    fn coordinates_snap_to_nearest_line_of_grid_32(coordinate: i32, expected: i32) {
+        assert_eq!(
+            snap_to_grid(point(coordinate, coordinate), 32),
+            point(expected, expected)
+        );
+    }
+
+    #[test]
+    fn zero_grid_size_leaves_point_unchanged() {
+        assert_eq!(snap_to_grid(point(-7, 13), 0), point(-7, 13));
+    }
+
+    #[test]
+    fn snapped_rect_keeps_its_size() {
+        let snapped = snap_rect_to_grid(rect(-13, 21, 20, 10), 8);
+
+        assert_eq!(snapped, rect(-16, 24, 20, 10));
+    }
+}
@@ -13,6 +13,41 @@ pub fn point_is_inside_rect(point: Point, rect: Rect) -> bool {
     horizontal_overlap && vertical_overlap
 }
 
+/// Whether the rects share any area. Rects only touching at an edge don't
+/// intersect.
+pub fn rect_intersects_rect(lhs: Rect, rhs: Rect) -> bool {
+    let horizontal_overlap = lhs.x < rhs.x + rhs.w as i32 && rhs.x < lhs.x + lhs.w as i32;
+    let vertical_overlap = lhs.y < rhs.y + rhs.h as i32 && rhs.y < lhs.y + lhs.h as i32;
+
+    horizontal_overlap && vertical_overlap
+}
+
+impl Rect {
+    /// Whether `other` lies entirely inside this rect, edges included
+    pub fn contains_rect(&self, other: Rect) -> bool {
+        let horizontal_overlap =
+            self.x <= other.x && other.x + other.w as i32 <= self.x + self.w as i32;
+        let vertical_overlap =
+            self.y <= other.y && other.y + other.h as i32 <= self.y + self.h as i32;
+
+        horizontal_overlap && vertical_overlap
+    }
+
+    /// Shifts this rect to lie inside `bounds`, shrinking it to the size of
+    /// `bounds` if it's larger.
+    pub fn clamped_to(&self, bounds: Rect) -> Rect {
+        let (w, h) = (self.w.min(bounds.w), self.h.min(bounds.h));
+        let x = self
+            .x
+            .clamp(bounds.x, bounds.x + bounds.w as i32 - w as i32);
+        let y = self
+            .y
+            .clamp(bounds.y, bounds.y + bounds.h as i32 - h as i32);
+
+        Rect { x, y, w, h }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -50,4 +85,71 @@ mod tests {
         let point = point(1, 1);
         assert!(point_is_inside_rect(point, rect))
     }
+
+    #[test]
+    fn overlapping_rects_intersect() {
+        //   0 1 2 3
+        // 0 ┌───┐
+        // 1 │ ┌─┼─┐
+        // 2 └─┼─┘ │
+        // 3   └───┘
+        assert!(rect_intersects_rect(rect(0, 0, 2, 2), rect(1, 1, 2, 2)));
+        assert!(rect_intersects_rect(rect(1, 1, 2, 2), rect(0, 0, 2, 2)));
+    }
+
+    #[test]
+    fn rects_touching_at_edge_do_not_intersect() {
+        //   0 1 2 3 4
+        // 0 ┌───┬───┐
+        // 1 │   │   │
+        // 2 └───┴───┘
+        assert!(!rect_intersects_rect(rect(0, 0, 2, 2), rect(2, 0, 2, 2)));
+    }
+
+    #[test]
+    fn rect_inside_other_rect_intersects() {
+        assert!(rect_intersects_rect(rect(0, 0, 4, 4), rect(1, 1, 1, 1)));
+    }
+
+    #[test]
+    fn rect_contains_rect_inside_it() {
+        //   0 1 2 3
+        // 0 ┌─────┐
+        // 1 │ ┌┐  │
+        // 2 │ └┘  │
+        // 3 └─────┘
+        let bounds = rect(0, 0, 3, 3);
+        assert!(bounds.contains_rect(rect(1, 1, 1, 1)));
+        assert!(bounds.contains_rect(bounds));
+    }
+
+    #[test]
+    fn rect_does_not_contain_overlapping_rect() {
+        let bounds = rect(0, 0, 3, 3);
+        assert!(!bounds.contains_rect(rect(2, 2, 2, 2)));
+        assert!(!bounds.contains_rect(rect(-1, 0, 2, 2)));
+    }
+
+    #[test]
+    fn rect_partly_off_left_edge_is_shifted_inside() {
+        let bounds = rect(0, 0, 100, 100);
+        let clamped = rect(-5, 10, 20, 20).clamped_to(bounds);
+        assert_eq!(clamped, rect(0, 10, 20, 20));
+        assert!(bounds.contains_rect(clamped));
+    }
+
+    #[test]
+    fn rect_partly_off_bottom_right_is_shifted_inside() {
+        let bounds = rect(0, 0, 100, 100);
+        let clamped = rect(90, 95, 20, 20).clamped_to(bounds);
+        assert_eq!(clamped, rect(80, 80, 20, 20));
+    }
+
+    #[test]
+    fn rect_larger_than_bounds_is_shrunk_to_bounds() {
+        let bounds = rect(10, 10, 100, 100);
+        let clamped = rect(-10, 50, 200, 50).clamped_to(bounds);
+        assert_eq!(clamped, rect(10, 50, 100, 50));
+        assert!(bounds.contains_rect(clamped));
+    }
 }
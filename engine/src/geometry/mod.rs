@@ -1,6 +1,8 @@
+pub mod bezier;
+pub mod grid;
 pub mod intersection;
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct Rect {
     pub x: i32,
     pub y: i32,
@@ -8,7 +10,7 @@ pub struct Rect {
     pub h: u32,
 }
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct Dimension {
     pub width: u32,
     pub height: u32,
@@ -16,6 +18,50 @@ pub struct Dimension {
 
 pub type Point = glam::IVec2;
 
+impl Dimension {
+    pub fn area(&self) -> u64 {
+        self.width as u64 * self.height as u64
+    }
+
+    /// Width divided by height, or zero for a dimension without height
+    pub fn aspect_ratio(&self) -> f32 {
+        if self.height == 0 {
+            return 0.0;
+        }
+        self.width as f32 / self.height as f32
+    }
+
+    /// Both sides multiplied by `factor`, truncated to whole pixels
+    pub fn scaled(&self, factor: f32) -> Dimension {
+        Dimension {
+            width: (factor * self.width as f32) as u32,
+            height: (factor * self.height as f32) as u32,
+        }
+    }
+
+    /// A rect of this size with its top left corner at `origin`
+    pub fn to_rect(&self, origin: Point) -> Rect {
+        Rect {
+            x: origin.x,
+            y: origin.y,
+            w: self.width,
+            h: self.height,
+        }
+    }
+}
+
+impl From<(u32, u32)> for Dimension {
+    fn from((width, height): (u32, u32)) -> Self {
+        Dimension { width, height }
+    }
+}
+
+impl From<Dimension> for (u32, u32) {
+    fn from(dimension: Dimension) -> Self {
+        (dimension.width, dimension.height)
+    }
+}
+
 #[inline(always)]
 pub const fn rect(x: i32, y: i32, w: u32, h: u32) -> Rect {
     Rect { x, y, w, h }
@@ -25,3 +71,81 @@ pub const fn rect(x: i32, y: i32, w: u32, h: u32) -> Rect {
 pub const fn point(x: i32, y: i32) -> Point {
     glam::IVec2::new(x, y)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn area_is_width_times_height() {
+        let dimension = Dimension {
+            width: 320,
+            height: 180,
+        };
+
+        assert_eq!(dimension.area(), 57600);
+    }
+
+    #[test]
+    fn area_does_not_overflow_for_large_dimensions() {
+        let dimension = Dimension {
+            width: u32::MAX,
+            height: 2,
+        };
+
+        assert_eq!(dimension.area(), u32::MAX as u64 * 2);
+    }
+
+    #[test]
+    fn aspect_ratio_is_width_over_height() {
+        let dimension = Dimension {
+            width: 320,
+            height: 180,
+        };
+
+        assert_eq!(dimension.aspect_ratio(), 16.0 / 9.0);
+    }
+
+    #[test]
+    fn aspect_ratio_of_zero_height_is_zero() {
+        let dimension = Dimension {
+            width: 320,
+            height: 0,
+        };
+
+        assert_eq!(dimension.aspect_ratio(), 0.0);
+    }
+
+    #[test]
+    fn scaled_dimension_scales_both_sides() {
+        let dimension = Dimension {
+            width: 320,
+            height: 180,
+        };
+
+        assert_eq!(
+            dimension.scaled(2.0),
+            Dimension {
+                width: 640,
+                height: 360
+            }
+        );
+    }
+
+    #[test]
+    fn dimension_converts_to_and_from_tuple() {
+        let dimension = Dimension::from((320, 180));
+
+        assert_eq!(<(u32, u32)>::from(dimension), (320, 180));
+    }
+
+    #[test]
+    fn dimension_becomes_rect_at_origin() {
+        let dimension = Dimension {
+            width: 320,
+            height: 180,
+        };
+
+        assert_eq!(dimension.to_rect(point(10, 20)), rect(10, 20, 320, 180));
+    }
+}
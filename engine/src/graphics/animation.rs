@@ -7,6 +7,7 @@ pub struct AnimationID(u32);
 pub struct AnimationSystem {
     animations: HashMap<AnimationID, AnimationData>,
     next_id: u32,
+    paused: bool,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -25,6 +26,7 @@ impl AnimationSystem {
         AnimationSystem {
             animations: HashMap::new(),
             next_id: 0,
+            paused: false,
         }
     }
 
@@ -90,19 +92,35 @@ impl AnimationSystem {
         );
     }
 
+    /// Removes an animation, e.g. when the entity using it is destroyed.
+    /// Removed IDs are ignored by the rest of the system.
+    pub fn remove_animation(&mut self, animation_id: AnimationID) {
+        self.animations.remove(&animation_id);
+    }
+
+    pub fn clear_animations(&mut self) {
+        self.animations.clear();
+    }
+
     #[allow(dead_code)]
     pub fn start_animation(&mut self, animation_id: AnimationID) {
-        self.animations.get_mut(&animation_id).unwrap().is_playing = true;
+        if let Some(animation) = self.animations.get_mut(&animation_id) {
+            animation.is_playing = true;
+        }
     }
 
     #[allow(dead_code)]
     pub fn stop_animation(&mut self, animation_id: AnimationID) {
-        self.animations.get_mut(&animation_id).unwrap().is_playing = false;
+        if let Some(animation) = self.animations.get_mut(&animation_id) {
+            animation.is_playing = false;
+        }
     }
 
     #[allow(dead_code)]
     pub fn is_playing(&self, animation_id: AnimationID) -> bool {
-        self.animations.get(&animation_id).unwrap().is_playing
+        self.animations
+            .get(&animation_id)
+            .is_some_and(|animation| animation.is_playing)
     }
 
     #[allow(dead_code)]
@@ -113,24 +131,41 @@ impl AnimationSystem {
 
     #[allow(dead_code)]
     pub fn reset_animation(&mut self, animation_id: AnimationID) {
-        let animation = self.animations.get_mut(&animation_id).unwrap();
-        animation.current_frame = animation.from;
-        animation.playback_pos_ms = 0;
+        if let Some(animation) = self.animations.get_mut(&animation_id) {
+            animation.current_frame = animation.from;
+            animation.playback_pos_ms = 0;
+        }
     }
 
     #[allow(dead_code)]
     pub fn step_to_next_frame(&mut self, animation_id: AnimationID) {
-        let animation = self.animations.get_mut(&animation_id).unwrap();
-        animation.current_frame = if animation.current_frame + 1 > animation.to {
-            animation.from
-        } else {
-            animation.current_frame + 1
+        if let Some(animation) = self.animations.get_mut(&animation_id) {
+            animation.current_frame = if animation.current_frame + 1 > animation.to {
+                animation.from
+            } else {
+                animation.current_frame + 1
+            }
         }
     }
 
+    /// Freezes all animations, e.g. while the game is paused. Frames can still
+    /// be stepped through manually with `step_to_next_frame`.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
     pub fn update(&mut self, delta_time_ms: u128) {
+        if self.paused {
+            return;
+        }
+
         for (_, animation) in &mut self.animations {
-            if !animation.is_playing {
+            // an animation without any duration has nothing to advance to
+            if !animation.is_playing || animation.total_length_ms == 0 {
                 continue;
             }
 
@@ -146,25 +181,30 @@ impl AnimationSystem {
         }
     }
 
-    pub fn current_frame(&mut self, animation_id: AnimationID) -> usize {
-        self.animations[&animation_id].current_frame
+    pub fn current_frame(&self, animation_id: AnimationID) -> Option<usize> {
+        self.animations
+            .get(&animation_id)
+            .map(|animation| animation.current_frame)
     }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub enum FrameTagError {
+    MissingFrameTag(String),
+    InvalidFrameRange {
+        from: usize,
+        to: usize,
+        frame_count: usize,
+    },
+}
+
 pub fn add_asperite_sprite_sheet_animation(
     animation_system: &mut AnimationSystem,
     sprite_sheet: &aseprite::SpritesheetData,
     frame_tag_name: &str,
-) -> AnimationID {
-    let frame_tag = sprite_sheet_frame_tag(sprite_sheet, frame_tag_name);
-    let from = frame_tag.from as usize;
-    let to = frame_tag.to as usize;
-    let frame_periods_ms = sprite_sheet.frames[from..=to]
-        .iter()
-        .map(|frame| frame.duration as u128)
-        .collect::<Vec<u128>>();
-
-    animation_system.add_animation(from, to, &frame_periods_ms)
+) -> Result<AnimationID, FrameTagError> {
+    let (from, to, frame_periods_ms) = frame_tag_frame_periods(sprite_sheet, frame_tag_name)?;
+    Ok(animation_system.add_animation(from, to, &frame_periods_ms))
 }
 
 pub fn reload_aseperite_sprite_sheet_animation(
@@ -172,38 +212,55 @@ pub fn reload_aseperite_sprite_sheet_animation(
     animation_system: &mut AnimationSystem,
     sprite_sheet: &aseprite::SpritesheetData,
     frame_tag_name: &str,
-) {
-    let frame_tag = sprite_sheet_frame_tag(sprite_sheet, frame_tag_name);
+) -> Result<(), FrameTagError> {
+    let (from, to, frame_periods_ms) = frame_tag_frame_periods(sprite_sheet, frame_tag_name)?;
+    animation_system.reload_animation(id, from, to, &frame_periods_ms);
+    Ok(())
+}
+
+/// Gets the frame range of a frame tag together with the frame periods,
+/// checking that the range lies within the sprite sheet frames.
+fn frame_tag_frame_periods(
+    sprite_sheet: &aseprite::SpritesheetData,
+    frame_tag_name: &str,
+) -> Result<(usize, usize, Vec<u128>), FrameTagError> {
+    let frame_tag = sprite_sheet_frame_tag(sprite_sheet, frame_tag_name)
+        .ok_or_else(|| FrameTagError::MissingFrameTag(frame_tag_name.to_owned()))?;
     let from = frame_tag.from as usize;
     let to = frame_tag.to as usize;
+    let frame_count = sprite_sheet.frames.len();
+    if from > to || to >= frame_count {
+        return Err(FrameTagError::InvalidFrameRange {
+            from,
+            to,
+            frame_count,
+        });
+    }
+
     let frame_periods_ms = sprite_sheet.frames[from..=to]
         .iter()
         .map(|frame| frame.duration as u128)
         .collect::<Vec<u128>>();
 
-    animation_system.reload_animation(id, from, to, &frame_periods_ms);
+    Ok((from, to, frame_periods_ms))
 }
 
-fn sprite_sheet_frame_tag(
-    sprite_sheet: &aseprite::SpritesheetData,
+fn sprite_sheet_frame_tag<'a>(
+    sprite_sheet: &'a aseprite::SpritesheetData,
     frame_tag_name: &str,
-) -> aseprite::Frametag {
-    let frame_tags = sprite_sheet.meta.frame_tags.as_ref().unwrap();
-    let frame_tag = frame_tags
+) -> Option<&'a aseprite::Frametag> {
+    sprite_sheet
+        .meta
+        .frame_tags
+        .as_ref()?
         .iter()
         .find(|tag| tag.name == frame_tag_name)
-        .map(|f| f.clone())
-        .unwrap();
-
-    frame_tag
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    // TODO: panics if to - from doesn't match length of frames
-
     #[test]
     fn initially_returns_first_frame() {
         let mut animation_system = AnimationSystem::new();
@@ -213,7 +270,18 @@ mod tests {
 
         let frame = animation_system.current_frame(animation_id);
 
-        assert_eq!(frame, 0);
+        assert_eq!(frame, Some(0));
+    }
+
+    #[parameterized(frame_periods_ms = { vec![], vec![0, 0] })]
+    fn animation_without_duration_stays_on_first_frame(frame_periods_ms: Vec<u128>) {
+        let mut animation_system = AnimationSystem::new();
+        let animation_id = animation_system.add_animation(3, 4, &frame_periods_ms);
+
+        animation_system.start_animation(animation_id);
+        animation_system.update(100);
+
+        assert_eq!(animation_system.current_frame(animation_id), Some(3));
     }
 
     #[test]
@@ -228,7 +296,37 @@ mod tests {
         animation_system.update(delta_time_ms);
         let frame = animation_system.current_frame(animation_id);
 
-        assert_eq!(frame, 2);
+        assert_eq!(frame, Some(2));
+    }
+
+    #[test]
+    fn paused_animation_does_not_advance_past_frame_period() {
+        let mut animation_system = AnimationSystem::new();
+        let frame_periods_ms = [100, 100];
+        let (from, to) = (1, 2);
+        let animation_id = animation_system.add_animation(from, to, &frame_periods_ms);
+
+        animation_system.start_animation(animation_id);
+        animation_system.set_paused(true);
+        animation_system.update(150);
+        let frame = animation_system.current_frame(animation_id);
+
+        assert_eq!(frame, Some(1));
+    }
+
+    #[test]
+    fn paused_animation_can_be_stepped_manually() {
+        let mut animation_system = AnimationSystem::new();
+        let frame_periods_ms = [100, 100];
+        let (from, to) = (1, 2);
+        let animation_id = animation_system.add_animation(from, to, &frame_periods_ms);
+
+        animation_system.start_animation(animation_id);
+        animation_system.set_paused(true);
+        animation_system.step_to_next_frame(animation_id);
+        let frame = animation_system.current_frame(animation_id);
+
+        assert_eq!(frame, Some(2));
     }
 
     #[test]
@@ -243,7 +341,7 @@ mod tests {
         animation_system.update(delta_time_ms);
         let frame = animation_system.current_frame(animation_id);
 
-        assert_eq!(frame, 1);
+        assert_eq!(frame, Some(1));
     }
 
     #[test]
@@ -259,7 +357,7 @@ mod tests {
         animation_system.update(delta_time_ms);
         let frame = animation_system.current_frame(animation_id);
 
-        assert_eq!(frame, 1);
+        assert_eq!(frame, Some(1));
     }
 
     #[test]
@@ -272,7 +370,7 @@ mod tests {
         animation_system.step_to_next_frame(animation_id);
         let frame = animation_system.current_frame(animation_id);
 
-        assert_eq!(frame, 2);
+        assert_eq!(frame, Some(2));
     }
 
     #[test]
@@ -287,7 +385,7 @@ mod tests {
         animation_system.step_to_next_frame(animation_id); // -> 0
         let frame = animation_system.current_frame(animation_id);
 
-        assert_eq!(frame, 0);
+        assert_eq!(frame, Some(0));
     }
 
     #[test]
@@ -301,7 +399,7 @@ mod tests {
         animation_system.reload_animation(animation_id, from, to, &frame_periods_ms);
         let frame = animation_system.current_frame(animation_id);
 
-        assert_eq!(frame, 3);
+        assert_eq!(frame, Some(3));
     }
 
     #[test]
@@ -317,6 +415,201 @@ mod tests {
         animation_system.step_to_next_frame(animation_id); // 2 -> 3
         let frame = animation_system.current_frame(animation_id);
 
-        assert_eq!(frame, 3);
+        assert_eq!(frame, Some(3));
+    }
+
+    #[test]
+    fn current_frame_of_unknown_animation_is_none() {
+        let mut other_system = AnimationSystem::new();
+        other_system.add_animation(0, 1, &[100, 100]);
+        let unknown_id = other_system.add_animation(0, 1, &[100, 100]);
+        let animation_system = AnimationSystem::new();
+
+        let frame = animation_system.current_frame(unknown_id);
+
+        assert_eq!(frame, None);
+    }
+
+    #[test]
+    fn removed_animation_no_longer_advances() {
+        let mut animation_system = AnimationSystem::new();
+        let frame_periods_ms = [100, 100];
+        let (from, to) = (1, 2);
+        let removed_id = animation_system.add_animation(from, to, &frame_periods_ms);
+        let kept_id = animation_system.add_animation(from, to, &frame_periods_ms);
+
+        animation_system.start_animation(removed_id);
+        animation_system.start_animation(kept_id);
+        animation_system.remove_animation(removed_id);
+        animation_system.update(100);
+
+        assert!(!animation_system.is_playing(removed_id));
+        assert_eq!(animation_system.current_frame(kept_id), Some(2));
+    }
+
+    #[test]
+    fn current_frame_of_removed_animation_is_none() {
+        let mut animation_system = AnimationSystem::new();
+        let animation_id = animation_system.add_animation(0, 1, &[100, 100]);
+
+        animation_system.remove_animation(animation_id);
+        animation_system.step_to_next_frame(animation_id);
+        let frame = animation_system.current_frame(animation_id);
+
+        assert_eq!(frame, None);
+    }
+
+    #[test]
+    fn cleared_animations_have_no_current_frame() {
+        let mut animation_system = AnimationSystem::new();
+        let first_id = animation_system.add_animation(0, 1, &[100, 100]);
+        let second_id = animation_system.add_animation(2, 3, &[100, 100]);
+
+        animation_system.clear_animations();
+
+        assert_eq!(animation_system.current_frame(first_id), None);
+        assert_eq!(animation_system.current_frame(second_id), None);
+    }
+
+    /// Plays an animation by stepping through `delta_times_ms` in order,
+    /// returning the frame it ends up on
+    fn frame_after_playing(frame_periods_ms: &[u128], delta_times_ms: &[u128]) -> Option<usize> {
+        let mut animation_system = AnimationSystem::new();
+        let to = frame_periods_ms.len() - 1;
+        let animation_id = animation_system.add_animation(0, to, frame_periods_ms);
+
+        animation_system.start_animation(animation_id);
+        for delta_time_ms in delta_times_ms {
+            animation_system.update(*delta_time_ms);
+        }
+        animation_system.current_frame(animation_id)
+    }
+
+    #[parameterized(step_ms = { 1, 7, 16, 33, 101 })]
+    fn many_small_steps_end_on_same_frame_as_one_large_step(step_ms: u128) {
+        let frame_periods_ms = [100, 50, 150, 80];
+        let step_count = 10_000;
+        let small_steps = vec![step_ms; step_count];
+        let total_ms = step_ms * step_count as u128;
+
+        assert_eq!(
+            frame_after_playing(&frame_periods_ms, &small_steps),
+            frame_after_playing(&frame_periods_ms, &[total_ms])
+        );
+    }
+
+    #[test]
+    fn varying_steps_end_on_same_frame_as_one_large_step() {
+        let frame_periods_ms = [100, 50, 150, 80];
+        let varying_steps = [16, 17, 33, 1, 250, 16, 0, 99]
+            .iter()
+            .copied()
+            .cycle()
+            .take(10_000)
+            .collect::<Vec<u128>>();
+        let total_ms = varying_steps.iter().sum();
+
+        assert_eq!(
+            frame_after_playing(&frame_periods_ms, &varying_steps),
+            frame_after_playing(&frame_periods_ms, &[total_ms])
+        );
+    }
+
+    fn sprite_sheet_with_frame_tag(
+        frame_count: usize,
+        from: u32,
+        to: u32,
+    ) -> aseprite::SpritesheetData {
+        let frame = aseprite::Frame {
+            filename: String::new(),
+            frame: aseprite::Rect {
+                x: 0,
+                y: 0,
+                w: 16,
+                h: 16,
+            },
+            rotated: false,
+            trimmed: false,
+            sprite_source_size: aseprite::Rect {
+                x: 0,
+                y: 0,
+                w: 16,
+                h: 16,
+            },
+            source_size: aseprite::Dimensions { w: 16, h: 16 },
+            duration: 100,
+        };
+        aseprite::SpritesheetData {
+            frames: vec![frame; frame_count],
+            meta: aseprite::Metadata {
+                app: String::new(),
+                version: String::new(),
+                format: String::new(),
+                size: aseprite::Dimensions {
+                    w: 16 * frame_count as u32,
+                    h: 16,
+                },
+                scale: "1".to_owned(),
+                frame_tags: Some(vec![aseprite::Frametag {
+                    name: "Walk".to_owned(),
+                    from,
+                    to,
+                    direction: aseprite::Direction::Forward,
+                }]),
+                layers: None,
+                image: None,
+            },
+        }
+    }
+
+    #[test]
+    fn aseprite_animation_can_be_added_from_well_formed_frame_tag() {
+        let mut animation_system = AnimationSystem::new();
+        let sprite_sheet = sprite_sheet_with_frame_tag(4, 1, 3);
+
+        let animation_id =
+            add_asperite_sprite_sheet_animation(&mut animation_system, &sprite_sheet, "Walk")
+                .unwrap();
+        animation_system.start_animation(animation_id);
+        animation_system.update(200);
+        let frame = animation_system.current_frame(animation_id);
+
+        assert_eq!(frame, Some(3));
+    }
+
+    #[parameterized(from = {
+        1, 2, 3
+    }, to = {
+        4, 1, 10
+    })]
+    fn aseprite_animation_with_out_of_range_frame_tag_is_error(from: u32, to: u32) {
+        let mut animation_system = AnimationSystem::new();
+        let sprite_sheet = sprite_sheet_with_frame_tag(4, from, to);
+
+        let result =
+            add_asperite_sprite_sheet_animation(&mut animation_system, &sprite_sheet, "Walk");
+
+        assert_eq!(
+            result,
+            Err(FrameTagError::InvalidFrameRange {
+                from: from as usize,
+                to: to as usize,
+                frame_count: 4
+            })
+        );
+    }
+
+    #[test]
+    fn aseprite_animation_with_missing_frame_tag_is_error() {
+        let mut animation_system = AnimationSystem::new();
+        let sprite_sheet = sprite_sheet_with_frame_tag(4, 0, 3);
+
+        let result =
+            add_asperite_sprite_sheet_animation(&mut animation_system, &sprite_sheet, "Jump");
+
+        assert_eq!(
+            result,
+            Err(FrameTagError::MissingFrameTag("Jump".to_owned()))
+        );
     }
 }
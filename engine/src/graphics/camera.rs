@@ -0,0 +1,127 @@
+use crate::geometry::Dimension;
+
+/// A view into world space, centered on `position`. A `zoom` above 1.0 makes
+/// the world appear larger.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera2D {
+    pub position: glam::Vec2,
+    pub zoom: f32,
+}
+
+impl Camera2D {
+    pub fn new(x: f32, y: f32) -> Self {
+        Camera2D {
+            position: glam::vec2(x, y),
+            zoom: 1.0,
+        }
+    }
+
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        self.position += glam::vec2(dx, dy);
+    }
+
+    pub fn world_to_screen(&self, world_pos: glam::Vec2, canvas_size: Dimension) -> glam::Vec2 {
+        (world_pos - self.position) * self.zoom + canvas_center(canvas_size)
+    }
+
+    pub fn screen_to_world(&self, screen_pos: glam::Vec2, canvas_size: Dimension) -> glam::Vec2 {
+        (screen_pos - canvas_center(canvas_size)) / self.zoom + self.position
+    }
+
+    /// The world space bounds visible on the canvas, as (left, right, bottom, top)
+    pub fn view_bounds(&self, canvas_size: Dimension) -> (f32, f32, f32, f32) {
+        let half_extent = canvas_center(canvas_size) / self.zoom;
+        let top_left = self.position - half_extent;
+        let bottom_right = self.position + half_extent;
+        (top_left.x, bottom_right.x, bottom_right.y, top_left.y)
+    }
+}
+
+impl Default for Camera2D {
+    fn default() -> Self {
+        Camera2D::new(0.0, 0.0)
+    }
+}
+
+fn canvas_center(canvas_size: Dimension) -> glam::Vec2 {
+    glam::vec2(canvas_size.width as f32, canvas_size.height as f32) / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CANVAS_SIZE: Dimension = Dimension {
+        width: 400,
+        height: 300,
+    };
+
+    #[test]
+    fn camera_position_is_at_center_of_screen() {
+        let camera = Camera2D::new(100.0, 50.0);
+
+        let screen_pos = camera.world_to_screen(glam::vec2(100.0, 50.0), CANVAS_SIZE);
+
+        assert_eq!(screen_pos, glam::vec2(200.0, 150.0));
+    }
+
+    #[test]
+    fn panning_camera_moves_world_in_opposite_direction() {
+        let mut camera = Camera2D::new(0.0, 0.0);
+
+        camera.pan(10.0, -20.0);
+        let screen_pos = camera.world_to_screen(glam::vec2(0.0, 0.0), CANVAS_SIZE);
+
+        assert_eq!(screen_pos, glam::vec2(190.0, 170.0));
+    }
+
+    #[test]
+    fn zooming_in_scales_distances_from_camera() {
+        let camera = Camera2D {
+            position: glam::vec2(100.0, 50.0),
+            zoom: 2.0,
+        };
+
+        let screen_pos = camera.world_to_screen(glam::vec2(110.0, 40.0), CANVAS_SIZE);
+
+        assert_eq!(screen_pos, glam::vec2(220.0, 130.0));
+    }
+
+    #[parameterized(position = {
+        (0.0, 0.0), (100.0, 50.0), (-30.0, 75.0)
+    }, zoom = {
+        1.0, 2.0, 0.5
+    })]
+    fn screen_to_world_is_inverse_of_world_to_screen(position: (f32, f32), zoom: f32) {
+        let camera = Camera2D {
+            position: glam::vec2(position.0, position.1),
+            zoom,
+        };
+        let world_pos = glam::vec2(12.0, 34.0);
+
+        let screen_pos = camera.world_to_screen(world_pos, CANVAS_SIZE);
+
+        assert_eq!(camera.screen_to_world(screen_pos, CANVAS_SIZE), world_pos);
+    }
+
+    #[test]
+    fn default_camera_view_bounds_covers_canvas_centered_on_origin() {
+        let camera = Camera2D::default();
+
+        let bounds = camera.view_bounds(CANVAS_SIZE);
+
+        assert_eq!(bounds, (-200.0, 200.0, 150.0, -150.0));
+    }
+
+    #[test]
+    fn zoomed_camera_view_bounds_covers_smaller_area() {
+        let camera = Camera2D {
+            position: glam::vec2(200.0, 150.0),
+            zoom: 2.0,
+        };
+
+        let bounds = camera.view_bounds(CANVAS_SIZE);
+
+        assert_eq!(bounds, (100.0, 300.0, 225.0, 75.0));
+    }
+}
@@ -0,0 +1,225 @@
+/// An 8-bit per channel RGBA color
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseColorError {
+    /// The string isn't on the form "#RRGGBB" or "#RRGGBBAA"
+    InvalidFormat(String),
+}
+
+impl Color {
+    pub const TRANSPARENT: Color = Color::rgba(0, 0, 0, 0);
+    pub const BLACK: Color = Color::rgb(0, 0, 0);
+    pub const WHITE: Color = Color::rgb(255, 255, 255);
+    pub const RED: Color = Color::rgb(255, 0, 0);
+    pub const GREEN: Color = Color::rgb(0, 255, 0);
+    pub const BLUE: Color = Color::rgb(0, 0, 255);
+    pub const YELLOW: Color = Color::rgb(255, 255, 0);
+    pub const MAGENTA: Color = Color::rgb(255, 0, 255);
+    pub const CYAN: Color = Color::rgb(0, 255, 255);
+
+    /// An opaque color
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Color { r, g, b, a: 255 }
+    }
+
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Color { r, g, b, a }
+    }
+
+    /// Parses a color from a "#RRGGBB" or "#RRGGBBAA" hex string
+    pub fn from_hex(hex: &str) -> Result<Self, ParseColorError> {
+        let invalid_format = || ParseColorError::InvalidFormat(hex.to_owned());
+        let digits = hex.strip_prefix('#').ok_or_else(invalid_format)?;
+        let is_hex = digits.chars().all(|c| c.is_ascii_hexdigit());
+        if !is_hex || !(digits.len() == 6 || digits.len() == 8) {
+            return Err(invalid_format());
+        }
+
+        let channel = |index: usize| {
+            u8::from_str_radix(&digits[2 * index..2 * index + 2], 16).map_err(|_| invalid_format())
+        };
+        let alpha = if digits.len() == 8 { channel(3)? } else { 255 };
+
+        Ok(Color::rgba(channel(0)?, channel(1)?, channel(2)?, alpha))
+    }
+
+    pub fn with_alpha(self, a: u8) -> Self {
+        Color { a, ..self }
+    }
+
+    /// The channels normalized to the range [0, 1]
+    pub fn to_f32_array(self) -> [f32; 4] {
+        [
+            self.r as f32 / 255.0,
+            self.g as f32 / 255.0,
+            self.b as f32 / 255.0,
+            self.a as f32 / 255.0,
+        ]
+    }
+}
+
+impl From<(u8, u8, u8)> for Color {
+    fn from((r, g, b): (u8, u8, u8)) -> Self {
+        Color::rgb(r, g, b)
+    }
+}
+
+impl From<(u8, u8, u8, u8)> for Color {
+    fn from((r, g, b, a): (u8, u8, u8, u8)) -> Self {
+        Color::rgba(r, g, b, a)
+    }
+}
+
+impl From<Color> for (u8, u8, u8, u8) {
+    fn from(color: Color) -> Self {
+        (color.r, color.g, color.b, color.a)
+    }
+}
+
+/// Converts a hue in degrees (wrapping around at 360) and a saturation and
+/// value in the range [0, 1] to an opaque color
+pub fn hsv_to_rgb(h: f32, s: f32, v: f32) -> Color {
+    let (s, v) = (s.clamp(0.0, 1.0), v.clamp(0.0, 1.0));
+    let h = h.rem_euclid(360.0);
+
+    let chroma = v * s;
+    let sector = h / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = v - chroma;
+    let to_u8 = |channel: f32| f32::round((channel + m) * 255.0) as u8;
+
+    Color::rgb(to_u8(r), to_u8(g), to_u8(b))
+}
+
+/// Converts the RGB channels of `color` to a hue in degrees in the range
+/// [0, 360) and a saturation and value in the range [0, 1]. Grays have hue 0.
+pub fn rgb_to_hsv(color: Color) -> (f32, f32, f32) {
+    let [r, g, b, _] = color.to_f32_array();
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+
+    let h = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    let s = if max == 0.0 { 0.0 } else { delta / max };
+
+    (h, s, max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[parameterized(hex = {
+        "#000000", "#ffffff", "#FF8000", "#12345678"
+    }, expected = {
+        Color::BLACK, Color::WHITE, Color::rgb(255, 128, 0), Color::rgba(0x12, 0x34, 0x56, 0x78)
+    })]
+    fn valid_hex_strings_are_parsed(hex: &str, expected: Color) {
+        assert_eq!(Color::from_hex(hex), Ok(expected));
+    }
+
+    #[parameterized(hex = {
+        "", "#", "000000", "#00000", "#0000000", "#gg0000", "#+10000", "#00ä000"
+    })]
+    fn invalid_hex_strings_are_errors(hex: &str) {
+        assert_eq!(
+            Color::from_hex(hex),
+            Err(ParseColorError::InvalidFormat(hex.to_owned()))
+        );
+    }
+
+    #[test]
+    fn named_constants_have_expected_channels() {
+        assert_eq!(Color::BLACK, Color::rgba(0, 0, 0, 255));
+        assert_eq!(Color::WHITE, Color::rgba(255, 255, 255, 255));
+        assert_eq!(Color::RED, Color::rgba(255, 0, 0, 255));
+        assert_eq!(Color::GREEN, Color::rgba(0, 255, 0, 255));
+        assert_eq!(Color::BLUE, Color::rgba(0, 0, 255, 255));
+        assert_eq!(Color::TRANSPARENT.a, 0);
+    }
+
+    #[test]
+    fn tuples_convert_to_colors() {
+        assert_eq!(Color::from((1, 2, 3)), Color::rgba(1, 2, 3, 255));
+        assert_eq!(Color::from((1, 2, 3, 4)), Color::rgba(1, 2, 3, 4));
+        assert_eq!(
+            <(u8, u8, u8, u8)>::from(Color::rgba(1, 2, 3, 4)),
+            (1, 2, 3, 4)
+        );
+    }
+
+    #[test]
+    fn channels_are_normalized() {
+        assert_eq!(
+            Color::rgba(255, 0, 51, 255).to_f32_array(),
+            [1.0, 0.0, 0.2, 1.0]
+        );
+    }
+
+    fn assert_hsv_eq(actual: (f32, f32, f32), expected: (f32, f32, f32)) {
+        const EPSILON: f32 = 0.01;
+        assert!(
+            (actual.0 - expected.0).abs() < EPSILON
+                && (actual.1 - expected.1).abs() < EPSILON
+                && (actual.2 - expected.2).abs() < EPSILON,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[parameterized(hsv = {
+        (0.0, 1.0, 1.0), (120.0, 1.0, 1.0), (240.0, 1.0, 1.0), (30.0, 1.0, 1.0), (180.0, 0.5, 0.5), (300.0, 1.0, 1.0)
+    }, expected = {
+        Color::RED, Color::GREEN, Color::BLUE, Color::rgb(255, 128, 0), Color::rgb(64, 128, 128), Color::MAGENTA
+    })]
+    fn hsv_is_converted_to_rgb(hsv: (f32, f32, f32), expected: Color) {
+        let (h, s, v) = hsv;
+        assert_eq!(hsv_to_rgb(h, s, v), expected);
+    }
+
+    #[parameterized(hue = { 360.0, 480.0, -120.0 }, expected = {
+        Color::RED, Color::GREEN, Color::BLUE
+    })]
+    fn hue_wraps_around(hue: f32, expected: Color) {
+        assert_eq!(hsv_to_rgb(hue, 1.0, 1.0), expected);
+    }
+
+    #[test]
+    fn zero_saturation_is_gray_for_any_hue() {
+        assert_eq!(hsv_to_rgb(0.0, 0.0, 0.5), Color::rgb(128, 128, 128));
+        assert_eq!(hsv_to_rgb(200.0, 0.0, 0.5), Color::rgb(128, 128, 128));
+    }
+
+    #[parameterized(color = {
+        Color::RED, Color::GREEN, Color::BLUE, Color::rgb(255, 128, 0), Color::rgb(128, 128, 128), Color::BLACK
+    }, expected = {
+        (0.0, 1.0, 1.0), (120.0, 1.0, 1.0), (240.0, 1.0, 1.0), (30.1, 1.0, 1.0), (0.0, 0.0, 0.502), (0.0, 0.0, 0.0)
+    })]
+    fn rgb_is_converted_to_hsv(color: Color, expected: (f32, f32, f32)) {
+        assert_hsv_eq(rgb_to_hsv(color), expected);
+    }
+}
@@ -0,0 +1,227 @@
+//! Debug shapes submitted from anywhere during a frame, drawn on top of the
+//! game when the frame is rendered, e.g. to visualize collision rects.
+
+use crate::geometry::{intersection::rect_intersects_rect, Point, Rect};
+
+use super::{
+    color::Color,
+    fonts::{FontID, TextSystem},
+    rendering::Renderer,
+};
+
+/// Debug shapes are drawn above every other layer
+const DEBUG_DRAW_LAYER: i32 = i32::MAX;
+
+const COLLIDING_COLOR: Color = Color::RED;
+const NON_COLLIDING_COLOR: Color = Color::GREEN;
+
+#[derive(Debug, Default)]
+pub struct DebugDraw {
+    shapes: Vec<DebugShape>,
+    enabled: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DebugShape {
+    Line {
+        start: Point,
+        end: Point,
+        color: Color,
+    },
+    Rect {
+        rect: Rect,
+        color: Color,
+    },
+    Label {
+        pos: Point,
+        text: String,
+        color: Color,
+    },
+}
+
+impl DebugDraw {
+    pub fn new() -> Self {
+        DebugDraw::default()
+    }
+
+    /// Shapes are only drawn while enabled, but are always cleared each frame
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn line(&mut self, start: Point, end: Point, color: Color) {
+        self.shapes.push(DebugShape::Line { start, end, color });
+    }
+
+    pub fn rect(&mut self, rect: Rect, color: Color) {
+        self.shapes.push(DebugShape::Rect { rect, color });
+    }
+
+    pub fn label(&mut self, pos: Point, text: &str, color: Color) {
+        self.shapes.push(DebugShape::Label {
+            pos,
+            text: text.to_owned(),
+            color,
+        });
+    }
+
+    /// Outlines `rects`, red if intersecting any of the other rects and green
+    /// otherwise
+    pub fn collision_rects(&mut self, rects: &[Rect]) {
+        for (rect, is_colliding) in rects.iter().zip(colliding_rects(rects)) {
+            let color = if is_colliding {
+                COLLIDING_COLOR
+            } else {
+                NON_COLLIDING_COLOR
+            };
+            self.rect(*rect, color);
+        }
+    }
+
+    /// Shapes submitted since the last flush
+    pub fn shapes(&self) -> &[DebugShape] {
+        &self.shapes
+    }
+
+    /// Draws and clears all submitted shapes, in canvas space on top of
+    /// everything else
+    pub fn flush(&mut self, renderer: &mut Renderer, text_system: &mut TextSystem, font: FontID) {
+        let shapes = self.drain();
+        if !self.enabled || shapes.is_empty() {
+            return;
+        }
+
+        renderer.reset_camera();
+        renderer.set_layer(DEBUG_DRAW_LAYER);
+        let text_color = text_system.text_color();
+        for shape in shapes {
+            match shape {
+                DebugShape::Line { start, end, color } => {
+                    renderer.set_draw_color(color);
+                    renderer.draw_line(start.x, start.y, end.x, end.y);
+                }
+                DebugShape::Rect { rect, color } => {
+                    renderer.set_draw_color(color);
+                    renderer.draw_rect(rect);
+                }
+                DebugShape::Label { pos, text, color } => {
+                    text_system.set_text_color(color);
+                    text_system.draw_text(renderer, font, pos.x, pos.y, &text);
+                }
+            }
+        }
+        text_system.set_text_color(text_color);
+        renderer.reset_layer();
+    }
+
+    fn drain(&mut self) -> Vec<DebugShape> {
+        std::mem::take(&mut self.shapes)
+    }
+}
+
+/// Whether each rect intersects any of the other rects
+pub fn colliding_rects(rects: &[Rect]) -> Vec<bool> {
+    let mut is_colliding = vec![false; rects.len()];
+    for i in 0..rects.len() {
+        for j in i + 1..rects.len() {
+            if rect_intersects_rect(rects[i], rects[j]) {
+                is_colliding[i] = true;
+                is_colliding[j] = true;
+            }
+        }
+    }
+    is_colliding
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::{point, rect};
+
+    #[test]
+    fn submitted_shapes_are_collected_in_order() {
+        let mut debug_draw = DebugDraw::new();
+
+        debug_draw.rect(rect(0, 0, 16, 16), Color::RED);
+        debug_draw.line(point(0, 0), point(16, 16), Color::GREEN);
+        debug_draw.label(point(0, 20), "player", Color::WHITE);
+
+        assert_eq!(
+            debug_draw.shapes(),
+            &[
+                DebugShape::Rect {
+                    rect: rect(0, 0, 16, 16),
+                    color: Color::RED,
+                },
+                DebugShape::Line {
+                    start: point(0, 0),
+                    end: point(16, 16),
+                    color: Color::GREEN,
+                },
+                DebugShape::Label {
+                    pos: point(0, 20),
+                    text: "player".to_owned(),
+                    color: Color::WHITE,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn drained_shapes_are_cleared() {
+        let mut debug_draw = DebugDraw::new();
+        debug_draw.rect(rect(0, 0, 16, 16), Color::RED);
+
+        let drained = debug_draw.drain();
+
+        assert_eq!(drained.len(), 1);
+        assert!(debug_draw.shapes().is_empty());
+    }
+
+    #[test]
+    fn rects_are_classified_by_whether_they_collide() {
+        let rects = [
+            rect(0, 0, 10, 10),  // overlaps the next rect
+            rect(5, 5, 10, 10),  // overlaps the previous rect
+            rect(30, 0, 10, 10), // only touches the next rect
+            rect(40, 0, 10, 10), // only touches the previous rect
+            rect(0, 30, 4, 4),   // inside the next rect
+            rect(-5, 25, 20, 20),
+        ];
+
+        let is_colliding = colliding_rects(&rects);
+
+        assert_eq!(is_colliding, vec![true, true, false, false, true, true]);
+    }
+
+    #[test]
+    fn single_rect_does_not_collide_with_itself() {
+        assert_eq!(colliding_rects(&[rect(0, 0, 10, 10)]), vec![false]);
+    }
+
+    #[test]
+    fn collision_rects_are_drawn_in_collision_colors() {
+        let mut debug_draw = DebugDraw::new();
+
+        debug_draw.collision_rects(&[rect(0, 0, 10, 10), rect(5, 5, 10, 10), rect(30, 0, 4, 4)]);
+
+        let colors = debug_draw
+            .shapes()
+            .iter()
+            .map(|shape| match shape {
+                DebugShape::Rect { color, .. } => *color,
+                _ => panic!("expected only rects"),
+            })
+            .collect::<Vec<Color>>();
+        assert_eq!(colors, vec![Color::RED, Color::RED, Color::GREEN]);
+    }
+
+    #[test]
+    fn debug_draw_is_initially_disabled() {
+        assert!(!DebugDraw::new().is_enabled());
+    }
+}
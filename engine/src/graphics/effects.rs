@@ -0,0 +1,79 @@
+/// Toggles between visible and hidden every `period_ms`, starting visible.
+/// Fed the frame delta, so the blink rate is independent of frame rate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Blink {
+    period_ms: u128,
+    elapsed_ms: u128,
+}
+
+impl Blink {
+    pub fn new(period_ms: u128) -> Self {
+        assert!(period_ms > 0, "blink period must be non-zero");
+        Blink {
+            period_ms,
+            elapsed_ms: 0,
+        }
+    }
+
+    pub fn update(&mut self, delta_time_ms: u128) {
+        self.elapsed_ms = (self.elapsed_ms + delta_time_ms) % (2 * self.period_ms);
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.elapsed_ms < self.period_ms
+    }
+
+    /// Restart the blink from the beginning of its visible phase
+    pub fn reset(&mut self) {
+        self.elapsed_ms = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blink_is_initially_visible() {
+        let blink = Blink::new(100);
+
+        assert!(blink.is_visible());
+    }
+
+    #[parameterized(elapsed_ms = {
+        0, 99, 100, 199, 200, 299, 300, 1000
+    }, expected = {
+        true, true, false, false, true, true, false, true
+    })]
+    fn blink_toggles_every_period(elapsed_ms: u128, expected: bool) {
+        let mut blink = Blink::new(100);
+
+        blink.update(elapsed_ms);
+
+        assert_eq!(blink.is_visible(), expected);
+    }
+
+    #[test]
+    fn blink_toggles_when_period_is_reached_over_several_frames() {
+        let mut blink = Blink::new(100);
+
+        blink.update(60);
+        assert!(blink.is_visible());
+        blink.update(40);
+        assert!(!blink.is_visible());
+        blink.update(99);
+        assert!(!blink.is_visible());
+        blink.update(1);
+        assert!(blink.is_visible());
+    }
+
+    #[test]
+    fn reset_blink_is_visible() {
+        let mut blink = Blink::new(100);
+
+        blink.update(150);
+        blink.reset();
+
+        assert!(blink.is_visible());
+    }
+}
@@ -1,23 +1,98 @@
-use std::{collections::HashMap, hash::Hash, path::Path};
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    path::{Path, PathBuf},
+};
 
-use freetype::face::LoadFlag;
+use freetype::face::{KerningMode, LoadFlag};
+use itertools::Itertools;
 use sdl2::video::GLContext;
 
-use crate::geometry::Rect;
+use crate::{
+    geometry::{point, Point, Rect},
+    resources::resolve_resource_path,
+};
 
-use super::rendering::{Renderer, TextureID};
+use super::{
+    color::Color,
+    rendering::{Renderer, TextureID},
+};
 
 pub struct TextSystem {
     library: freetype::Library,
-    fonts: HashMap<FontID, HashMap<char, GlyphData>>,
+    fonts: HashMap<FontID, FontData>,
     next_id: u32,
     scaling: f32,
-    color: (u8, u8, u8, u8),
+    color: Color,
+    tab_width: u32,
+    shadow: Option<(Point, Color)>,
+    outline: Option<Color>,
     show_bounding_boxes: bool,
 }
 
+/// A single glyph quad to submit to the renderer
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct GlyphDraw {
+    texture: TextureID,
+    rect: Rect,
+    color: Color,
+}
+
+/// The order glyphs are laid out in when drawing text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextDirection {
+    LeftToRight,
+    /// Lays out the first character rightmost, within the same bounds as
+    /// left-to-right text
+    RightToLeft,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct FontID(u32);
+pub struct FontID(pub(crate) u32);
+
+#[derive(Debug)]
+pub enum LoadError {
+    FreetypeError {
+        path: PathBuf,
+        error: freetype::Error,
+    },
+}
+
+/// Vertical font metrics in pixels, for aligning text runs on a shared baseline
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FontMetrics {
+    /// Distance from the baseline to the top of the tallest glyphs
+    pub ascent: u32,
+    /// Distance from the baseline to the bottom of the lowest glyphs
+    pub descent: u32,
+    /// Distance between consecutive baselines, without any line gap
+    pub line_height: u32,
+}
+
+/// Metrics of a single rasterized glyph, e.g. for laying out text in tools
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GlyphInfo {
+    pub width: u32,
+    pub height: u32,
+    /// Offset from the pen position to the left edge of the glyph
+    pub bearing_x: i32,
+    /// Offset from the baseline to the top edge of the glyph
+    pub bearing_y: i32,
+    /// Horizontal pen advance in 26.6 fixed point pixels
+    pub advance: u32,
+}
+
+struct FontData {
+    glyphs: HashMap<char, GlyphData>,
+    /// The pixel size the glyphs were rasterized at
+    pixel_size: u32,
+    metrics: FontMetrics,
+    /// Whether the font has a kerning table, if not `kerning` is empty
+    has_kerning: bool,
+    /// Horizontal kerning in 26.6 fixed point pixels, for glyph pairs with
+    /// non-zero kerning
+    kerning: HashMap<(char, char), i32>,
+}
 
 #[derive(Debug, Clone, Copy)]
 struct GlyphData {
@@ -36,7 +111,10 @@ impl TextSystem {
             fonts: HashMap::new(),
             next_id: 0,
             scaling: 1.0,
-            color: (255, 255, 255, 255),
+            color: Color::WHITE,
+            tab_width: DEFAULT_TAB_WIDTH,
+            shadow: None,
+            outline: None,
             show_bounding_boxes: false,
         }
     }
@@ -47,14 +125,120 @@ impl TextSystem {
         renderer: &mut Renderer,
         path: &Path,
         font_size: u32,
-    ) -> FontID {
-        let face = self.library.new_face(path, 0).unwrap();
-        face.set_pixel_sizes(0, font_size).unwrap();
-        let mut glyphs = HashMap::new();
-        let printable_ascii = b' '..=b'~';
-        for character in printable_ascii {
+    ) -> Result<FontID, LoadError> {
+        let font_data =
+            self.rasterize_font(path, font_size, |_, texture_data, width, height| {
+                renderer.add_texture(gl, texture_data, width, height)
+            })?;
+
+        let id = self.generate_id();
+        self.fonts.insert(id, font_data);
+
+        Ok(id)
+    }
+
+    /// The pixel size `font` was rasterized at. Text drawn with a scaling
+    /// other than 1.0 is resampled from this size and loses crispness, so
+    /// load the font again at the wanted size for large text instead.
+    pub fn font_pixel_size(&self, font: FontID) -> u32 {
+        self.fonts[&font].pixel_size
+    }
+
+    /// The vertical metrics of `font` with the current scaling applied
+    pub fn font_metrics(&self, font: FontID) -> FontMetrics {
+        let metrics = self.fonts[&font].metrics;
+        let scale = |value: u32| f32::round(value as f32 * self.scaling) as u32;
+        let (ascent, descent) = (scale(metrics.ascent), scale(metrics.descent));
+        FontMetrics {
+            ascent,
+            descent,
+            line_height: ascent + descent,
+        }
+    }
+
+    /// The rasterized glyphs of `font` ordered by character, unaffected by
+    /// the current scaling
+    pub fn glyphs(&self, font: FontID) -> impl Iterator<Item = (char, GlyphInfo)> + '_ {
+        self.fonts[&font]
+            .glyphs
+            .iter()
+            .map(|(character, glyph)| {
+                let info = GlyphInfo {
+                    width: glyph.width,
+                    height: glyph.height,
+                    bearing_x: glyph.bearing_x,
+                    bearing_y: glyph.bearing_y,
+                    advance: glyph.advance,
+                };
+                (*character, info)
+            })
+            .sorted_by_key(|(character, _)| *character)
+    }
+
+    /// Whether `font` adjusts the spacing of glyph pairs using kerning
+    pub fn has_kerning(&self, font: FontID) -> bool {
+        self.fonts[&font].has_kerning
+    }
+
+    /// Rasterize the font again into an existing ID, e.g. when the font file
+    /// has changed. The glyph textures are reused. If loading fails, the
+    /// current glyphs are kept.
+    pub fn reload_font(
+        &mut self,
+        id: FontID,
+        renderer: &mut Renderer,
+        path: &Path,
+        font_size: u32,
+    ) -> Result<(), LoadError> {
+        self.reload_font_textures(
+            id,
+            path,
+            font_size,
+            |texture_id, texture_data, width, height| {
+                renderer.reload_texture(texture_id, texture_data, width, height)
+            },
+        )
+    }
+
+    /// Reloads `id`, passing the new RGBA data of each glyph to
+    /// `reload_texture` along with the texture the glyph already uses
+    fn reload_font_textures(
+        &mut self,
+        id: FontID,
+        path: &Path,
+        font_size: u32,
+        mut reload_texture: impl FnMut(TextureID, &[u8], u32, u32),
+    ) -> Result<(), LoadError> {
+        log::info!("Reloading font from \"{}\"", path.display());
+        let old_glyphs = &self.fonts[&id].glyphs;
+        let font_data =
+            self.rasterize_font(path, font_size, |character, texture_data, width, height| {
+                let texture_id = old_glyphs[&character].texture;
+                reload_texture(texture_id, texture_data, width, height);
+                texture_id
+            })?;
+        self.fonts.insert(id, font_data);
+
+        Ok(())
+    }
+
+    /// Renders the glyphs of a font, passing the RGBA data of each glyph to
+    /// `upload_glyph` which returns the texture to draw the glyph with.
+    /// Nothing is uploaded unless every glyph could be rendered.
+    fn rasterize_font(
+        &self,
+        path: &Path,
+        font_size: u32,
+        mut upload_glyph: impl FnMut(char, &[u8], u32, u32) -> TextureID,
+    ) -> Result<FontData, LoadError> {
+        let face = self.load_face(path, font_size)?;
+        let mut rendered_glyphs = Vec::new();
+        for character in PRINTABLE_ASCII {
             face.load_char(character as usize, LoadFlag::RENDER)
-                .unwrap();
+                .map_err(|error| LoadError::FreetypeError {
+                    path: path.to_owned(),
+                    error,
+                })?;
             let glyph = face.glyph();
             let bitmap = glyph.bitmap();
             let bitmap_width = bitmap.width() as u32;
@@ -68,34 +252,76 @@ impl TextSystem {
                 }
             }
 
-            let texture_id = renderer.add_texture(gl, &texture_data, bitmap_width, bitmap_height);
-            glyphs.insert(
-                character as char,
-                GlyphData {
-                    texture: texture_id,
-                    width: bitmap_width,
-                    height: bitmap_height,
-                    bearing_x: glyph.bitmap_left(),
-                    bearing_y: glyph.bitmap_top(),
-                    advance: glyph.advance().x as u32,
-                },
-            );
+            let info = GlyphInfo {
+                width: bitmap_width,
+                height: bitmap_height,
+                bearing_x: glyph.bitmap_left(),
+                bearing_y: glyph.bitmap_top(),
+                advance: glyph.advance().x as u32,
+            };
+            rendered_glyphs.push((character as char, texture_data, info));
         }
 
-        let id = self.generate_id();
-        self.fonts.insert(id, glyphs);
+        let has_kerning = face.has_kerning();
+        let kerning = if has_kerning {
+            kerning_pairs(&face).map_err(|error| LoadError::FreetypeError {
+                path: path.to_owned(),
+                error,
+            })?
+        } else {
+            HashMap::new()
+        };
+
+        let glyphs = rendered_glyphs
+            .into_iter()
+            .map(|(character, texture_data, info)| {
+                let glyph = GlyphData {
+                    texture: upload_glyph(character, &texture_data, info.width, info.height),
+                    width: info.width,
+                    height: info.height,
+                    bearing_x: info.bearing_x,
+                    bearing_y: info.bearing_y,
+                    advance: info.advance,
+                };
+                (character, glyph)
+            })
+            .collect();
 
-        id
+        Ok(FontData {
+            glyphs,
+            pixel_size: font_size,
+            metrics: face_metrics(&face),
+            has_kerning,
+            kerning,
+        })
     }
 
+    fn load_face(&self, path: &Path, font_size: u32) -> Result<freetype::Face, LoadError> {
+        let to_load_error = |error| LoadError::FreetypeError {
+            path: path.to_owned(),
+            error,
+        };
+        let face = self
+            .library
+            .new_face(resolve_resource_path(path), 0)
+            .map_err(to_load_error)?;
+        face.set_pixel_sizes(0, font_size).map_err(to_load_error)?;
+        Ok(face)
+    }
+
+    /// Scale subsequently drawn and measured text, see `font_pixel_size`
     #[allow(dead_code)]
     pub fn set_scaling(&mut self, scaling: f32) {
         self.scaling = scaling;
     }
 
     #[allow(dead_code)]
-    pub fn set_text_color(&mut self, r: u8, g: u8, b: u8, a: u8) {
-        self.color = (r, g, b, a);
+    pub fn set_text_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    pub fn text_color(&self) -> Color {
+        self.color
     }
 
     #[allow(dead_code)]
@@ -108,59 +334,182 @@ impl TextSystem {
         self.show_bounding_boxes = false;
     }
 
-    pub fn draw_text(
+    pub fn draw_text(&mut self, renderer: &mut Renderer, font: FontID, x: i32, y: i32, text: &str) {
+        self.draw_text_with_direction(renderer, font, x, y, text, TextDirection::LeftToRight);
+    }
+
+    /// Draw text laid out in `direction`, with `x` the left edge of the text
+    /// in both directions
+    pub fn draw_text_with_direction(
         &mut self,
         renderer: &mut Renderer,
         font: FontID,
-        mut x: i32,
+        x: i32,
         mut y: i32,
         text: &str,
+        direction: TextDirection,
     ) {
-        let glyphs = &self.fonts[&font];
+        let font_data = &self.fonts[&font];
         let (_, y_max) = self.text_dimensions(font, text);
         y += y_max as i32; // offset the y position by the tallet glyph
-        for character in text.chars() {
-            assert!(
-                character.is_ascii(),
-                "non-ascii characters are not yet supported"
-            );
-            let glyph = glyphs[&character];
-            let glyph_x = x + f32::round(glyph.bearing_x as f32 * self.scaling) as i32;
-            let glyph_y = y - f32::round(glyph.bearing_y as f32 * self.scaling) as i32;
-            let glyph_rect = Rect {
-                x: glyph_x,
-                y: glyph_y,
-                w: f32::round(glyph.width as f32 * self.scaling) as u32,
-                h: f32::round(glyph.height as f32 * self.scaling) as u32,
-            };
-            let (r, g, b, a) = self.color;
-            renderer.set_texture_blend_color(r, g, b, a);
-            renderer.draw_texture(glyph.texture, glyph_rect, None);
+        let glyph_rects = self.glyph_rects(font_data, x, y, text, direction);
+        for glyph_draw in self.glyph_draws(&glyph_rects) {
+            renderer.set_texture_blend_color(glyph_draw.color);
+            renderer.draw_texture(glyph_draw.texture, glyph_draw.rect, None);
+        }
 
-            if self.show_bounding_boxes {
-                renderer.set_draw_color(0, 255, 0, 255);
+        if self.show_bounding_boxes {
+            renderer.set_draw_color(Color::GREEN);
+            for (_, glyph_rect) in glyph_rects {
                 renderer.draw_rect(glyph_rect);
             }
+        }
+    }
+
+    /// Draw a copy of subsequent text offset by `offset` in a shadow color
+    /// behind it, or no shadow if `None`
+    pub fn set_text_shadow(&mut self, shadow: Option<(Point, Color)>) {
+        self.shadow = shadow;
+    }
+
+    /// Draw a 1px outline in the given color around subsequent text, or no
+    /// outline if `None`
+    pub fn set_text_outline(&mut self, outline: Option<Color>) {
+        self.outline = outline;
+    }
+
+    /// The texture and screen rect of each glyph in `text`, with `y` the baseline
+    fn glyph_rects(
+        &self,
+        font_data: &FontData,
+        x: i32,
+        y: i32,
+        text: &str,
+        direction: TextDirection,
+    ) -> Vec<(TextureID, Rect)> {
+        self.layout_text(font_data, text, direction)
+            .into_iter()
+            .map(|(character, glyph_offset)| {
+                let glyph = font_data.glyphs[&character];
+                let glyph_x =
+                    x + glyph_offset + f32::round(glyph.bearing_x as f32 * self.scaling) as i32;
+                let glyph_y = y - f32::round(glyph.bearing_y as f32 * self.scaling) as i32;
+                let glyph_rect = Rect {
+                    x: glyph_x,
+                    y: glyph_y,
+                    w: f32::round(glyph.width as f32 * self.scaling) as u32,
+                    h: f32::round(glyph.height as f32 * self.scaling) as u32,
+                };
+                (glyph.texture, glyph_rect)
+            })
+            .collect()
+    }
 
-            x += f32::round(glyph.advance as f32 / 64.0 * self.scaling) as i32;
+    /// The glyph quads to draw, back to front: shadow, outline, then the text
+    fn glyph_draws(&self, glyph_rects: &[(TextureID, Rect)]) -> Vec<GlyphDraw> {
+        let mut passes = Vec::new();
+        if let Some((offset, color)) = self.shadow {
+            passes.push((offset, color));
+        }
+        if let Some(color) = self.outline {
+            passes.extend(OUTLINE_OFFSETS.iter().map(|offset| (*offset, color)));
         }
+        passes.push((point(0, 0), self.color));
+
+        passes
+            .into_iter()
+            .flat_map(|(offset, color)| {
+                glyph_rects.iter().map(move |(texture, rect)| GlyphDraw {
+                    texture: *texture,
+                    rect: Rect {
+                        x: rect.x + offset.x,
+                        y: rect.y + offset.y,
+                        ..*rect
+                    },
+                    color,
+                })
+            })
+            .collect()
     }
 
     pub fn text_dimensions(&self, font: FontID, text: &str) -> (u32, u32) {
-        let glyphs = &self.fonts[&font];
+        let font_data = &self.fonts[&font];
 
-        let mut width = 0;
-        let mut height = 0;
-        for character in text.chars() {
-            let glyph = glyphs[&character];
-            let scaled_height = f32::round(glyph.height as f32 * self.scaling) as u32;
-            let scaled_advance = f32::round(glyph.advance as f32 / 64.0 * self.scaling) as u32;
+        let (_, width) = self.layout_line(font_data, text);
+        let height = text
+            .chars()
+            .filter_map(|character| font_data.glyphs.get(&character))
+            .map(|glyph| f32::round(glyph.height as f32 * self.scaling) as u32)
+            .max()
+            .unwrap_or(0);
+
+        (width.max(0) as u32, height)
+    }
 
-            width += scaled_advance;
-            height = u32::max(height, scaled_height);
+    /// Set the distance in pixels between the tab stops that `\t` advances to
+    pub fn set_tab_width(&mut self, tab_width: u32) {
+        self.tab_width = tab_width;
+    }
+
+    /// The glyphs of `text` paired with their x offset from the left edge of
+    /// the text
+    fn layout_text(
+        &self,
+        font_data: &FontData,
+        text: &str,
+        direction: TextDirection,
+    ) -> Vec<(char, i32)> {
+        let (glyphs, width) = self.layout_line(font_data, text);
+        match direction {
+            TextDirection::LeftToRight => glyphs,
+            TextDirection::RightToLeft => glyphs
+                .into_iter()
+                .map(|(character, offset)| {
+                    (
+                        character,
+                        width - offset - self.scaled_advance(font_data, character),
+                    )
+                })
+                .collect(),
         }
+    }
 
-        (width, height)
+    /// Lays out `text` left-to-right, returning the glyph offsets and the
+    /// total width. Tabs advance to the next tab stop and produce no glyph.
+    fn layout_line(&self, font_data: &FontData, text: &str) -> (Vec<(char, i32)>, i32) {
+        let mut glyphs = Vec::new();
+        let mut pen_x = 0;
+        let mut previous = None;
+        for character in text.chars() {
+            assert!(
+                character.is_ascii(),
+                "non-ascii characters are not yet supported"
+            );
+            if character == '\t' {
+                pen_x = next_tab_stop(pen_x, self.tab_width);
+                previous = None;
+                continue;
+            }
+            pen_x += self.scaled_kerning(font_data, previous, character);
+            glyphs.push((character, pen_x));
+            pen_x += self.scaled_advance(font_data, character);
+            previous = Some(character);
+        }
+        (glyphs, pen_x)
+    }
+
+    fn scaled_advance(&self, font_data: &FontData, character: char) -> i32 {
+        let glyph = font_data.glyphs[&character];
+        f32::round(glyph.advance as f32 / 64.0 * self.scaling) as i32
+    }
+
+    /// The pixel offset to apply between `previous` and `character`
+    fn scaled_kerning(&self, font_data: &FontData, previous: Option<char>, character: char) -> i32 {
+        let kerning = previous
+            .and_then(|previous| font_data.kerning.get(&(previous, character)))
+            .copied()
+            .unwrap_or(0);
+        f32::round(kerning as f32 / 64.0 * self.scaling) as i32
     }
 
     fn generate_id(&mut self) -> FontID {
@@ -169,3 +518,381 @@ impl TextSystem {
         FontID(id)
     }
 }
+
+const DEFAULT_TAB_WIDTH: u32 = 32;
+
+/// Offsets of the copies drawn to form a 1px outline
+const OUTLINE_OFFSETS: [Point; 8] = [
+    point(-1, -1),
+    point(0, -1),
+    point(1, -1),
+    point(-1, 0),
+    point(1, 0),
+    point(-1, 1),
+    point(0, 1),
+    point(1, 1),
+];
+
+/// The first tab stop strictly after `x`
+fn next_tab_stop(x: i32, tab_width: u32) -> i32 {
+    if tab_width == 0 {
+        return x;
+    }
+    let tab_width = tab_width as i32;
+    (x.div_euclid(tab_width) + 1) * tab_width
+}
+
+const PRINTABLE_ASCII: std::ops::RangeInclusive<u8> = b' '..=b'~';
+
+fn face_metrics(face: &freetype::Face) -> FontMetrics {
+    let size_metrics = match face.size_metrics() {
+        Some(size_metrics) => size_metrics,
+        None => return FontMetrics::default(),
+    };
+    // metrics are in 26.6 fixed point, with the descender below the baseline negative
+    let ascent = (size_metrics.ascender as f32 / 64.0).round() as u32;
+    let descent = (-size_metrics.descender as f32 / 64.0).round() as u32;
+    FontMetrics {
+        ascent,
+        descent,
+        line_height: ascent + descent,
+    }
+}
+
+/// Looks up the horizontal kerning of all printable ASCII glyph pairs,
+/// keeping the non-zero ones
+fn kerning_pairs(face: &freetype::Face) -> Result<HashMap<(char, char), i32>, freetype::Error> {
+    let mut kerning = HashMap::new();
+    for left in PRINTABLE_ASCII {
+        let left_index = face.get_char_index(left as usize);
+        for right in PRINTABLE_ASCII {
+            let right_index = face.get_char_index(right as usize);
+            let offset = face.get_kerning(left_index, right_index, KerningMode::KerningDefault)?;
+            if offset.x != 0 {
+                kerning.insert((left as char, right as char), offset.x as i32);
+            }
+        }
+    }
+    Ok(kerning)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loading_missing_font_returns_error_with_path() {
+        let text_system = TextSystem::new();
+        let path = Path::new("does/not/exist.ttf");
+
+        let result = text_system.load_face(path, 16);
+
+        assert!(
+            matches!(result, Err(LoadError::FreetypeError { path: error_path, .. }) if error_path == path)
+        );
+    }
+
+    fn arial_path() -> &'static Path {
+        Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../resources/font/arial.ttf"
+        ))
+    }
+
+    #[test]
+    fn line_height_is_ascent_plus_descent() {
+        let text_system = TextSystem::new();
+        let face = text_system.load_face(arial_path(), 16).unwrap();
+
+        let metrics = face_metrics(&face);
+
+        assert!(metrics.ascent > 0);
+        assert!(metrics.descent > 0);
+        assert_eq!(metrics.line_height, metrics.ascent + metrics.descent);
+    }
+
+    #[test]
+    fn font_metrics_are_scaled() {
+        let (mut text_system, font) = text_system_with_font(&[]);
+
+        text_system.set_scaling(2.0);
+        let metrics = text_system.font_metrics(font);
+
+        assert_eq!(
+            metrics,
+            FontMetrics {
+                ascent: 24,
+                descent: 6,
+                line_height: 30
+            }
+        );
+    }
+
+    #[test]
+    fn kerned_pair_is_narrower_than_advance_sum() {
+        let text_system = TextSystem::new();
+        let face = text_system.load_face(arial_path(), 16).unwrap();
+        let advance = |character: char| {
+            face.load_char(character as usize, LoadFlag::DEFAULT)
+                .unwrap();
+            face.glyph().advance().x as i32
+        };
+
+        let kerning = kerning_pairs(&face).unwrap();
+        let naive_width = advance('A') + advance('V');
+        let kerned_width = naive_width + kerning[&('A', 'V')];
+
+        assert!(face.has_kerning());
+        assert!(kerned_width < naive_width);
+    }
+
+    fn text_system_with_font(glyphs: &[(char, GlyphData)]) -> (TextSystem, FontID) {
+        let mut text_system = TextSystem::new();
+        let id = text_system.generate_id();
+        text_system.fonts.insert(
+            id,
+            FontData {
+                glyphs: glyphs.iter().copied().collect(),
+                pixel_size: 16,
+                metrics: FontMetrics {
+                    ascent: 12,
+                    descent: 3,
+                    line_height: 15,
+                },
+                has_kerning: false,
+                kerning: HashMap::new(),
+            },
+        );
+        (text_system, id)
+    }
+
+    fn glyph(width: u32, height: u32, advance_px: u32) -> GlyphData {
+        GlyphData {
+            texture: TextureID(0),
+            width,
+            height,
+            bearing_x: 0,
+            bearing_y: height as i32,
+            advance: advance_px * 64,
+        }
+    }
+
+    #[parameterized(scaling = { 1.0, 2.0, 3.0, 0.5 })]
+    fn text_dimensions_scale_linearly_with_scaling(scaling: f32) {
+        let (mut text_system, font) =
+            text_system_with_font(&[('a', glyph(6, 8, 8)), ('b', glyph(6, 12, 10))]);
+        let (width, height) = text_system.text_dimensions(font, "ab");
+
+        text_system.set_scaling(scaling);
+        let (scaled_width, scaled_height) = text_system.text_dimensions(font, "ab");
+
+        assert_eq!(scaled_width, (width as f32 * scaling) as u32);
+        assert_eq!(scaled_height, (height as f32 * scaling) as u32);
+    }
+
+    #[test]
+    fn tab_advances_to_next_tab_stop() {
+        let (mut text_system, font) = text_system_with_font(&[('a', glyph(6, 8, 8))]);
+        text_system.set_tab_width(32);
+
+        let font_data = &text_system.fonts[&font];
+        let glyphs = text_system.layout_text(font_data, "a\ta", TextDirection::LeftToRight);
+
+        assert_eq!(glyphs, vec![('a', 0), ('a', 32)]);
+        assert_eq!(text_system.text_dimensions(font, "a\ta").0, 40);
+    }
+
+    #[parameterized(x = { 0, 1, 31, 32, 33 }, expected = { 32, 32, 32, 64, 64 })]
+    fn next_tab_stop_is_after_x(x: i32, expected: i32) {
+        assert_eq!(next_tab_stop(x, 32), expected);
+    }
+
+    #[test]
+    fn right_to_left_layout_mirrors_left_to_right_layout() {
+        let (text_system, font) =
+            text_system_with_font(&[('a', glyph(6, 8, 8)), ('b', glyph(6, 12, 10))]);
+        let font_data = &text_system.fonts[&font];
+
+        let left_to_right = text_system.layout_text(font_data, "ab", TextDirection::LeftToRight);
+        let right_to_left = text_system.layout_text(font_data, "ab", TextDirection::RightToLeft);
+
+        assert_eq!(left_to_right, vec![('a', 0), ('b', 8)]);
+        assert_eq!(right_to_left, vec![('a', 10), ('b', 0)]);
+    }
+
+    fn text_glyph_draws(text_system: &TextSystem, font: FontID, text: &str) -> Vec<GlyphDraw> {
+        let font_data = &text_system.fonts[&font];
+        let glyph_rects =
+            text_system.glyph_rects(font_data, 0, 0, text, TextDirection::LeftToRight);
+        text_system.glyph_draws(&glyph_rects)
+    }
+
+    #[test]
+    fn text_shadow_doubles_glyph_draws() {
+        let (mut text_system, font) = text_system_with_font(&[('a', glyph(6, 8, 8))]);
+        let draws_without_shadow = text_glyph_draws(&text_system, font, "aaa").len();
+
+        text_system.set_text_shadow(Some((point(1, 1), Color::BLACK)));
+        let draws_with_shadow = text_glyph_draws(&text_system, font, "aaa").len();
+
+        assert_eq!(draws_without_shadow, 3);
+        assert_eq!(draws_with_shadow, 2 * draws_without_shadow);
+    }
+
+    #[test]
+    fn text_shadow_is_drawn_offset_behind_text() {
+        let (mut text_system, font) = text_system_with_font(&[('a', glyph(6, 8, 8))]);
+        text_system.set_text_color(Color::WHITE);
+        text_system.set_text_shadow(Some((point(2, 1), Color::BLACK)));
+
+        let draws = text_glyph_draws(&text_system, font, "a");
+
+        assert_eq!(draws[0].color, Color::BLACK);
+        assert_eq!((draws[0].rect.x, draws[0].rect.y), (2, -7));
+        assert_eq!(draws[1].color, Color::WHITE);
+        assert_eq!((draws[1].rect.x, draws[1].rect.y), (0, -8));
+    }
+
+    #[test]
+    fn text_outline_draws_eight_copies_around_text() {
+        let (mut text_system, font) = text_system_with_font(&[('a', glyph(6, 8, 8))]);
+
+        text_system.set_text_outline(Some(Color::BLACK));
+        let draws = text_glyph_draws(&text_system, font, "a");
+
+        assert_eq!(draws.len(), 9);
+        assert!(draws[..8].iter().all(|draw| draw.color == Color::BLACK));
+    }
+
+    fn text_system_with_printable_ascii() -> (TextSystem, FontID) {
+        let glyphs = PRINTABLE_ASCII
+            .map(|character| {
+                let glyph = GlyphData {
+                    texture: TextureID(character as u32),
+                    ..glyph(6, 8, 8)
+                };
+                (character as char, glyph)
+            })
+            .collect::<Vec<_>>();
+        text_system_with_font(&glyphs)
+    }
+
+    #[test]
+    fn reloaded_font_reuses_glyph_textures() {
+        let (mut text_system, font) = text_system_with_printable_ascii();
+        let mut reloaded_textures = Vec::new();
+
+        text_system
+            .reload_font_textures(font, arial_path(), 24, |texture_id, _, _, _| {
+                reloaded_textures.push(texture_id)
+            })
+            .unwrap();
+
+        assert_eq!(text_system.font_pixel_size(font), 24);
+        assert_eq!(
+            reloaded_textures.into_iter().sorted().collect::<Vec<_>>(),
+            PRINTABLE_ASCII
+                .map(|character| TextureID(character as u32))
+                .collect::<Vec<_>>()
+        );
+        assert_ne!(text_system.text_dimensions(font, "a").0, 8);
+    }
+
+    #[test]
+    fn failed_font_reload_keeps_glyphs() {
+        let (mut text_system, font) = text_system_with_printable_ascii();
+        let mut reloaded_textures = Vec::new();
+
+        let result = text_system.reload_font_textures(
+            font,
+            Path::new("does/not/exist.ttf"),
+            24,
+            |texture_id, _, _, _| reloaded_textures.push(texture_id),
+        );
+
+        assert!(result.is_err());
+        assert!(reloaded_textures.is_empty());
+        assert_eq!(text_system.font_pixel_size(font), 16);
+        assert_eq!(text_system.text_dimensions(font, "a").0, 8);
+    }
+
+    #[test]
+    fn loaded_font_has_glyphs_for_printable_ascii() {
+        let (mut text_system, font) = text_system_with_font(&[]);
+        let font_data = text_system
+            .rasterize_font(arial_path(), 16, |_, _, _, _| TextureID(0))
+            .unwrap();
+        text_system.fonts.insert(font, font_data);
+
+        let characters = text_system
+            .glyphs(font)
+            .map(|(character, _)| character)
+            .collect::<Vec<char>>();
+
+        assert_eq!(
+            characters,
+            PRINTABLE_ASCII.map(char::from).collect::<Vec<char>>()
+        );
+    }
+
+    #[test]
+    fn glyph_info_mirrors_glyph_data() {
+        let (text_system, font) = text_system_with_font(&[('a', glyph(6, 8, 7))]);
+
+        let glyphs = text_system.glyphs(font).collect::<Vec<_>>();
+
+        assert_eq!(
+            glyphs,
+            vec![(
+                'a',
+                GlyphInfo {
+                    width: 6,
+                    height: 8,
+                    bearing_x: 0,
+                    bearing_y: 8,
+                    advance: 7 * 64,
+                }
+            )]
+        );
+    }
+}
+
+#[cfg(all(test, feature = "gl-tests"))]
+mod gl_tests {
+    use super::*;
+    use crate::graphics::rendering::gl_tests::init_test_gl_context;
+
+    fn arial_path() -> &'static Path {
+        Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../resources/font/arial.ttf"
+        ))
+    }
+
+    fn glyph_textures(text_system: &TextSystem, font: FontID) -> Vec<TextureID> {
+        text_system.fonts[&font]
+            .glyphs
+            .iter()
+            .sorted_by_key(|(character, _)| **character)
+            .map(|(_, glyph)| glyph.texture)
+            .collect()
+    }
+
+    #[test]
+    fn reloaded_font_keeps_id_and_glyph_textures() {
+        let context = init_test_gl_context();
+        let mut renderer = Renderer::new(&context.gl_context, 100, 100).unwrap();
+        let mut text_system = TextSystem::new();
+        let font = text_system
+            .load_font(&context.gl_context, &mut renderer, arial_path(), 16)
+            .unwrap();
+        let textures_before = glyph_textures(&text_system, font);
+
+        text_system
+            .reload_font(font, &mut renderer, arial_path(), 24)
+            .unwrap();
+
+        assert_eq!(text_system.font_pixel_size(font), 24);
+        assert_eq!(glyph_textures(&text_system, font), textures_before);
+    }
+}
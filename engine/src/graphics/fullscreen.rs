@@ -1,20 +1,37 @@
+use std::{fmt, str::FromStr};
+
 use sdl2::{
     sys::SDL_WindowFlags,
-    video::{Window, WindowPos},
+    video::{FullscreenType, Window, WindowPos},
     VideoSubsystem,
 };
 
-use crate::geometry::Rect;
+use crate::{geometry::Rect, graphics::rendering::Renderer};
 
 pub struct FullscreenSystem {
     last_windowed_pos: (i32, i32),
     last_windowed_size: (u32, u32),
+    fullscreen_mode: WindowMode,
+}
+
+/// Notified of the new window size after the window mode changes
+pub trait WindowResizeListener {
+    fn on_window_resize(&mut self, width: u32, height: u32);
 }
 
-#[derive(PartialEq)]
-enum WindowMode {
+impl WindowResizeListener for Renderer {
+    fn on_window_resize(&mut self, width: u32, height: u32) {
+        Renderer::on_window_resize(self, width, height);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowMode {
     Windowed,
-    Fullscreen,
+    /// Borderless window covering the whole display
+    BorderlessFullscreen,
+    /// Real fullscreen, changing the display mode
+    ExclusiveFullscreen,
 }
 
 impl FullscreenSystem {
@@ -22,6 +39,7 @@ impl FullscreenSystem {
         FullscreenSystem {
             last_windowed_pos: ((screen_width / 2) as i32, (screen_height / 2) as i32),
             last_windowed_size: (0, 0),
+            fullscreen_mode: WindowMode::BorderlessFullscreen,
         }
     }
 
@@ -32,27 +50,118 @@ impl FullscreenSystem {
         }
     }
 
-    pub fn toggle_fullscreen(&self, window: &mut Window, sdl_video: &VideoSubsystem) {
-        match window_mode(window) {
-            WindowMode::Windowed => {
-                change_to_fullscreen_mode(window, sdl_video);
+    /// The mode used when toggling fullscreen
+    pub fn fullscreen_mode(&self) -> WindowMode {
+        self.fullscreen_mode
+    }
+
+    /// Set the mode used when toggling fullscreen
+    pub fn set_fullscreen_mode(&mut self, mode: WindowMode) {
+        self.fullscreen_mode = mode;
+    }
+
+    /// Toggles between windowed mode and the configured fullscreen mode.
+    /// Returns whether the window mode changed.
+    pub fn toggle_fullscreen(&self, window: &mut Window, sdl_video: &VideoSubsystem) -> bool {
+        let mode = toggled_window_mode(window_mode(window), self.fullscreen_mode);
+        self.set_window_mode(window, sdl_video, mode)
+    }
+
+    /// Returns whether the window mode changed
+    pub fn set_window_mode(
+        &self,
+        window: &mut Window,
+        sdl_video: &VideoSubsystem,
+        mode: WindowMode,
+    ) -> bool {
+        let current_mode = window_mode(window);
+        if current_mode == mode {
+            return false;
+        }
+
+        if current_mode != WindowMode::Windowed {
+            change_to_windowed_mode(window, self.last_windowed_pos, self.last_windowed_size);
+        }
+
+        match mode {
+            WindowMode::Windowed => {}
+            WindowMode::BorderlessFullscreen => {
+                change_to_borderless_fullscreen_mode(window, sdl_video);
             }
-            WindowMode::Fullscreen => {
-                change_to_windowed_mode(window, self.last_windowed_pos, self.last_windowed_size);
+            WindowMode::ExclusiveFullscreen => {
+                change_to_exclusive_fullscreen_mode(window, sdl_video);
             }
         }
+        true
+    }
+}
+
+/// The mode to switch to when toggling fullscreen from `current_mode`
+pub fn toggled_window_mode(current_mode: WindowMode, fullscreen_mode: WindowMode) -> WindowMode {
+    match current_mode {
+        WindowMode::Windowed => fullscreen_mode,
+        WindowMode::BorderlessFullscreen | WindowMode::ExclusiveFullscreen => WindowMode::Windowed,
+    }
+}
+
+/// Resizes `listener` to the window size if the window mode changed
+pub fn resize_on_window_mode_change(
+    mode_changed: bool,
+    window_size: (u32, u32),
+    listener: &mut impl WindowResizeListener,
+) {
+    if mode_changed {
+        listener.on_window_resize(window_size.0, window_size.1);
+    }
+}
+
+impl fmt::Display for WindowMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let string = match self {
+            WindowMode::Windowed => "Windowed",
+            WindowMode::BorderlessFullscreen => "BorderlessFullscreen",
+            WindowMode::ExclusiveFullscreen => "ExclusiveFullscreen",
+        };
+        write!(f, "{}", string)
+    }
+}
+
+impl FromStr for WindowMode {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string {
+            "Windowed" => Ok(WindowMode::Windowed),
+            "BorderlessFullscreen" => Ok(WindowMode::BorderlessFullscreen),
+            "ExclusiveFullscreen" => Ok(WindowMode::ExclusiveFullscreen),
+            _ => Err(format!("invalid window mode \"{}\"", string)),
+        }
     }
 }
 
 fn window_mode(window: &Window) -> WindowMode {
-    if window.window_flags() & SDL_WindowFlags::SDL_WINDOW_BORDERLESS as u32 == 0 {
-        WindowMode::Windowed
+    window_mode_from_flags(window.window_flags())
+}
+
+fn window_mode_from_flags(flags: u32) -> WindowMode {
+    let fullscreen = SDL_WindowFlags::SDL_WINDOW_FULLSCREEN as u32;
+    let fullscreen_desktop = SDL_WindowFlags::SDL_WINDOW_FULLSCREEN_DESKTOP as u32;
+    let borderless = SDL_WindowFlags::SDL_WINDOW_BORDERLESS as u32;
+
+    // SDL_WINDOW_FULLSCREEN_DESKTOP contains the SDL_WINDOW_FULLSCREEN bit, so
+    // it needs to be checked first
+    if flags & fullscreen_desktop == fullscreen_desktop {
+        WindowMode::BorderlessFullscreen
+    } else if flags & fullscreen != 0 {
+        WindowMode::ExclusiveFullscreen
+    } else if flags & borderless != 0 {
+        WindowMode::BorderlessFullscreen
     } else {
-        WindowMode::Fullscreen
+        WindowMode::Windowed
     }
 }
 
-fn change_to_fullscreen_mode(window: &mut Window, sdl_video: &VideoSubsystem) {
+fn change_to_borderless_fullscreen_mode(window: &mut Window, sdl_video: &VideoSubsystem) {
     let Rect {
         x,
         y,
@@ -64,11 +173,22 @@ fn change_to_fullscreen_mode(window: &mut Window, sdl_video: &VideoSubsystem) {
     window.set_size(width, height).unwrap();
 }
 
+fn change_to_exclusive_fullscreen_mode(window: &mut Window, sdl_video: &VideoSubsystem) {
+    let Rect {
+        w: width,
+        h: height,
+        ..
+    } = screen_rect(window, sdl_video);
+    window.set_size(width, height).unwrap();
+    window.set_fullscreen(FullscreenType::True).unwrap();
+}
+
 fn change_to_windowed_mode(
     window: &mut Window,
     last_windowed_pos: (i32, i32),
     last_windowed_size: (u32, u32),
 ) {
+    window.set_fullscreen(FullscreenType::Off).unwrap();
     window.set_bordered(true);
     window.set_position(
         WindowPos::Positioned(last_windowed_pos.0),
@@ -79,21 +199,73 @@ fn change_to_windowed_mode(
         .unwrap();
 }
 
+/// The bounds of the display the window is on, in global screen coordinates
 fn screen_rect(window: &Window, sdl_video: &VideoSubsystem) -> Rect {
     let display_index = window.display_index().unwrap();
-    let display_mode = sdl_video.desktop_display_mode(display_index).unwrap();
-    let num_displays = sdl_video.num_video_displays().unwrap();
-    let mut display_bounds = Vec::new();
-    for i in 0..num_displays {
-        display_bounds.push(sdl_video.display_bounds(i).unwrap());
+    let bounds = sdl_video.display_bounds(display_index).unwrap();
+    Rect {
+        x: bounds.x(),
+        y: bounds.y(),
+        w: bounds.width(),
+        h: bounds.height(),
     }
-    let x: i32 = display_bounds.iter().map(|rect| rect.top_left().x).sum();
-    let y: i32 = display_bounds[display_index as usize].top();
+}
 
-    Rect {
-        x,
-        y,
-        w: display_mode.w as u32,
-        h: display_mode.h as u32,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHOWN: u32 = SDL_WindowFlags::SDL_WINDOW_SHOWN as u32;
+    const OPENGL: u32 = SDL_WindowFlags::SDL_WINDOW_OPENGL as u32;
+    const BORDERLESS: u32 = SDL_WindowFlags::SDL_WINDOW_BORDERLESS as u32;
+    const FULLSCREEN: u32 = SDL_WindowFlags::SDL_WINDOW_FULLSCREEN as u32;
+    const FULLSCREEN_DESKTOP: u32 = SDL_WindowFlags::SDL_WINDOW_FULLSCREEN_DESKTOP as u32;
+
+    #[test]
+    fn bordered_window_is_windowed() {
+        let mode = window_mode_from_flags(SHOWN | OPENGL);
+
+        assert_eq!(mode, WindowMode::Windowed);
+    }
+
+    #[test]
+    fn borderless_window_is_borderless_fullscreen() {
+        let mode = window_mode_from_flags(SHOWN | OPENGL | BORDERLESS);
+
+        assert_eq!(mode, WindowMode::BorderlessFullscreen);
+    }
+
+    #[test]
+    fn desktop_fullscreen_window_is_borderless_fullscreen() {
+        let mode = window_mode_from_flags(SHOWN | OPENGL | FULLSCREEN_DESKTOP);
+
+        assert_eq!(mode, WindowMode::BorderlessFullscreen);
+    }
+
+    #[test]
+    fn fullscreen_window_is_exclusive_fullscreen() {
+        let mode = window_mode_from_flags(SHOWN | OPENGL | FULLSCREEN);
+
+        assert_eq!(mode, WindowMode::ExclusiveFullscreen);
+    }
+
+    #[parameterized(fullscreen_mode = {
+        WindowMode::BorderlessFullscreen, WindowMode::ExclusiveFullscreen
+    })]
+    fn toggling_twice_returns_to_windowed_mode(fullscreen_mode: WindowMode) {
+        let toggled = toggled_window_mode(WindowMode::Windowed, fullscreen_mode);
+
+        assert_eq!(toggled, fullscreen_mode);
+        assert_eq!(
+            toggled_window_mode(toggled, fullscreen_mode),
+            WindowMode::Windowed
+        );
+    }
+
+    #[parameterized(mode = {
+        WindowMode::Windowed, WindowMode::BorderlessFullscreen, WindowMode::ExclusiveFullscreen
+    })]
+    fn window_mode_can_be_parsed_from_its_string(mode: WindowMode) {
+        assert_eq!(mode.to_string().parse::<WindowMode>(), Ok(mode));
     }
 }
@@ -1,8 +1,24 @@
-//! Implementation of the midpoint circle drawing algorithm
+//! Implementation of the midpoint circle and ellipse drawing algorithms
 //! https://en.wikipedia.org/wiki/Midpoint_circle_algorithm
 
+use std::collections::HashMap;
+
 use itertools::Itertools;
 
+/// Least recently used cache of circle points, keyed by radius
+#[derive(Debug)]
+pub struct CirclePointsCache {
+    capacity: usize,
+    entries: HashMap<u32, CacheEntry>,
+    access_count: u64,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    points: Vec<(i32, i32)>,
+    last_access: u64,
+}
+
 /// Computes all the points of a circle at the origin with a given radius
 pub fn circle_points(radius: u32) -> Vec<(i32, i32)> {
     let segment = circle_segment(radius);
@@ -14,6 +30,58 @@ pub fn circle_points(radius: u32) -> Vec<(i32, i32)> {
         .collect()
 }
 
+/// Computes all the points of an ellipse at the origin with the given
+/// horizontal and vertical radii
+pub fn ellipse_points(radius_x: u32, radius_y: u32) -> Vec<(i32, i32)> {
+    let flat_arc = ellipse_arc(radius_x, radius_y);
+    let steep_arc = ellipse_arc(radius_y, radius_x)
+        .into_iter()
+        .map(|(x, y)| (y, x));
+
+    flat_arc
+        .into_iter()
+        .chain(steep_arc)
+        .flat_map(project_point_four_ways)
+        .unique()
+        .collect()
+}
+
+impl CirclePointsCache {
+    pub fn new(capacity: usize) -> Self {
+        CirclePointsCache {
+            capacity,
+            entries: HashMap::new(),
+            access_count: 0,
+        }
+    }
+
+    /// The points of a circle with the given radius, only computed if not
+    /// already cached.
+    pub fn circle_points(&mut self, radius: u32) -> &[(i32, i32)] {
+        self.access_count += 1;
+        if !self.entries.contains_key(&radius) && self.entries.len() >= self.capacity {
+            self.evict_least_recently_used();
+        }
+        let entry = self.entries.entry(radius).or_insert_with(|| CacheEntry {
+            points: circle_points(radius),
+            last_access: 0,
+        });
+        entry.last_access = self.access_count;
+        &entry.points
+    }
+
+    fn evict_least_recently_used(&mut self) {
+        let least_recently_used = self
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_access)
+            .map(|(radius, _)| *radius);
+        if let Some(radius) = least_recently_used {
+            self.entries.remove(&radius);
+        }
+    }
+}
+
 /// Gives the circle segment that lies in the 90° to 45° 8-slice.
 fn circle_segment(radius: u32) -> Vec<(i32, i32)> {
     // add initial point at 90° degrees
@@ -41,6 +109,46 @@ fn circle_segment(radius: u32) -> Vec<(i32, i32)> {
     segment
 }
 
+/// Gives the part of the first quadrant of an ellipse that starts at 90° and
+/// ends where the slope of the ellipse becomes steeper than -1.
+fn ellipse_arc(radius_x: u32, radius_y: u32) -> Vec<(i32, i32)> {
+    let (rx2, ry2) = ((radius_x as i64).pow(2), (radius_y as i64).pow(2));
+
+    // a zero sized ellipse is a single point
+    if rx2 == 0 && ry2 == 0 {
+        return vec![(0, 0)];
+    }
+
+    // add initial point at 90° degrees
+    let mut arc = vec![(0, radius_y as i32)];
+
+    // add remaining points until the slope reaches -1
+    let (mut point_x, mut point_y) = arc[0];
+    loop {
+        // calculate next point, using doubled coordinates to stay in integers
+        let (mid_x, mid_y) = (2 * point_x as i64 + 2, 2 * point_y as i64 - 1);
+
+        if ry2 * mid_x.pow(2) + rx2 * mid_y.pow(2) > 4 * rx2 * ry2 {
+            point_y -= 1;
+        }
+        point_x += 1;
+
+        // check if slope is still at most -1
+        if ry2 * point_x as i64 > rx2 * point_y as i64 {
+            break;
+        }
+
+        // add point to arc
+        arc.push((point_x, point_y));
+    }
+    arc
+}
+
+/// Takes a point in the first quadrant and mirrors it to the other quadrants
+fn project_point_four_ways((x, y): (i32, i32)) -> [(i32, i32); 4] {
+    [(x, y), (x, -y), (-x, -y), (-x, y)]
+}
+
 /// Takes a point in the 90° to 45° slice and projects it to the other 8-slices
 fn project_point_eight_ways((x, y): (i32, i32)) -> [(i32, i32); 8] {
     assert!(
@@ -196,4 +304,126 @@ mod tests {
 
         assert_eq!(&actual_points, &expected_points);
     }
+
+    #[test]
+    fn cached_circle_points_match_computed_points() {
+        let mut cache = CirclePointsCache::new(4);
+
+        let cached_points = cache.circle_points(5).to_vec();
+
+        assert_eq!(cached_points, circle_points(5));
+    }
+
+    #[test]
+    fn repeated_radius_hits_cache() {
+        let mut cache = CirclePointsCache::new(4);
+
+        let first_points = cache.circle_points(5).as_ptr();
+        let second_points = cache.circle_points(5).as_ptr();
+
+        assert_eq!(first_points, second_points);
+        assert_eq!(cache.entries.len(), 1);
+    }
+
+    #[test]
+    fn least_recently_used_radius_is_evicted_when_full() {
+        let mut cache = CirclePointsCache::new(2);
+
+        cache.circle_points(1);
+        cache.circle_points(2);
+        cache.circle_points(1);
+        cache.circle_points(3);
+
+        assert_eq!(cache.entries.len(), 2);
+        assert!(cache.entries.contains_key(&1));
+        assert!(!cache.entries.contains_key(&2));
+        assert!(cache.entries.contains_key(&3));
+    }
+
+    #[test]
+    fn ellipse_arc_radius_4_by_2() {
+        //    ----
+        //    432101234
+        // -2 __█████__
+        // -1 _█_____█_
+        //  0 █_______█
+        //  1 _█_____█_
+        //  2 __█████__
+        let (radius_x, radius_y) = (4, 2);
+
+        let actual_arc = ellipse_arc(radius_x, radius_y);
+
+        //    ----
+        //    432101234
+        //  1 _______█_
+        //  2 ____███__
+        let expected_arc = [(0, 2), (1, 2), (2, 2), (3, 1)];
+        assert_eq!(&actual_arc, &expected_arc);
+    }
+
+    #[test]
+    fn ellipse_radius_3_by_1() {
+        //    ---
+        //    3210123
+        // -1 _█████_
+        //  0 █_____█
+        //  1 _█████_
+        let (radius_x, radius_y) = (3, 1);
+
+        let actual_points = ellipse_points(radius_x, radius_y)
+            .into_iter()
+            .sorted()
+            .collect::<Vec<(i32, i32)>>();
+
+        let expected_points = [
+            (-3, 0),
+            (-2, -1),
+            (-2, 1),
+            (-1, -1),
+            (-1, 1),
+            (0, -1),
+            (0, 1),
+            (1, -1),
+            (1, 1),
+            (2, -1),
+            (2, 1),
+            (3, 0),
+        ]
+        .into_iter()
+        .sorted()
+        .collect::<Vec<(i32, i32)>>();
+
+        assert_eq!(&actual_points, &expected_points);
+    }
+
+    #[test]
+    fn ellipse_with_equal_radii_is_circle() {
+        for radius in 1..=8 {
+            let ellipse = ellipse_points(radius, radius)
+                .into_iter()
+                .sorted()
+                .collect::<Vec<(i32, i32)>>();
+            let circle = circle_points(radius)
+                .into_iter()
+                .sorted()
+                .collect::<Vec<(i32, i32)>>();
+
+            assert_eq!(ellipse, circle, "radius {}", radius);
+        }
+    }
+
+    #[test]
+    fn ellipse_with_swapped_radii_is_transposed() {
+        let wide = ellipse_points(5, 2)
+            .into_iter()
+            .map(|(x, y)| (y, x))
+            .sorted()
+            .collect::<Vec<(i32, i32)>>();
+        let tall = ellipse_points(2, 5)
+            .into_iter()
+            .sorted()
+            .collect::<Vec<(i32, i32)>>();
+
+        assert_eq!(wide, tall);
+    }
 }
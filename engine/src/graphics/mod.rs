@@ -1,6 +1,13 @@
 pub mod animation;
+pub mod camera;
+pub mod color;
+pub mod debug_draw;
+pub mod effects;
 pub mod fonts;
 pub mod fullscreen;
 mod midpoint;
+pub mod monitor;
 pub mod rendering;
+pub mod screenshot;
 pub mod sprites;
+pub mod tilemap;
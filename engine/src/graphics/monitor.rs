@@ -0,0 +1,97 @@
+use sdl2::VideoSubsystem;
+
+use crate::geometry::Rect;
+
+#[derive(Debug, Clone)]
+pub struct MonitorInfo {
+    pub index: u32,
+    pub name: String,
+    pub bounds: Rect,
+    pub refresh_rate: i32,
+}
+
+/// Lists all monitors currently connected, e.g. for building a settings menu.
+pub fn list_monitors(sdl_video: &VideoSubsystem) -> Vec<MonitorInfo> {
+    let num_displays = sdl_video.num_video_displays().unwrap_or(0);
+    (0..num_displays)
+        .filter_map(|index| {
+            let bounds = sdl_video.display_bounds(index).ok()?;
+            let name = sdl_video.display_name(index).unwrap_or_default();
+            let refresh_rate = sdl_video
+                .desktop_display_mode(index)
+                .map_or(0, |mode| mode.refresh_rate);
+            Some(MonitorInfo {
+                index: index as u32,
+                name,
+                bounds: Rect {
+                    x: bounds.x(),
+                    y: bounds.y(),
+                    w: bounds.width(),
+                    h: bounds.height(),
+                },
+                refresh_rate,
+            })
+        })
+        .collect()
+}
+
+/// Returns `monitor` if it's one of the listed monitors, otherwise falls back
+/// to the first monitor.
+pub fn validate_monitor_index(monitors: &[MonitorInfo], monitor: u64) -> u64 {
+    if monitors.iter().any(|info| info.index as u64 == monitor) {
+        monitor
+    } else {
+        log::warn!(
+            "Monitor {} does not exist ({} monitors found), falling back to monitor 0",
+            monitor,
+            monitors.len()
+        );
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitors(count: u32) -> Vec<MonitorInfo> {
+        (0..count)
+            .map(|index| MonitorInfo {
+                index,
+                name: format!("Monitor {}", index),
+                bounds: Rect {
+                    x: 1920 * index as i32,
+                    y: 0,
+                    w: 1920,
+                    h: 1080,
+                },
+                refresh_rate: 60,
+            })
+            .collect()
+    }
+
+    #[parameterized(monitor = { 0, 1, 2 })]
+    fn existing_monitor_index_is_kept(monitor: u64) {
+        let monitors = monitors(3);
+
+        let index = validate_monitor_index(&monitors, monitor);
+
+        assert_eq!(index, monitor);
+    }
+
+    #[parameterized(monitor = { 3, 100, u64::MAX })]
+    fn out_of_range_monitor_index_falls_back_to_first_monitor(monitor: u64) {
+        let monitors = monitors(3);
+
+        let index = validate_monitor_index(&monitors, monitor);
+
+        assert_eq!(index, 0);
+    }
+
+    #[test]
+    fn monitor_index_falls_back_to_zero_if_no_monitors_found() {
+        let index = validate_monitor_index(&[], 1);
+
+        assert_eq!(index, 0);
+    }
+}
@@ -1,8 +1,13 @@
 #![allow(dead_code)]
 
 use crate::{
-    geometry::{Dimension, Rect},
-    graphics::midpoint,
+    geometry::{bezier::bezier_points, Dimension, Point, Rect},
+    graphics::{
+        camera::Camera2D,
+        color::Color,
+        midpoint::{self, CirclePointsCache},
+    },
+    resources::resolve_resource_path,
 };
 use gl::types::*;
 use glam::Mat4;
@@ -10,10 +15,11 @@ use image::GenericImageView;
 use itertools::Itertools;
 use sdl2::video::GLContext;
 use std::{
-    collections::HashMap,
+    collections::{hash_map::Entry, HashMap},
     ffi::{c_void, CString},
     mem::size_of,
-    path::Path,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU8, Ordering},
 };
 
 #[derive(Debug)]
@@ -24,14 +30,56 @@ pub struct Renderer {
     canvas: Canvas,
     /// Store data from user draw calls
     draw: DrawData,
+    /// Shaders used when drawing the canvas to the window
+    upscaling: UpscalingData,
 }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, PartialOrd, Hash)]
-pub struct TextureID(u32);
+/// How the canvas is scaled up when drawn to the window
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum CanvasUpscaler {
+    /// Plain nearest neighbour scaling, keeping pixels square
+    #[default]
+    Nearest,
+    /// Scale2x (EPX), smoothing diagonal edges of pixel art
+    Scale2x,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Hash)]
+pub struct TextureID(pub(crate) u32);
 
 #[derive(Debug)]
 pub enum LoadError {
-    ImageError(image::ImageError),
+    ImageError {
+        path: PathBuf,
+        error: image::ImageError,
+    },
+}
+
+#[derive(Debug)]
+pub enum ShaderError {
+    IoError(std::io::Error),
+    CompileError(String),
+    LinkError(String),
+}
+
+/// Severity of OpenGL debug messages, notifications are always ignored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum GlDebugSeverity {
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Debug)]
+pub enum RendererInitError {
+    ShaderError(ShaderError),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum BlitError {
+    /// The buffer doesn't hold `width * height` RGBA pixels
+    BufferSizeMismatch { expected: usize, actual: usize },
 }
 
 #[derive(Debug)]
@@ -49,6 +97,23 @@ struct ShaderData {
     vao: u32,
     white_texture_id: u32,
     textures: HashMap<TextureID, TextureData>,
+    /// Textures reused by `blit_pixels`, one per blit between flushes
+    pixel_buffer_textures: Vec<TextureID>,
+}
+
+#[derive(Debug)]
+struct UpscalingData {
+    upscaler: CanvasUpscaler,
+    /// Upscaler programs compiled so far, the nearest upscaler uses the
+    /// regular shader program
+    programs: HashMap<CanvasUpscaler, UpscalerProgram>,
+}
+
+#[derive(Debug)]
+struct UpscalerProgram {
+    program: ShaderProgram,
+    _vertex_shader: Shader,
+    _fragment_shader: Shader,
 }
 
 #[derive(Debug, Default)]
@@ -57,15 +122,19 @@ pub struct Canvas {
     pub size: Dimension,
     pub scaled_size: Dimension,
     pub scale: f32,
+    /// Largest integer scale the canvas is drawn with, None for no limit
+    pub max_scale: Option<u32>,
     pub fbo: u32,
     pub vao: u32,
     pub texture: u32,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 struct TextureData {
     width: u32,
     height: u32,
+    /// RGBA data kept on the CPU for sampling, bottom row first
+    pixels: Option<Vec<u8>>,
 }
 
 #[derive(Debug)]
@@ -81,31 +150,51 @@ struct VertexSection {
     length: usize,            // The number of vertices in the section
     primitive: PrimitiveType, // The primitive to draw the vertices as
     texture_id: u32,          // The texture to draw with
-    color_key: ColorRGBA,     // The RGBA value to draw transparently
+    color_key: Color,         // The RGBA value to draw transparently
+    color_mod: ColorMod,      // The modulation applied to texels
+    camera: Option<Camera2D>, // The camera to draw with, None for canvas space
+    layer: i32,               // Sections are drawn in order of increasing layer
+}
+
+/// Texels are drawn as `texel * mult + add`
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ColorMod {
+    mult: Color,
+    add: Color,
 }
 
 #[derive(Debug)]
 struct DrawData {
-    draw_color: ColorRGBA,
-    texture_blend_color: ColorRGBA,
-    active_color_key: ColorRGBA,
+    draw_color: Color,
+    texture_blend_color: Color,
+    active_color_key: Color,
+    active_color_mod: ColorMod,
+    active_camera: Option<Camera2D>,
+    active_layer: i32,
     vertices: Vec<Vertex>,
     sections: Vec<VertexSection>,
+    circle_points: CirclePointsCache,
     window_width: f32,
     window_height: f32,
+    /// Whether the window is cleared to black around the canvas
+    letterbox_visible: bool,
+    /// Whether `present` draws the canvas to the window, instead of e.g. the
+    /// editor showing it in an ImGui window
+    canvas_drawn_to_window: bool,
+    /// Whether the canvas has been cleared since the last `present`
+    target_cleared: bool,
+    /// How many pixel buffer textures pending sections are drawn with
+    pixel_buffers_used: usize,
 }
 
 /// xyz
 #[derive(Debug, Copy, Clone)]
 struct Position(f32, f32, f32);
-/// rgb
-#[derive(Debug, Copy, Clone)]
-struct ColorRGBA(u8, u8, u8, u8);
 // uv
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 struct TextureUV(f32, f32);
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 #[repr(C, packed)]
 struct Vertex {
     pos: VertexPosition,
@@ -113,7 +202,7 @@ struct Vertex {
     texture_uv: VertexTextureUV,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 #[repr(C, packed)]
 struct VertexPosition {
     x: GLfloat,
@@ -121,7 +210,7 @@ struct VertexPosition {
     z: GLfloat,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 #[repr(C, packed)]
 struct VertexColor {
     r: GLfloat,
@@ -130,7 +219,7 @@ struct VertexColor {
     a: GLfloat,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 #[repr(C, packed)]
 struct VertexTextureUV {
     u: GLfloat,
@@ -139,20 +228,47 @@ struct VertexTextureUV {
 
 const VERTEX_SHADER_SRC: &str = include_str!("shaders/vertex.shader");
 const FRAGMENT_SHADER_SRC: &str = include_str!("shaders/fragment.shader");
+const SCALE2X_FRAGMENT_SHADER_SRC: &str = include_str!("shaders/scale2x_fragment.shader");
+
+/// How many distinct circle radii keep their points cached between draws
+const CIRCLE_POINTS_CACHE_CAPACITY: usize = 64;
 
 pub fn load_texture_from_image_path(
     gl: &GLContext,
     renderer: &mut Renderer,
     path: &Path,
 ) -> Result<TextureID, LoadError> {
-    let image = image::open(path)
-        .map_err(|e| LoadError::ImageError(e))?
-        .flipv();
-    let (width, height) = image.dimensions();
-    let data = image
-        .pixels()
-        .flat_map(|(_x, _y, pixel)| pixel.0)
-        .collect::<Vec<u8>>();
+    let (data, width, height) = load_image_rgba(path)?;
+    let id = renderer.add_texture(gl, &data, width, height);
+
+    Ok(id)
+}
+
+/// Loads a texture that keeps a copy of its pixels on the CPU, so that they
+/// can be read with `Renderer::sample_texture`, e.g. for looking up terrain
+/// types in a map image.
+pub fn load_sampleable_texture(
+    gl: &GLContext,
+    renderer: &mut Renderer,
+    path: &Path,
+) -> Result<TextureID, LoadError> {
+    let (data, width, height) = load_image_rgba(path)?;
+    let id = renderer.add_texture(gl, &data, width, height);
+    renderer.retain_texture_pixels(id, data);
+
+    Ok(id)
+}
+
+/// Loads a texture with all pixels matching the RGB channels of `color_key`
+/// made fully transparent, so that no color key is needed when drawing it.
+pub fn load_texture_with_color_key(
+    gl: &GLContext,
+    renderer: &mut Renderer,
+    path: &Path,
+    color_key: Color,
+) -> Result<TextureID, LoadError> {
+    let (mut data, width, height) = load_image_rgba(path)?;
+    apply_color_key(&mut data, color_key);
     let id = renderer.add_texture(gl, &data, width, height);
 
     Ok(id)
@@ -163,17 +279,77 @@ pub fn reload_texture_from_image_path(
     renderer: &mut Renderer,
     path: &Path,
 ) -> Result<(), LoadError> {
-    let image = image::open(path)
-        .map_err(|e| LoadError::ImageError(e))?
+    let (data, width, height) = load_image_rgba(path)?;
+    renderer.reload_texture(id, &data, width, height);
+
+    Ok(())
+}
+
+/// Loads an image as RGBA bytes flipped for OpenGL, together with its width
+/// and height.
+fn load_image_rgba(path: &Path) -> Result<(Vec<u8>, u32, u32), LoadError> {
+    let image = image::open(resolve_resource_path(path))
+        .map_err(|error| LoadError::ImageError {
+            path: path.to_owned(),
+            error,
+        })?
         .flipv();
     let (width, height) = image.dimensions();
     let data = image
         .pixels()
         .flat_map(|(_x, _y, pixel)| pixel.0)
         .collect::<Vec<u8>>();
-    renderer.reload_texture(id, &data, width, height);
 
-    Ok(())
+    Ok((data, width, height))
+}
+
+/// Reads a pixel of RGBA data stored bottom row first, as uploaded to OpenGL,
+/// with `y` counted from the top
+fn sample_rgba(rgba_data: &[u8], width: u32, height: u32, x: u32, y: u32) -> Option<Color> {
+    if x >= width || y >= height {
+        return None;
+    }
+    let row = height - 1 - y;
+    let index = 4 * (row * width + x) as usize;
+    match rgba_data.get(index..index + 4)? {
+        [r, g, b, a] => Some(Color::rgba(*r, *g, *b, *a)),
+        _ => None,
+    }
+}
+
+/// Reverses the row order of RGBA data, e.g. to turn pixels read back from
+/// OpenGL right side up
+fn flip_rows(rgba_data: &[u8], width: u32) -> Vec<u8> {
+    rgba_data
+        .chunks_exact(width as usize * 4)
+        .rev()
+        .flatten()
+        .copied()
+        .collect()
+}
+
+/// Sets the alpha of every RGBA pixel matching the RGB channels of
+/// `color_key` to zero
+fn apply_color_key(rgba_data: &mut [u8], color_key: Color) {
+    for pixel in rgba_data.chunks_exact_mut(4) {
+        if pixel[..3] == [color_key.r, color_key.g, color_key.b] {
+            pixel[3] = 0;
+        }
+    }
+}
+
+/// Recompiles the renderer shaders from source files on disk. If anything
+/// fails, the current shaders are kept.
+pub fn load_shaders_from_paths(
+    renderer: &mut Renderer,
+    vertex_shader_path: &Path,
+    fragment_shader_path: &Path,
+) -> Result<(), ShaderError> {
+    let vertex_shader_src =
+        std::fs::read_to_string(vertex_shader_path).map_err(ShaderError::IoError)?;
+    let fragment_shader_src =
+        std::fs::read_to_string(fragment_shader_path).map_err(ShaderError::IoError)?;
+    renderer.reload_shaders(&vertex_shader_src, &fragment_shader_src)
 }
 
 macro_rules! assert_no_gl_error {
@@ -188,6 +364,9 @@ macro_rules! assert_no_gl_error {
     };
 }
 
+/// The least severe OpenGL debug messages that will be logged
+static GL_DEBUG_MIN_SEVERITY: AtomicU8 = AtomicU8::new(GlDebugSeverity::Low as u8);
+
 #[no_mangle]
 extern "system" fn on_opengl_debug_message(
     _source: u32,
@@ -198,16 +377,44 @@ extern "system" fn on_opengl_debug_message(
     message: *const i8,
     _user_param: *mut c_void,
 ) {
-    unsafe {
-        let formatted_message = format!(
-            "OpenGL: {}",
-            std::ffi::CStr::from_ptr(message).to_str().unwrap()
-        );
+    let min_severity = GlDebugSeverity::from_u8(GL_DEBUG_MIN_SEVERITY.load(Ordering::Relaxed));
+    if let Some(level) = debug_message_log_level(severity, min_severity) {
+        unsafe {
+            log::log!(
+                level,
+                "OpenGL: {}",
+                std::ffi::CStr::from_ptr(message).to_str().unwrap()
+            );
+        }
+    }
+}
+
+fn debug_message_log_level(severity: GLenum, min_severity: GlDebugSeverity) -> Option<log::Level> {
+    let severity = GlDebugSeverity::from_gl_enum(severity)?;
+    if severity < min_severity {
+        return None;
+    }
+    match severity {
+        GlDebugSeverity::High | GlDebugSeverity::Medium => Some(log::Level::Error),
+        GlDebugSeverity::Low => Some(log::Level::Warn),
+    }
+}
+
+impl GlDebugSeverity {
+    fn from_gl_enum(severity: GLenum) -> Option<Self> {
         match severity {
-            gl::DEBUG_SEVERITY_HIGH => log::error!("{}", formatted_message),
-            gl::DEBUG_SEVERITY_MEDIUM => log::error!("{}", formatted_message),
-            gl::DEBUG_SEVERITY_LOW => log::warn!("{}", formatted_message),
-            _ => (),
+            gl::DEBUG_SEVERITY_HIGH => Some(GlDebugSeverity::High),
+            gl::DEBUG_SEVERITY_MEDIUM => Some(GlDebugSeverity::Medium),
+            gl::DEBUG_SEVERITY_LOW => Some(GlDebugSeverity::Low),
+            _ => None,
+        }
+    }
+
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => GlDebugSeverity::Low,
+            1 => GlDebugSeverity::Medium,
+            _ => GlDebugSeverity::High,
         }
     }
 }
@@ -226,7 +433,11 @@ fn gl_error_to_string(err: gl::types::GLenum) -> &'static str {
 }
 
 impl Renderer {
-    pub fn new(gl: &GLContext, window_width: u32, window_height: u32) -> Self {
+    pub fn new(
+        gl: &GLContext,
+        window_width: u32,
+        window_height: u32,
+    ) -> Result<Self, RendererInitError> {
         // Enable OpenGL debug logging
         unsafe {
             gl::Enable(gl::DEBUG_OUTPUT);
@@ -234,9 +445,12 @@ impl Renderer {
         }
 
         // Setup shader program
-        let vertex_shader = compile_shader(VERTEX_SHADER_SRC, gl::VERTEX_SHADER);
-        let fragment_shader = compile_shader(FRAGMENT_SHADER_SRC, gl::FRAGMENT_SHADER);
-        let program = link_program(&vertex_shader, &fragment_shader);
+        let vertex_shader = compile_shader(VERTEX_SHADER_SRC, gl::VERTEX_SHADER)
+            .map_err(RendererInitError::ShaderError)?;
+        let fragment_shader = compile_shader(FRAGMENT_SHADER_SRC, gl::FRAGMENT_SHADER)
+            .map_err(RendererInitError::ShaderError)?;
+        let program = link_program(&vertex_shader, &fragment_shader)
+            .map_err(RendererInitError::ShaderError)?;
 
         // Setup drawing buffer
         let primitives_vbo = new_vbo();
@@ -267,13 +481,19 @@ impl Renderer {
         );
         Vertex::set_attribute_pointers(canvas_vao, canvas_vbo);
 
+        // Smooth primitives drawn to a multisampled window framebuffer, if
+        // one was requested. Has no effect on the canvas.
+        unsafe {
+            gl::Enable(gl::MULTISAMPLE);
+        }
+
         // Enable alpha blending
         unsafe {
             gl::Enable(gl::BLEND);
             gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
         }
 
-        Renderer {
+        Ok(Renderer {
             shader: ShaderData {
                 program,
                 _vertex_shader: vertex_shader,
@@ -282,6 +502,7 @@ impl Renderer {
                 vao: primitives_vao,
                 white_texture_id: white_texture,
                 textures: HashMap::new(),
+                pixel_buffer_textures: Vec::new(),
             },
             canvas: Canvas::new(
                 canvas_fbo,
@@ -293,28 +514,44 @@ impl Renderer {
                 window_height,
             ),
             draw: DrawData {
-                draw_color: ColorRGBA(0, 0, 0, 255),
-                texture_blend_color: ColorRGBA(255, 255, 255, 255),
-                active_color_key: ColorRGBA(0, 0, 0, 0),
+                draw_color: Color::BLACK,
+                texture_blend_color: Color::WHITE,
+                active_color_key: Color::TRANSPARENT,
+                active_color_mod: ColorMod::default(),
+                active_camera: None,
+                active_layer: 0,
                 vertices: Vec::new(),
                 sections: Vec::new(),
+                circle_points: CirclePointsCache::new(CIRCLE_POINTS_CACHE_CAPACITY),
                 window_width: window_width as f32,
                 window_height: window_height as f32,
+                letterbox_visible: true,
+                canvas_drawn_to_window: false,
+                target_cleared: false,
+                pixel_buffers_used: 0,
             },
-        }
+            upscaling: UpscalingData {
+                upscaler: CanvasUpscaler::Nearest,
+                programs: HashMap::new(),
+            },
+        })
+    }
+
+    /// Draws the pending draw calls and presents the canvas, see
+    /// `flush_to_target` and `present`
+    pub fn render(&mut self, gl: &GLContext) {
+        self.flush_to_target(gl);
+        self.present(gl);
     }
 
-    pub fn render(&mut self, _gl: &GLContext) {
-        /* Draw to canvas */
+    /// Draws the draw calls submitted since the last flush to the canvas, and
+    /// removes them. The canvas is cleared by the first flush after each
+    /// `present`, so later flushes draw on top of earlier ones. Layers only
+    /// order draw calls within the same flush.
+    pub fn flush_to_target(&mut self, _gl: &GLContext) {
         unsafe {
             gl::UseProgram(self.shader.program.0);
             gl::BindFramebuffer(gl::FRAMEBUFFER, self.canvas.fbo);
-            self.set_projection_matrix(
-                0.0,
-                self.canvas.size.width as f32,
-                self.canvas.size.height as f32,
-                0.0,
-            );
             gl::Viewport(
                 0,
                 0,
@@ -323,52 +560,99 @@ impl Renderer {
             );
 
             // clear
-            gl::ClearColor(0.0, 0.0, 0.0, 1.0);
-            gl::Clear(gl::COLOR_BUFFER_BIT);
+            if !self.draw.target_cleared {
+                gl::ClearColor(0.0, 0.0, 0.0, 1.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT);
+                self.draw.target_cleared = true;
+            }
 
             // draw vertices
             set_vertex_data(self.shader.vbo, &self.draw.vertices);
             gl::BindVertexArray(self.shader.vao);
-            let mut buffer_index = 0;
-            for section in &self.draw.sections {
+            let mut current_camera = None;
+            self.set_camera_projection(None);
+            for (buffer_index, section) in section_draw_order(&self.draw.sections) {
+                if section.camera != current_camera {
+                    self.set_camera_projection(section.camera.as_ref());
+                    current_camera = section.camera;
+                }
+
                 let mode = match section.primitive {
                     PrimitiveType::Triangle => gl::TRIANGLES,
                     PrimitiveType::Line => gl::LINES,
                     PrimitiveType::Point => gl::POINTS,
                 };
 
-                self.set_color_key_uniform(section.color_key);
+                set_color_key_uniform(self.shader.program.0, section.color_key);
+                set_color_mod_uniforms(self.shader.program.0, section.color_mod);
                 gl::ActiveTexture(gl::TEXTURE0);
                 gl::BindTexture(gl::TEXTURE_2D, section.texture_id);
-                gl::DrawArrays(mode, buffer_index, section.length as i32);
-                buffer_index += section.length as i32;
+                gl::DrawArrays(mode, buffer_index as i32, section.length as i32);
             }
         }
 
-        /* Render canvas to screen */
-        // FIXME: using this as a temporary fix for separating rendering in
-        // "editor mode" and rendering in "game mode".
-        let should_render_canvas = false;
+        self.draw.vertices.clear();
+        self.draw.sections.clear();
+        self.draw.pixel_buffers_used = 0;
+    }
+
+    /// Draws the canvas to the window, ending the frame's drawing
+    pub fn present(&mut self, _gl: &GLContext) {
+        self.draw.target_cleared = false;
         unsafe {
             gl::UseProgram(self.shader.program.0);
             gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+        let letterbox_visible = self.draw.letterbox_visible;
+        let canvas_drawn = self.draw.canvas_drawn_to_window;
+        present_canvas(self, letterbox_visible, canvas_drawn);
+    }
 
-            // clear
-            gl::ClearColor(0.0, 0.0, 0.0, 1.0);
-            gl::Clear(gl::COLOR_BUFFER_BIT);
+    /// Replaces the shader program with one built from the given sources. If
+    /// compilation or linking fails the current program is kept, and the error
+    /// log is returned.
+    pub fn reload_shaders(
+        &mut self,
+        vertex_shader_src: &str,
+        fragment_shader_src: &str,
+    ) -> Result<(), ShaderError> {
+        let vertex_shader = compile_shader(vertex_shader_src, gl::VERTEX_SHADER)?;
+        let fragment_shader = compile_shader(fragment_shader_src, gl::FRAGMENT_SHADER)?;
+        let program = link_program(&vertex_shader, &fragment_shader)?;
+
+        self.shader.program = program;
+        self.shader._vertex_shader = vertex_shader;
+        self.shader._fragment_shader = fragment_shader;
+
+        Ok(())
+    }
+
+    /// Choose the shader scaling up the canvas when it's drawn to the window,
+    /// compiling it the first time it's used. On error the current
+    /// upscaler is kept.
+    pub fn set_canvas_upscaler(&mut self, upscaler: CanvasUpscaler) -> Result<(), ShaderError> {
+        if let Some(fragment_shader_src) = upscaler_fragment_shader_src(upscaler) {
+            if let Entry::Vacant(entry) = self.upscaling.programs.entry(upscaler) {
+                entry.insert(build_upscaler_program(fragment_shader_src)?);
+            }
+        }
+        self.upscaling.upscaler = upscaler;
+        Ok(())
+    }
+
+    pub fn canvas_upscaler(&self) -> CanvasUpscaler {
+        self.upscaling.upscaler
+    }
 
-            if should_render_canvas {
-                self.set_projection_matrix(-1.0, 1.0, -1.0, 1.0);
-                gl::Viewport(
-                    self.canvas.pos.x,
-                    self.canvas.pos.y,
-                    self.canvas.scaled_size.width as i32,
-                    self.canvas.scaled_size.height as i32,
-                );
-
-                gl::BindVertexArray(self.canvas.vao);
-                gl::BindTexture(gl::TEXTURE_2D, self.canvas.texture);
-                gl::DrawArrays(gl::TRIANGLES, 0, 6);
+    /// Enable or disable logging of OpenGL debug messages, only logging
+    /// messages of at least `min_severity`.
+    pub fn set_debug_output(&mut self, enabled: bool, min_severity: GlDebugSeverity) {
+        GL_DEBUG_MIN_SEVERITY.store(min_severity as u8, Ordering::Relaxed);
+        unsafe {
+            if enabled {
+                gl::Enable(gl::DEBUG_OUTPUT);
+            } else {
+                gl::Disable(gl::DEBUG_OUTPUT);
             }
         }
     }
@@ -381,11 +665,64 @@ impl Renderer {
         &mut self.canvas
     }
 
+    /// The canvas contents from the last `render` as RGBA bytes, top row first
+    pub fn read_canvas_pixels(&self) -> Vec<u8> {
+        let Dimension { width, height } = self.canvas.size;
+        let mut rgba_data = vec![0; width as usize * height as usize * 4];
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.canvas.fbo);
+            gl::ReadPixels(
+                0,
+                0,
+                width as i32,
+                height as i32,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                rgba_data.as_mut_ptr() as *mut c_void,
+            );
+            assert_no_gl_error!();
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+        flip_rows(&rgba_data, width)
+    }
+
+    /// See `Canvas::window_to_canvas`
+    pub fn window_to_canvas(&self, window_pos: Point) -> Point {
+        self.canvas.window_to_canvas(window_pos)
+    }
+
+    /// See `Canvas::canvas_to_window`
+    pub fn canvas_to_window(&self, canvas_pos: Point) -> Point {
+        self.canvas.canvas_to_window(canvas_pos)
+    }
+
     pub fn on_window_resize(&mut self, width: u32, height: u32) {
         self.draw.window_width = width as f32;
         self.draw.window_height = height as f32;
     }
 
+    /// Whether the window around the canvas is cleared to black each frame.
+    /// Turning it off keeps whatever was drawn there, e.g. a decorative
+    /// border rendered by the game. Only has an effect while the canvas is
+    /// drawn to the window.
+    pub fn set_viewport_letterbox_visible(&mut self, visible: bool) {
+        self.draw.letterbox_visible = visible;
+    }
+
+    /// Whether `present` draws the canvas to the window. Off by default, for
+    /// when the canvas is shown some other way, e.g. in an editor window.
+    pub fn set_canvas_drawn_to_window(&mut self, drawn: bool) {
+        self.draw.canvas_drawn_to_window = drawn;
+    }
+
+    /// Limit how far the canvas is scaled up, e.g. to keep it from filling
+    /// very large monitors. None removes the limit.
+    pub fn set_max_canvas_scale(&mut self, max_scale: Option<u32>) {
+        self.canvas.max_scale = max_scale;
+        self.canvas
+            .update(self.draw.window_width, self.draw.window_height);
+    }
+
     pub fn add_texture(
         &mut self,
         gl: &GLContext,
@@ -394,78 +731,146 @@ impl Renderer {
         height: u32,
     ) -> TextureID {
         let id = TextureID(new_texture(gl));
-        self.shader
-            .textures
-            .insert(id, TextureData { width, height });
+        self.shader.textures.insert(
+            id,
+            TextureData {
+                width,
+                height,
+                pixels: None,
+            },
+        );
         set_texture_image(id.0, width, height, Some(rgba_data));
         id
     }
 
+    /// Replaces the texture image. Textures that keep their pixels on the
+    /// CPU keep the new pixels instead.
     pub fn reload_texture(&mut self, id: TextureID, rgba_data: &[u8], width: u32, height: u32) {
-        self.shader
+        let retains_pixels = self
+            .shader
             .textures
-            .insert(id, TextureData { width, height });
+            .get(&id)
+            .is_some_and(|texture| texture.pixels.is_some());
+        self.shader.textures.insert(
+            id,
+            TextureData {
+                width,
+                height,
+                pixels: retains_pixels.then(|| rgba_data.to_vec()),
+            },
+        );
         set_texture_image(id.0, width, height, Some(rgba_data));
     }
 
+    /// The color of the texture pixel at (`x`, `y`), counted from the top
+    /// left. None if out of bounds or if the texture wasn't loaded with
+    /// `load_sampleable_texture`.
+    pub fn sample_texture(&self, id: TextureID, x: u32, y: u32) -> Option<Color> {
+        let texture = self.shader.textures.get(&id)?;
+        let pixels = texture.pixels.as_ref()?;
+        sample_rgba(pixels, texture.width, texture.height, x, y)
+    }
+
+    fn retain_texture_pixels(&mut self, id: TextureID, rgba_data: Vec<u8>) {
+        if let Some(texture) = self.shader.textures.get_mut(&id) {
+            texture.pixels = Some(rgba_data);
+        }
+    }
+
     pub fn clear(&mut self) {
-        self.draw.draw_color = ColorRGBA(0, 0, 0, 255);
+        self.draw.draw_color = Color::BLACK;
+        self.draw.active_layer = 0;
+        self.draw.active_color_mod = ColorMod::default();
         self.draw.vertices.clear();
         self.draw.sections.clear();
+        self.draw.pixel_buffers_used = 0;
     }
 
     #[allow(dead_code)]
-    pub fn set_draw_color(&mut self, r: u8, g: u8, b: u8, a: u8) {
-        self.draw.draw_color = ColorRGBA(r, g, b, a);
+    pub fn set_draw_color(&mut self, color: Color) {
+        self.draw.draw_color = color;
     }
 
     #[allow(dead_code)]
-    pub fn set_texture_blend_color(&mut self, r: u8, g: u8, b: u8, a: u8) {
-        self.draw.texture_blend_color = ColorRGBA(r, g, b, a);
+    pub fn set_texture_blend_color(&mut self, color: Color) {
+        self.draw.texture_blend_color = color;
     }
 
+    /// Draw pixels of `color` transparently in subsequent texture draws. The
+    /// alpha channel of `color` is ignored.
     #[allow(dead_code)]
-    pub fn set_color_key(&mut self, r: u8, g: u8, b: u8) {
-        self.draw.active_color_key = ColorRGBA(r, g, b, 255);
+    pub fn set_color_key(&mut self, color: Color) {
+        self.draw.active_color_key = color.with_alpha(255);
     }
 
     #[allow(dead_code)]
     pub fn disable_color_key(&mut self) {
-        self.draw.active_color_key = ColorRGBA(0, 0, 0, 0);
+        self.draw.active_color_key = Color::TRANSPARENT;
+    }
+
+    /// Modulate texels of subsequent draws as `texel * mult + add`, e.g.
+    /// `set_texture_color_mod(Color::WHITE, Color::WHITE.with_alpha(0))` to
+    /// flash a sprite white while keeping its shape.
+    pub fn set_texture_color_mod(&mut self, mult: Color, add: Color) {
+        self.draw.active_color_mod = ColorMod { mult, add };
+    }
+
+    pub fn reset_texture_color_mod(&mut self) {
+        self.draw.active_color_mod = ColorMod::default();
     }
 
     #[allow(dead_code)]
     pub fn draw_point(&mut self, x: i32, y: i32) {
-        self.draw.vertices.push(Vertex::with_color(
-            Position(x as f32, y as f32, 0.0),
-            self.draw.draw_color,
-        ));
-        self.draw.sections.push(VertexSection {
-            length: 1,
-            primitive: PrimitiveType::Point,
-            texture_id: self.shader.white_texture_id,
-            color_key: self.draw.active_color_key,
-        })
+        let vertex = Vertex::with_color(Position(x as f32, y as f32, 0.0), self.draw.draw_color);
+        self.push_section([vertex], PrimitiveType::Point, self.shader.white_texture_id);
+    }
+
+    /// Draw many single pixels with their own colors as a single section,
+    /// e.g. for procedural effects like plasma or noise
+    pub fn draw_points(&mut self, points: &[(Point, Color)]) {
+        if points.is_empty() {
+            return;
+        }
+
+        let vertices = point_vertices(points);
+        self.push_section(vertices, PrimitiveType::Point, self.shader.white_texture_id);
     }
 
     #[allow(dead_code)]
     pub fn draw_line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32) {
-        let (x0, y0, x1, y1) = (x0 as f32, y0 as f32, x1 as f32, y1 as f32);
-        let color = self.draw.draw_color;
+        let vertices = line_vertices(x0, y0, x1, y1, self.draw.draw_color);
+        self.push_section(vertices, PrimitiveType::Line, self.shader.white_texture_id);
+    }
 
-        // offset slightly to get around weird missing pixels
-        let start = Position(x0 - 0.5, y0 - 0.5, 0.0);
-        let end = Position(x1, y1, 0.0);
+    /// Draw a line between each pair of points, as a single section
+    pub fn draw_lines(&mut self, segments: &[(Point, Point)]) {
+        if segments.is_empty() {
+            return;
+        }
 
-        self.draw.vertices.push(Vertex::with_color(start, color));
-        self.draw.vertices.push(Vertex::with_color(end, color));
+        let vertices = segments
+            .iter()
+            .flat_map(|(start, end)| {
+                line_vertices(start.x, start.y, end.x, end.y, self.draw.draw_color)
+            })
+            .collect::<Vec<Vertex>>();
+        self.push_section(vertices, PrimitiveType::Line, self.shader.white_texture_id);
+    }
 
-        self.draw.sections.push(VertexSection {
-            length: 2,
-            primitive: PrimitiveType::Line,
-            texture_id: self.shader.white_texture_id,
-            color_key: self.draw.active_color_key,
-        })
+    /// Draw connected lines through all `points`, as a single section
+    pub fn draw_line_strip(&mut self, points: &[Point]) {
+        if points.len() < 2 {
+            return;
+        }
+
+        let vertices = line_strip_vertices(points, self.draw.draw_color);
+        self.push_section(vertices, PrimitiveType::Line, self.shader.white_texture_id);
+    }
+
+    /// Draw a cubic bezier curve from `p0` to `p3` with control points `p1`
+    /// and `p2`, approximated by `segments` lines.
+    pub fn draw_bezier(&mut self, p0: Point, p1: Point, p2: Point, p3: Point, segments: u32) {
+        self.draw_line_strip(&bezier_points(p0, p1, p2, p3, segments));
     }
 
     #[allow(dead_code)]
@@ -484,83 +889,46 @@ impl Renderer {
 
     #[allow(dead_code)]
     pub fn draw_rect_fill(&mut self, rect: Rect) {
-        let (x, y, w, h) = (rect.x as f32, rect.y as f32, rect.w as f32, rect.h as f32);
-        let color = self.draw.draw_color;
-        let vertices = &mut self.draw.vertices;
-
-        let top_left = Position(x, y, 0.0);
-        let top_right = Position(x + w, y, 0.0);
-        let bottom_left = Position(x, y + h, 0.0);
-        let bottom_right = Position(x + w, y + h, 0.0);
-
-        // first triangle
-        vertices.push(Vertex::with_color(top_left, color));
-        vertices.push(Vertex::with_color(top_right, color));
-        vertices.push(Vertex::with_color(bottom_left, color));
-
-        // second triangle
-        vertices.push(Vertex::with_color(top_right, color));
-        vertices.push(Vertex::with_color(bottom_left, color));
-        vertices.push(Vertex::with_color(bottom_right, color));
-
-        self.draw.sections.push(VertexSection {
-            length: 6,
-            primitive: PrimitiveType::Triangle,
-            texture_id: self.shader.white_texture_id,
-            color_key: self.draw.active_color_key,
-        })
+        let vertices = rect_fill_vertices(rect, self.draw.draw_color);
+        self.push_section(
+            vertices,
+            PrimitiveType::Triangle,
+            self.shader.white_texture_id,
+        );
     }
 
     #[allow(dead_code)]
     pub fn draw_circle(&mut self, center_x: i32, center_y: i32, radius: u32) {
-        let circle_vertices = midpoint::circle_points(radius).into_iter().map(|(x, y)| {
-            Vertex::with_color(
-                Position((center_x + x) as f32, (center_y + y) as f32, 0.0),
-                self.draw.draw_color,
-            )
-        });
-
-        let prev_vertices_len = self.draw.vertices.len();
-        self.draw.vertices.extend(circle_vertices);
-
-        self.draw.sections.push(VertexSection {
-            length: self.draw.vertices.len() - prev_vertices_len,
-            primitive: PrimitiveType::Point,
-            texture_id: self.shader.white_texture_id,
-            color_key: self.draw.active_color_key,
-        })
+        let points = self.draw.circle_points.circle_points(radius);
+        let vertices = outline_vertices(center_x, center_y, points, self.draw.draw_color);
+        self.push_section(vertices, PrimitiveType::Point, self.shader.white_texture_id);
     }
 
     #[allow(dead_code)]
     pub fn draw_fill_circle(&mut self, center_x: i32, center_y: i32, radius: u32) {
-        let half_circle_points = midpoint::circle_points(radius)
-            .into_iter()
-            .filter(|(_, y)| *y >= 0) // grab upper half of circle
-            .unique_by(|(x, _)| *x); // make sure we don't overlap any lines (messes with transparency)
-        let line_vertices = half_circle_points.flat_map(|(x, y)| {
-            [
-                // start the line on upper half circle
-                Vertex::with_color(
-                    Position((center_x + x) as f32, (center_y + y) as f32, 0.0),
-                    self.draw.draw_color,
-                ),
-                // end the line on lower half circle
-                Vertex::with_color(
-                    Position((center_x + x) as f32, (center_y - y) as f32, 0.0),
-                    self.draw.draw_color,
-                ),
-            ]
-        });
+        let points = self.draw.circle_points.circle_points(radius);
+        let vertices = fill_outline_vertices(center_x, center_y, points, self.draw.draw_color);
+        self.push_section(vertices, PrimitiveType::Line, self.shader.white_texture_id);
+    }
 
-        let prev_vertices_len = self.draw.vertices.len();
-        self.draw.vertices.extend(line_vertices);
+    #[allow(dead_code)]
+    pub fn draw_ellipse(&mut self, center_x: i32, center_y: i32, radius_x: u32, radius_y: u32) {
+        let points = midpoint::ellipse_points(radius_x, radius_y);
+        let vertices = outline_vertices(center_x, center_y, &points, self.draw.draw_color);
+        self.push_section(vertices, PrimitiveType::Point, self.shader.white_texture_id);
+    }
 
-        self.draw.sections.push(VertexSection {
-            length: self.draw.vertices.len() - prev_vertices_len,
-            primitive: PrimitiveType::Line,
-            texture_id: self.shader.white_texture_id,
-            color_key: self.draw.active_color_key,
-        })
+    #[allow(dead_code)]
+    pub fn draw_fill_ellipse(
+        &mut self,
+        center_x: i32,
+        center_y: i32,
+        radius_x: u32,
+        radius_y: u32,
+    ) {
+        let points = midpoint::ellipse_points(radius_x, radius_y);
+        let vertices = fill_outline_vertices(center_x, center_y, &points, self.draw.draw_color);
+        self.push_section(vertices, PrimitiveType::Line, self.shader.white_texture_id);
     }
 
     #[allow(dead_code)]
@@ -575,97 +943,273 @@ impl Renderer {
         draw_rect: Rect,
         clip_rect: Option<Rect>,
     ) {
-        let (draw_x, draw_y, draw_w, draw_h) = (
-            draw_rect.x as f32,
-            draw_rect.y as f32,
-            draw_rect.w as f32,
-            draw_rect.h as f32,
-        );
-        let vertices = &mut self.draw.vertices;
-
-        let top_left_xy = Position(draw_x, draw_y, 0.0);
-        let top_right_xy = Position(draw_x + draw_w, draw_y, 0.0);
-        let bottom_left_xy = Position(draw_x, draw_y + draw_h, 0.0);
-        let bottom_right_xy = Position(draw_x + draw_w, draw_y + draw_h, 0.0);
-
-        let top_left_uv;
-        let top_right_uv;
-        let bottom_left_uv;
-        let bottom_right_uv;
-
-        if let Some(clip_rect) = clip_rect {
-            let texture = self.shader.textures[&texture_id];
-            let (tex_w, tex_h) = (texture.width as f32, texture.height as f32);
-            let (clip_x, clip_y, clip_w, clip_h) = (
-                clip_rect.x as f32,
-                clip_rect.y as f32,
-                clip_rect.w as f32,
-                clip_rect.h as f32,
+        let vertices = self.texture_quad_vertices(texture_id, draw_rect, clip_rect);
+        self.push_section(vertices, PrimitiveType::Triangle, texture_id.0);
+    }
+
+    /// Draw several quads of the same texture, as a single draw call.
+    /// * `quads` pairs of `draw_rect` and `clip_rect`, see `draw_texture`
+    pub fn draw_texture_quads(&mut self, texture_id: TextureID, quads: &[(Rect, Option<Rect>)]) {
+        if quads.is_empty() {
+            return;
+        }
+
+        let vertices = quads
+            .iter()
+            .flat_map(|(draw_rect, clip_rect)| {
+                self.texture_quad_vertices(texture_id, *draw_rect, *clip_rect)
+            })
+            .collect::<Vec<Vertex>>();
+        self.push_section(vertices, PrimitiveType::Triangle, texture_id.0);
+    }
+
+    /// Draw a buffer of RGBA pixels, e.g. from a software renderer, stretched
+    /// over `dest_rect`. Rows are stored top to bottom.
+    ///
+    /// The buffer is uploaded to a texture kept for blitting, reallocating
+    /// its storage only when the buffer size changes. Each blit between two
+    /// flushes gets a texture of its own.
+    pub fn blit_pixels(
+        &mut self,
+        gl: &GLContext,
+        buffer: &[u8],
+        width: u32,
+        height: u32,
+        dest_rect: Rect,
+    ) -> Result<(), BlitError> {
+        check_pixel_buffer_size(buffer.len(), width, height)?;
+
+        let pool_index = self.draw.pixel_buffers_used;
+        self.draw.pixel_buffers_used += 1;
+        let texture_id = match self.shader.pixel_buffer_textures.get(pool_index) {
+            Some(texture_id) => *texture_id,
+            None => {
+                let texture_id = TextureID(new_texture(gl));
+                self.shader.pixel_buffer_textures.push(texture_id);
+                texture_id
+            }
+        };
+
+        let current_size = self
+            .shader
+            .textures
+            .get(&texture_id)
+            .map(|texture| (texture.width, texture.height));
+        if needs_texture_storage(current_size, width, height) {
+            self.shader.textures.insert(
+                texture_id,
+                TextureData {
+                    width,
+                    height,
+                    pixels: None,
+                },
             );
-            top_left_uv = TextureUV(clip_x / tex_w, 1.0 - clip_y / tex_h);
-            top_right_uv = TextureUV((clip_x + clip_w) / tex_w, 1.0 - clip_y / tex_h);
-            bottom_left_uv = TextureUV(clip_x / tex_w, 1.0 - (clip_y + clip_h) / tex_h);
-            bottom_right_uv = TextureUV((clip_x + clip_w) / tex_w, 1.0 - (clip_y + clip_h) / tex_h);
+            set_texture_image(texture_id.0, width, height, Some(buffer));
         } else {
-            top_left_uv = TextureUV(0.0, 1.0);
-            top_right_uv = TextureUV(1.0, 1.0);
-            bottom_left_uv = TextureUV(0.0, 0.0);
-            bottom_right_uv = TextureUV(1.0, 0.0);
+            update_texture_image(texture_id.0, width, height, buffer);
         }
 
-        let blend_color = self.draw.texture_blend_color;
-
-        // first triangle
-        vertices.push(Vertex::new(top_left_xy, blend_color, top_left_uv));
-        vertices.push(Vertex::new(top_right_xy, blend_color, top_right_uv));
-        vertices.push(Vertex::new(bottom_left_xy, blend_color, bottom_left_uv));
+        let vertices =
+            texture_quad_vertices(dest_rect, PIXEL_BUFFER_UVS, self.draw.texture_blend_color);
+        self.push_section(vertices, PrimitiveType::Triangle, texture_id.0);
+        Ok(())
+    }
 
-        // second triangle
-        vertices.push(Vertex::new(top_right_xy, blend_color, top_right_uv));
-        vertices.push(Vertex::new(bottom_left_xy, blend_color, bottom_left_uv));
-        vertices.push(Vertex::new(bottom_right_xy, blend_color, bottom_right_uv));
+    /// Adds `vertices` to the vertex buffer as a section drawn with the
+    /// current draw state
+    fn push_section(
+        &mut self,
+        vertices: impl IntoIterator<Item = Vertex>,
+        primitive: PrimitiveType,
+        texture_id: u32,
+    ) {
+        let prev_vertices_len = self.draw.vertices.len();
+        self.draw.vertices.extend(vertices);
 
         self.draw.sections.push(VertexSection {
-            length: 6,
-            primitive: PrimitiveType::Triangle,
-            texture_id: texture_id.0,
+            length: self.draw.vertices.len() - prev_vertices_len,
+            primitive,
+            texture_id,
             color_key: self.draw.active_color_key,
+            color_mod: self.draw.active_color_mod,
+            camera: self.draw.active_camera,
+            layer: self.draw.active_layer,
         })
     }
 
-    pub fn set_resolution(&mut self, resolution_width: u32, resolution_height: u32) {
-        set_texture_image(
-            self.canvas.texture,
-            resolution_width,
-            resolution_height,
-            None,
-        );
-        self.canvas.size.width = resolution_width;
-        self.canvas.size.height = resolution_height;
-    }
+    fn texture_quad_vertices(
+        &self,
+        texture_id: TextureID,
+        draw_rect: Rect,
+        clip_rect: Option<Rect>,
+    ) -> [Vertex; 6] {
+        let uvs = if let Some(clip_rect) = clip_rect {
+            let texture = &self.shader.textures[&texture_id];
+            clip_rect_uvs(clip_rect, texture.width, texture.height)
+        } else {
+            FULL_TEXTURE_UVS
+        };
+        texture_quad_vertices(draw_rect, uvs, self.draw.texture_blend_color)
+    }
+
+    /// Draw a texture repeated across `draw_rect` at its original size, e.g.
+    /// for backgrounds. Uses a single quad, relying on the texture wrapping.
+    /// * `scroll_offset` texture pixel offset of the top left corner, for scrolling
+    #[allow(dead_code)]
+    pub fn draw_texture_tiled(
+        &mut self,
+        texture_id: TextureID,
+        draw_rect: Rect,
+        scroll_offset: Point,
+    ) {
+        self.draw_texture(
+            texture_id,
+            draw_rect,
+            Some(tiled_clip_rect(draw_rect, scroll_offset)),
+        );
+    }
+
+    /// Draw a texture split into nine regions, so that the corners keep their
+    /// size while the edges and center stretch to fill `draw_rect`.
+    /// * `border` the (left, right, top, bottom) border widths in texture pixels
+    #[allow(dead_code)]
+    pub fn draw_nine_slice(
+        &mut self,
+        texture_id: TextureID,
+        draw_rect: Rect,
+        border: (u32, u32, u32, u32),
+    ) {
+        let texture = &self.shader.textures[&texture_id];
+        let texture_size = Dimension {
+            width: texture.width,
+            height: texture.height,
+        };
+        for (clip_rect, region_rect) in nine_slice_rects(texture_size, draw_rect, border) {
+            if region_rect.w > 0 && region_rect.h > 0 {
+                self.draw_texture(texture_id, region_rect, Some(clip_rect));
+            }
+        }
+    }
 
-    fn set_color_key_uniform(&self, color_key: ColorRGBA) {
-        set_uniform_vec4f(
-            self.shader.program.0,
-            "color_key",
-            color_key.0 as f32 / 255.0,
-            color_key.1 as f32 / 255.0,
-            color_key.2 as f32 / 255.0,
-            color_key.3 as f32 / 255.0,
+    pub fn set_resolution(&mut self, resolution_width: u32, resolution_height: u32) {
+        set_texture_image(
+            self.canvas.texture,
+            resolution_width,
+            resolution_height,
+            None,
         );
+        self.canvas.size.width = resolution_width;
+        self.canvas.size.height = resolution_height;
+    }
+
+    /// Draw subsequent draw calls on `layer`. Higher layers are drawn on top of
+    /// lower ones, calls on the same layer are drawn in submission order.
+    pub fn set_layer(&mut self, layer: i32) {
+        self.draw.active_layer = layer;
+    }
+
+    /// Draw subsequent draw calls on the default layer 0
+    pub fn reset_layer(&mut self) {
+        self.draw.active_layer = 0;
+    }
+
+    /// Draw subsequent draw calls in world space, as seen through `camera`
+    pub fn set_camera(&mut self, camera: &Camera2D) {
+        self.draw.active_camera = Some(*camera);
+    }
+
+    /// Draw subsequent draw calls in canvas space, e.g. for UI
+    pub fn reset_camera(&mut self) {
+        self.draw.active_camera = None;
+    }
+
+    fn set_camera_projection(&self, camera: Option<&Camera2D>) {
+        let (left, right, bottom, top) = match camera {
+            Some(camera) => camera.view_bounds(self.canvas.size),
+            None => (
+                0.0,
+                self.canvas.size.width as f32,
+                self.canvas.size.height as f32,
+                0.0,
+            ),
+        };
+        self.set_projection_matrix(left, right, bottom, top);
     }
 
     fn set_projection_matrix(&self, left: f32, right: f32, bottom: f32, top: f32) {
-        let projection = Mat4::orthographic_lh(left, right, bottom, top, -1.0, 1.0);
         unsafe {
             gl::UseProgram(self.shader.program.0);
-            let projection_name = CString::new("projection").unwrap();
-            let location = gl::GetUniformLocation(self.shader.program.0, projection_name.as_ptr());
-            gl::UniformMatrix4fv(location, 1, gl::FALSE, &projection.to_cols_array()[0]);
+        }
+        set_projection_uniform(self.shader.program.0, left, right, bottom, top);
+    }
+
+    /// The program drawing the canvas to the window with the selected upscaler
+    fn canvas_program(&self) -> u32 {
+        self.upscaling
+            .programs
+            .get(&self.upscaling.upscaler)
+            .map_or(self.shader.program.0, |upscaler| upscaler.program.0)
+    }
+}
+
+/// The GL calls of presenting the canvas, so that tests can count which of
+/// them run
+trait PresentCalls {
+    fn clear_window(&mut self);
+    fn draw_canvas(&mut self);
+}
+
+impl PresentCalls for Renderer {
+    fn clear_window(&mut self) {
+        unsafe {
+            gl::ClearColor(0.0, 0.0, 0.0, 1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+        }
+    }
+
+    fn draw_canvas(&mut self) {
+        // Lay out the canvas for the window, in case it was last shown elsewhere
+        self.canvas
+            .update(self.draw.window_width, self.draw.window_height);
+        let program = self.canvas_program();
+        unsafe {
+            gl::UseProgram(program);
+            set_projection_uniform(program, -1.0, 1.0, -1.0, 1.0);
+            set_uniform_vec2f(
+                program,
+                "texture_size",
+                self.canvas.size.width as f32,
+                self.canvas.size.height as f32,
+            );
+            // The regular program still has the uniforms of the last drawn
+            // section, which mustn't tint or key out the whole canvas
+            set_color_key_uniform(program, Color::TRANSPARENT);
+            set_color_mod_uniforms(program, ColorMod::default());
+            gl::Viewport(
+                self.canvas.pos.x,
+                self.canvas.pos.y,
+                self.canvas.scaled_size.width as i32,
+                self.canvas.scaled_size.height as i32,
+            );
+
+            gl::BindVertexArray(self.canvas.vao);
+            gl::BindTexture(gl::TEXTURE_2D, self.canvas.texture);
+            gl::DrawArrays(gl::TRIANGLES, 0, 6);
         }
     }
 }
 
+/// Keeping the window contents only makes sense when the canvas is drawn on
+/// top of them, otherwise the window is always cleared
+fn present_canvas(calls: &mut impl PresentCalls, letterbox_visible: bool, canvas_drawn: bool) {
+    if letterbox_visible || !canvas_drawn {
+        calls.clear_window();
+    }
+    if canvas_drawn {
+        calls.draw_canvas();
+    }
+}
+
 impl Canvas {
     fn new(
         fbo: u32,
@@ -706,18 +1250,23 @@ impl Canvas {
         });
     }
 
+    /// The integer scale fitting the canvas best in the window, at least 1 so
+    /// that the canvas never disappears in small windows, and at most
+    /// `max_scale` if set.
     pub fn calculate_scale(&self, window_width: f32, window_height: f32) -> f32 {
-        f32::round(f32::min(
+        let scale = f32::round(f32::min(
             window_width / self.size.width as f32,
             window_height / self.size.height as f32,
         ))
+        .max(1.0);
+        match self.max_scale {
+            Some(max_scale) => scale.min(max_scale.max(1) as f32),
+            None => scale,
+        }
     }
 
     pub fn calculate_scaled_dimensions(&self, scale: f32) -> Dimension {
-        Dimension {
-            width: (scale * self.size.width as f32) as u32,
-            height: (scale * self.size.height as f32) as u32,
-        }
+        self.size.scaled(scale)
     }
 
     pub fn calculate_position(&self, window: Rect) -> glam::IVec2 {
@@ -726,6 +1275,25 @@ impl Canvas {
             window.y + (window.h as i32 - self.scaled_size.height as i32) / 2,
         )
     }
+
+    /// Maps a window position to the canvas pixel it's inside of. Can be
+    /// outside of the canvas.
+    pub fn window_to_canvas(&self, window_pos: Point) -> Point {
+        let offset_x = (window_pos.x - self.pos.x) as f32;
+        let offset_y = (window_pos.y - self.pos.y) as f32;
+        glam::ivec2(
+            f32::floor(offset_x / self.scale) as i32,
+            f32::floor(offset_y / self.scale) as i32,
+        )
+    }
+
+    /// Maps a canvas pixel to the window position of its top left corner
+    pub fn canvas_to_window(&self, canvas_pos: Point) -> Point {
+        glam::ivec2(
+            self.pos.x + (canvas_pos.x as f32 * self.scale) as i32,
+            self.pos.y + (canvas_pos.y as f32 * self.scale) as i32,
+        )
+    }
 }
 
 impl Drop for ShaderProgram {
@@ -757,41 +1325,50 @@ impl Drop for Renderer {
     }
 }
 
+impl Default for ColorMod {
+    fn default() -> Self {
+        ColorMod {
+            mult: Color::WHITE,
+            add: Color::TRANSPARENT,
+        }
+    }
+}
+
+impl ColorMod {
+    /// The normalized `color_mult` and `color_add` shader uniform values
+    fn uniform_values(&self) -> ([f32; 4], [f32; 4]) {
+        (self.mult.to_f32_array(), self.add.to_f32_array())
+    }
+}
+
+impl From<Color> for VertexColor {
+    fn from(color: Color) -> Self {
+        let [r, g, b, a] = color.to_f32_array();
+        VertexColor { r, g, b, a }
+    }
+}
+
 impl Vertex {
-    fn new(
-        Position(x, y, z): Position,
-        ColorRGBA(r, g, b, a): ColorRGBA,
-        TextureUV(u, v): TextureUV,
-    ) -> Self {
+    fn new(Position(x, y, z): Position, color: Color, TextureUV(u, v): TextureUV) -> Self {
         Vertex {
             pos: VertexPosition {
                 x: x as f32,
                 y: y as f32,
                 z: z as f32,
             },
-            color: VertexColor {
-                r: r as f32 / 255.0,
-                g: g as f32 / 255.0,
-                b: b as f32 / 255.0,
-                a: a as f32 / 255.0,
-            },
+            color: VertexColor::from(color),
             texture_uv: VertexTextureUV { u, v },
         }
     }
 
-    fn with_color(Position(x, y, z): Position, ColorRGBA(r, g, b, a): ColorRGBA) -> Self {
+    fn with_color(Position(x, y, z): Position, color: Color) -> Self {
         Vertex {
             pos: VertexPosition {
                 x: x as f32,
                 y: y as f32,
                 z: z as f32,
             },
-            color: VertexColor {
-                r: r as f32 / 255.0,
-                g: g as f32 / 255.0,
-                b: b as f32 / 255.0,
-                a: a as f32 / 255.0,
-            },
+            color: VertexColor::from(color),
             texture_uv: VertexTextureUV { u: 0.0, v: 0.0 },
         }
     }
@@ -873,80 +1450,335 @@ impl Vertex {
     }
 }
 
-fn compile_shader(src: &str, ty: GLenum) -> Shader {
-    let shader;
+/// UVs of the (top left, top right, bottom left, bottom right) corners of a
+/// whole texture
+const FULL_TEXTURE_UVS: [TextureUV; 4] = [
+    TextureUV(0.0, 1.0),
+    TextureUV(1.0, 1.0),
+    TextureUV(0.0, 0.0),
+    TextureUV(1.0, 0.0),
+];
+
+/// UVs of the corners of a pixel buffer texture, which unlike loaded images
+/// isn't flipped, so its first row is at the top
+const PIXEL_BUFFER_UVS: [TextureUV; 4] = [
+    TextureUV(0.0, 0.0),
+    TextureUV(1.0, 0.0),
+    TextureUV(0.0, 1.0),
+    TextureUV(1.0, 1.0),
+];
+
+/// Whether a texture of `current_size` must get new storage to hold an image
+/// of `width` x `height`, rather than having its pixels replaced in place
+fn needs_texture_storage(current_size: Option<(u32, u32)>, width: u32, height: u32) -> bool {
+    current_size != Some((width, height))
+}
+
+fn check_pixel_buffer_size(len: usize, width: u32, height: u32) -> Result<(), BlitError> {
+    let expected = width as usize * height as usize * 4;
+    if len == expected {
+        Ok(())
+    } else {
+        Err(BlitError::BufferSizeMismatch {
+            expected,
+            actual: len,
+        })
+    }
+}
+
+fn line_vertices(x0: i32, y0: i32, x1: i32, y1: i32, color: Color) -> [Vertex; 2] {
+    let (x0, y0, x1, y1) = (x0 as f32, y0 as f32, x1 as f32, y1 as f32);
+
+    // offset slightly to get around weird missing pixels
+    let start = Position(x0 - 0.5, y0 - 0.5, 0.0);
+    let end = Position(x1, y1, 0.0);
+
+    [
+        Vertex::with_color(start, color),
+        Vertex::with_color(end, color),
+    ]
+}
+
+fn point_vertices(points: &[(Point, Color)]) -> Vec<Vertex> {
+    points
+        .iter()
+        .map(|(point, color)| {
+            Vertex::with_color(Position(point.x as f32, point.y as f32, 0.0), *color)
+        })
+        .collect()
+}
+
+/// Line segments between each consecutive pair of `points`
+fn line_strip_vertices(points: &[Point], color: Color) -> Vec<Vertex> {
+    points
+        .windows(2)
+        .flat_map(|pair| line_vertices(pair[0].x, pair[0].y, pair[1].x, pair[1].y, color))
+        .collect()
+}
+
+/// Two triangles covering `rect`
+fn rect_fill_vertices(rect: Rect, color: Color) -> [Vertex; 6] {
+    let (x, y, w, h) = (rect.x as f32, rect.y as f32, rect.w as f32, rect.h as f32);
+
+    let top_left = Position(x, y, 0.0);
+    let top_right = Position(x + w, y, 0.0);
+    let bottom_left = Position(x, y + h, 0.0);
+    let bottom_right = Position(x + w, y + h, 0.0);
+
+    [
+        // first triangle
+        Vertex::with_color(top_left, color),
+        Vertex::with_color(top_right, color),
+        Vertex::with_color(bottom_left, color),
+        // second triangle
+        Vertex::with_color(top_right, color),
+        Vertex::with_color(bottom_left, color),
+        Vertex::with_color(bottom_right, color),
+    ]
+}
+
+/// Points along the outline of a circle or ellipse
+fn outline_vertices(
+    center_x: i32,
+    center_y: i32,
+    points: &[(i32, i32)],
+    color: Color,
+) -> Vec<Vertex> {
+    points
+        .iter()
+        .map(|(x, y)| {
+            Vertex::with_color(
+                Position((center_x + x) as f32, (center_y + y) as f32, 0.0),
+                color,
+            )
+        })
+        .collect()
+}
+
+/// Vertical lines filling a circle or ellipse
+fn fill_outline_vertices(
+    center_x: i32,
+    center_y: i32,
+    points: &[(i32, i32)],
+    color: Color,
+) -> Vec<Vertex> {
+    points
+        .iter()
+        .filter(|(_, y)| *y >= 0) // grab upper half of circle
+        .sorted_by_key(|(x, y)| (*x, -*y)) // outermost point of each column first
+        .unique_by(|(x, _)| *x) // make sure we don't overlap any lines (messes with transparency)
+        .flat_map(|(x, y)| {
+            [
+                // start the line on upper half circle
+                Vertex::with_color(
+                    Position((center_x + x) as f32, (center_y + y) as f32, 0.0),
+                    color,
+                ),
+                // end the line on lower half circle
+                Vertex::with_color(
+                    Position((center_x + x) as f32, (center_y - y) as f32, 0.0),
+                    color,
+                ),
+            ]
+        })
+        .collect()
+}
+
+/// Two triangles covering `draw_rect`, textured with the corner `uvs`
+/// (top left, top right, bottom left, bottom right)
+fn texture_quad_vertices(draw_rect: Rect, uvs: [TextureUV; 4], blend_color: Color) -> [Vertex; 6] {
+    let (draw_x, draw_y, draw_w, draw_h) = (
+        draw_rect.x as f32,
+        draw_rect.y as f32,
+        draw_rect.w as f32,
+        draw_rect.h as f32,
+    );
+
+    let top_left_xy = Position(draw_x, draw_y, 0.0);
+    let top_right_xy = Position(draw_x + draw_w, draw_y, 0.0);
+    let bottom_left_xy = Position(draw_x, draw_y + draw_h, 0.0);
+    let bottom_right_xy = Position(draw_x + draw_w, draw_y + draw_h, 0.0);
+
+    let [top_left_uv, top_right_uv, bottom_left_uv, bottom_right_uv] = uvs;
+
+    [
+        // first triangle
+        Vertex::new(top_left_xy, blend_color, top_left_uv),
+        Vertex::new(top_right_xy, blend_color, top_right_uv),
+        Vertex::new(bottom_left_xy, blend_color, bottom_left_uv),
+        // second triangle
+        Vertex::new(top_right_xy, blend_color, top_right_uv),
+        Vertex::new(bottom_left_xy, blend_color, bottom_left_uv),
+        Vertex::new(bottom_right_xy, blend_color, bottom_right_uv),
+    ]
+}
+
+/// Pairs each section with the index of its first vertex, sorted by layer.
+/// The sort is stable, so sections on the same layer keep submission order.
+fn section_draw_order(sections: &[VertexSection]) -> Vec<(usize, &VertexSection)> {
+    let mut start = 0;
+    let mut ordered_sections = Vec::with_capacity(sections.len());
+    for section in sections {
+        ordered_sections.push((start, section));
+        start += section.length;
+    }
+    ordered_sections.sort_by_key(|(_, section)| section.layer);
+    ordered_sections
+}
+
+/// The texture UVs of the corners of `clip_rect` as (top left, top right,
+/// bottom left, bottom right). Parts of the clip rect outside of the texture
+/// give UVs outside of [0, 1].
+fn clip_rect_uvs(clip_rect: Rect, texture_width: u32, texture_height: u32) -> [TextureUV; 4] {
+    let (tex_w, tex_h) = (texture_width as f32, texture_height as f32);
+    let (clip_x, clip_y, clip_w, clip_h) = (
+        clip_rect.x as f32,
+        clip_rect.y as f32,
+        clip_rect.w as f32,
+        clip_rect.h as f32,
+    );
+    [
+        TextureUV(clip_x / tex_w, 1.0 - clip_y / tex_h),
+        TextureUV((clip_x + clip_w) / tex_w, 1.0 - clip_y / tex_h),
+        TextureUV(clip_x / tex_w, 1.0 - (clip_y + clip_h) / tex_h),
+        TextureUV((clip_x + clip_w) / tex_w, 1.0 - (clip_y + clip_h) / tex_h),
+    ]
+}
+
+/// The part of an infinitely repeated texture that fills `draw_rect` at the
+/// texture's original size.
+fn tiled_clip_rect(draw_rect: Rect, scroll_offset: Point) -> Rect {
+    Rect {
+        x: scroll_offset.x,
+        y: scroll_offset.y,
+        w: draw_rect.w,
+        h: draw_rect.h,
+    }
+}
+
+/// Splits a texture and the rect it's drawn to into nine (source, destination)
+/// rect pairs, in row order starting from the top left corner. Borders that
+/// don't fit the rect together are shrunk to fit, keeping their proportions.
+fn nine_slice_rects(
+    texture_size: Dimension,
+    draw_rect: Rect,
+    (left, right, top, bottom): (u32, u32, u32, u32),
+) -> [(Rect, Rect); 9] {
+    let slices = |start: i32, length: u32, first: u32, last: u32| {
+        let (first, last) = if first + last > length {
+            let fitted_first = first * length / (first + last);
+            (fitted_first, length - fitted_first)
+        } else {
+            (first, last)
+        };
+        let middle = length - first - last;
+        [
+            (start, first),
+            (start + first as i32, middle),
+            (start + (first + middle) as i32, last),
+        ]
+    };
+    let src_columns = slices(0, texture_size.width, left, right);
+    let src_rows = slices(0, texture_size.height, top, bottom);
+    let dst_columns = slices(draw_rect.x, draw_rect.w, left, right);
+    let dst_rows = slices(draw_rect.y, draw_rect.h, top, bottom);
+
+    let mut rects = [(Rect::default(), Rect::default()); 9];
+    for row in 0..3 {
+        for column in 0..3 {
+            let (src_x, src_w) = src_columns[column];
+            let (src_y, src_h) = src_rows[row];
+            let (dst_x, dst_w) = dst_columns[column];
+            let (dst_y, dst_h) = dst_rows[row];
+            rects[row * 3 + column] = (
+                Rect {
+                    x: src_x,
+                    y: src_y,
+                    w: src_w,
+                    h: src_h,
+                },
+                Rect {
+                    x: dst_x,
+                    y: dst_y,
+                    w: dst_w,
+                    h: dst_h,
+                },
+            );
+        }
+    }
+    rects
+}
+
+fn compile_shader(src: &str, ty: GLenum) -> Result<Shader, ShaderError> {
+    let c_str = CString::new(src.as_bytes())
+        .map_err(|_| ShaderError::CompileError("shader source contains null byte".to_owned()))?;
 
     unsafe {
-        shader = gl::CreateShader(ty);
         // Attempt to compile the shader
-        let c_str = CString::new(src.as_bytes()).unwrap();
-        gl::ShaderSource(shader, 1, &c_str.as_ptr(), std::ptr::null());
-        gl::CompileShader(shader);
+        let shader = Shader(gl::CreateShader(ty));
+        gl::ShaderSource(shader.0, 1, &c_str.as_ptr(), std::ptr::null());
+        gl::CompileShader(shader.0);
 
         // Get the compile status
         let mut status = gl::FALSE as GLint;
-        gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut status);
+        gl::GetShaderiv(shader.0, gl::COMPILE_STATUS, &mut status);
 
-        // Fail on error
+        // Return info log on error
         if status != (gl::TRUE as GLint) {
             let mut len = 0;
-            gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut len);
-            let mut buf = Vec::with_capacity(len as usize);
-            buf.set_len((len as usize) - 1); // subtract 1 to skip the trailing null character
+            gl::GetShaderiv(shader.0, gl::INFO_LOG_LENGTH, &mut len);
+            let mut buf = vec![0u8; len as usize];
             gl::GetShaderInfoLog(
-                shader,
+                shader.0,
                 len,
                 std::ptr::null_mut(),
                 buf.as_mut_ptr() as *mut GLchar,
             );
-            panic!(
-                "{}",
-                std::str::from_utf8(&buf)
-                    .ok()
-                    .expect("ShaderInfoLog not valid utf8")
-            );
+            return Err(ShaderError::CompileError(info_log_to_string(buf)));
         }
 
         assert_no_gl_error!();
-    }
 
-    Shader(shader)
+        Ok(shader)
+    }
 }
 
-fn link_program(vs: &Shader, fs: &Shader) -> ShaderProgram {
+fn link_program(vs: &Shader, fs: &Shader) -> Result<ShaderProgram, ShaderError> {
     unsafe {
-        let program = gl::CreateProgram();
-        gl::AttachShader(program, vs.0);
-        gl::AttachShader(program, fs.0);
-        gl::LinkProgram(program);
+        let program = ShaderProgram(gl::CreateProgram());
+        gl::AttachShader(program.0, vs.0);
+        gl::AttachShader(program.0, fs.0);
+        gl::LinkProgram(program.0);
         // Get the link status
         let mut status = gl::FALSE as GLint;
-        gl::GetProgramiv(program, gl::LINK_STATUS, &mut status);
+        gl::GetProgramiv(program.0, gl::LINK_STATUS, &mut status);
 
-        // Fail on error
+        // Return info log on error
         if status != (gl::TRUE as GLint) {
             let mut len: GLint = 0;
-            gl::GetProgramiv(program, gl::INFO_LOG_LENGTH, &mut len);
-            let mut buf = Vec::with_capacity(len as usize);
-            buf.set_len((len as usize) - 1); // subtract 1 to skip the trailing null character
+            gl::GetProgramiv(program.0, gl::INFO_LOG_LENGTH, &mut len);
+            let mut buf = vec![0u8; len as usize];
             gl::GetProgramInfoLog(
-                program,
+                program.0,
                 len,
                 std::ptr::null_mut(),
                 buf.as_mut_ptr() as *mut GLchar,
             );
-            panic!(
-                "{}",
-                std::str::from_utf8(&buf)
-                    .ok()
-                    .expect("ProgramInfoLog not valid utf8")
-            );
+            return Err(ShaderError::LinkError(info_log_to_string(buf)));
         }
 
         assert_no_gl_error!();
 
-        ShaderProgram(program)
+        Ok(program)
+    }
+}
+
+fn info_log_to_string(mut buf: Vec<u8>) -> String {
+    // skip the trailing null character
+    if buf.last() == Some(&0) {
+        buf.pop();
     }
+    String::from_utf8_lossy(&buf).into_owned()
 }
 
 fn size_of_buf<T>(buf: &[T]) -> usize {
@@ -997,6 +1829,54 @@ fn new_texture(_gl: &GLContext) -> u32 {
     texture_id
 }
 
+/// Fragment shader of an upscaler, None for upscalers using the regular
+/// shader program
+fn upscaler_fragment_shader_src(upscaler: CanvasUpscaler) -> Option<&'static str> {
+    match upscaler {
+        CanvasUpscaler::Nearest => None,
+        CanvasUpscaler::Scale2x => Some(SCALE2X_FRAGMENT_SHADER_SRC),
+    }
+}
+
+fn build_upscaler_program(fragment_shader_src: &str) -> Result<UpscalerProgram, ShaderError> {
+    let vertex_shader = compile_shader(VERTEX_SHADER_SRC, gl::VERTEX_SHADER)?;
+    let fragment_shader = compile_shader(fragment_shader_src, gl::FRAGMENT_SHADER)?;
+    let program = link_program(&vertex_shader, &fragment_shader)?;
+    Ok(UpscalerProgram {
+        program,
+        _vertex_shader: vertex_shader,
+        _fragment_shader: fragment_shader,
+    })
+}
+
+fn set_projection_uniform(program: u32, left: f32, right: f32, bottom: f32, top: f32) {
+    let projection = Mat4::orthographic_lh(left, right, bottom, top, -1.0, 1.0);
+    unsafe {
+        let projection_name = CString::new("projection").unwrap();
+        let location = gl::GetUniformLocation(program, projection_name.as_ptr());
+        gl::UniformMatrix4fv(location, 1, gl::FALSE, &projection.to_cols_array()[0]);
+    }
+}
+
+fn set_color_key_uniform(program: u32, color_key: Color) {
+    let [r, g, b, a] = color_key.to_f32_array();
+    set_uniform_vec4f(program, "color_key", r, g, b, a);
+}
+
+fn set_color_mod_uniforms(program: u32, color_mod: ColorMod) {
+    let ([mr, mg, mb, ma], [ar, ag, ab, aa]) = color_mod.uniform_values();
+    set_uniform_vec4f(program, "color_mult", mr, mg, mb, ma);
+    set_uniform_vec4f(program, "color_add", ar, ag, ab, aa);
+}
+
+fn set_uniform_vec2f(program: u32, name: &str, v0: f32, v1: f32) {
+    unsafe {
+        let name_cstr = CString::new(name).unwrap();
+        let location = gl::GetUniformLocation(program, name_cstr.as_ptr());
+        gl::Uniform2f(location, v0, v1);
+    }
+}
+
 fn set_uniform_vec4f(program: u32, name: &str, v0: f32, v1: f32, v2: f32, v3: f32) {
     unsafe {
         let name_cstr = CString::new(name).unwrap();
@@ -1042,6 +1922,27 @@ fn set_texture_image(texture_id: u32, width: u32, height: u32, rgba_data: Option
     }
 }
 
+/// Replaces the pixels of a texture without reallocating its storage. The
+/// texture must already have storage of `width` x `height`.
+fn update_texture_image(texture_id: u32, width: u32, height: u32, rgba_data: &[u8]) {
+    unsafe {
+        gl::BindTexture(gl::TEXTURE_2D, texture_id);
+        gl::TexSubImage2D(
+            gl::TEXTURE_2D,
+            0,
+            0,
+            0,
+            width as i32,
+            height as i32,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            rgba_data.as_ptr() as *const c_void,
+        );
+        assert_no_gl_error!();
+        gl::BindTexture(gl::TEXTURE_2D, 0);
+    }
+}
+
 fn set_framebuffer_texture(fbo: u32, texture: u32) {
     unsafe {
         gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
@@ -1065,3 +1966,980 @@ fn set_framebuffer_texture(fbo: u32, texture: u32) {
         gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::{point, rect};
+
+    #[derive(Default)]
+    struct PresentCallCounter {
+        clears: u32,
+        canvas_draws: u32,
+    }
+
+    impl PresentCalls for PresentCallCounter {
+        fn clear_window(&mut self) {
+            self.clears += 1;
+        }
+
+        fn draw_canvas(&mut self) {
+            self.canvas_draws += 1;
+        }
+    }
+
+    fn present_call_counts(letterbox_visible: bool, canvas_drawn: bool) -> (u32, u32) {
+        let mut calls = PresentCallCounter::default();
+        present_canvas(&mut calls, letterbox_visible, canvas_drawn);
+        (calls.clears, calls.canvas_draws)
+    }
+
+    #[test]
+    fn visible_letterbox_is_cleared_before_drawing_canvas() {
+        assert_eq!(present_call_counts(true, true), (1, 1));
+    }
+
+    #[test]
+    fn hidden_letterbox_is_not_cleared() {
+        assert_eq!(present_call_counts(false, true), (0, 1));
+    }
+
+    #[parameterized(letterbox_visible = { true, false })]
+    fn window_is_cleared_when_canvas_is_not_drawn(letterbox_visible: bool) {
+        assert_eq!(present_call_counts(letterbox_visible, false), (1, 0));
+    }
+
+    #[test]
+    fn only_nearest_upscaler_uses_regular_fragment_shader() {
+        assert_eq!(upscaler_fragment_shader_src(CanvasUpscaler::Nearest), None);
+        assert_eq!(
+            upscaler_fragment_shader_src(CanvasUpscaler::Scale2x),
+            Some(SCALE2X_FRAGMENT_SHADER_SRC)
+        );
+    }
+
+    fn vertex_positions(vertices: &[Vertex]) -> Vec<(f32, f32)> {
+        vertices
+            .iter()
+            .map(|vertex| (vertex.pos.x, vertex.pos.y))
+            .collect()
+    }
+
+    #[test]
+    fn sampled_pixels_match_loaded_image() {
+        let path = std::env::temp_dir().join("engine_sample_texture_test.png");
+        let image = image::RgbaImage::from_fn(2, 2, |x, y| match (x, y) {
+            (0, 0) => image::Rgba([255, 0, 0, 255]),
+            (1, 0) => image::Rgba([0, 255, 0, 255]),
+            (0, 1) => image::Rgba([0, 0, 255, 255]),
+            _ => image::Rgba([255, 255, 255, 0]),
+        });
+        image.save(&path).unwrap();
+
+        let (data, width, height) = load_image_rgba(&path).unwrap();
+        let sample = |x, y| sample_rgba(&data, width, height, x, y);
+
+        assert_eq!(sample(0, 0), Some(Color::rgba(255, 0, 0, 255)));
+        assert_eq!(sample(1, 0), Some(Color::rgba(0, 255, 0, 255)));
+        assert_eq!(sample(0, 1), Some(Color::rgba(0, 0, 255, 255)));
+        assert_eq!(sample(1, 1), Some(Color::rgba(255, 255, 255, 0)));
+    }
+
+    #[parameterized(x = { 2, 0, 5 }, y = { 0, 2, 5 })]
+    fn sampling_outside_texture_is_none(x: u32, y: u32) {
+        let data = [0; 2 * 2 * 4];
+
+        assert_eq!(sample_rgba(&data, 2, 2, x, y), None);
+    }
+
+    #[test]
+    fn color_keyed_pixels_become_transparent() {
+        #[rustfmt::skip]
+        let mut data = [
+            255, 0, 255, 255,
+            255, 0, 254, 255,
+            0, 0, 0, 255,
+            255, 0, 255, 128,
+        ];
+
+        apply_color_key(&mut data, Color::MAGENTA);
+
+        #[rustfmt::skip]
+        assert_eq!(data, [
+            255, 0, 255, 0,
+            255, 0, 254, 255,
+            0, 0, 0, 255,
+            255, 0, 255, 0,
+        ]);
+    }
+
+    #[test]
+    fn default_color_mod_leaves_texels_unchanged() {
+        let (mult, add) = ColorMod::default().uniform_values();
+
+        assert_eq!(mult, [1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(add, [0.0, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn color_mod_uniforms_are_normalized_colors() {
+        let color_mod = ColorMod {
+            mult: Color::rgba(255, 0, 51, 255),
+            add: Color::rgba(255, 255, 255, 0),
+        };
+
+        let (mult, add) = color_mod.uniform_values();
+
+        assert_eq!(mult, [1.0, 0.0, 0.2, 1.0]);
+        assert_eq!(add, [1.0, 1.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn rect_fill_is_two_triangles_covering_rect() {
+        let vertices = rect_fill_vertices(rect(10, 20, 30, 40), Color::RED);
+
+        assert_eq!(
+            vertex_positions(&vertices),
+            vec![
+                (10.0, 20.0),
+                (40.0, 20.0),
+                (10.0, 60.0),
+                (40.0, 20.0),
+                (10.0, 60.0),
+                (40.0, 60.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn rect_fill_vertices_use_draw_color() {
+        let vertices = rect_fill_vertices(rect(0, 0, 1, 1), Color::RED);
+
+        assert!(vertices.iter().all(|vertex| vertex.color
+            == VertexColor {
+                r: 1.0,
+                g: 0.0,
+                b: 0.0,
+                a: 1.0
+            }));
+    }
+
+    /// A 320x180 canvas scaled up 3x and offset into a larger window
+    fn scaled_canvas() -> Canvas {
+        Canvas {
+            pos: glam::ivec2(37, 12),
+            size: Dimension {
+                width: 320,
+                height: 180,
+            },
+            scaled_size: Dimension {
+                width: 960,
+                height: 540,
+            },
+            scale: 3.0,
+            ..Default::default()
+        }
+    }
+
+    #[parameterized(window_size = {
+        (100.0, 100.0), (319.0, 1000.0), (0.0, 0.0)
+    })]
+    fn canvas_scale_is_at_least_one_in_small_windows(window_size: (f32, f32)) {
+        let canvas = scaled_canvas();
+
+        let scale = canvas.calculate_scale(window_size.0, window_size.1);
+
+        assert_eq!(scale, 1.0);
+    }
+
+    #[test]
+    fn canvas_scale_is_capped_by_max_scale() {
+        let canvas = Canvas {
+            max_scale: Some(4),
+            ..scaled_canvas()
+        };
+
+        let scale = canvas.calculate_scale(7680.0, 4320.0);
+
+        assert_eq!(scale, 4.0);
+    }
+
+    #[test]
+    fn canvas_scale_is_not_capped_without_max_scale() {
+        let canvas = scaled_canvas();
+
+        let scale = canvas.calculate_scale(7680.0, 4320.0);
+
+        assert_eq!(scale, 24.0);
+    }
+
+    #[test]
+    fn canvas_moved_through_mutable_reference_maps_from_new_position() {
+        let mut canvas = scaled_canvas();
+        let canvas_mut = &mut canvas;
+
+        canvas_mut.pos = glam::ivec2(12, 34);
+        canvas_mut.scale = 2.0;
+
+        assert_eq!(canvas.window_to_canvas(point(16, 40)), point(2, 3));
+    }
+
+    #[test]
+    fn updated_canvas_is_centered_in_window() {
+        let mut canvas = scaled_canvas();
+
+        canvas.update(1280.0, 800.0);
+
+        assert_eq!(canvas.scale, 4.0);
+        assert_eq!(
+            canvas.scaled_size,
+            Dimension {
+                width: 1280,
+                height: 720
+            }
+        );
+        assert_eq!(canvas.pos, glam::ivec2(0, 40));
+    }
+
+    #[parameterized(canvas_pos = {
+        (0, 0), (1, 2), (319, 179), (-4, 200)
+    })]
+    fn canvas_position_round_trips_through_window_position(canvas_pos: (i32, i32)) {
+        let canvas = scaled_canvas();
+        let canvas_pos = point(canvas_pos.0, canvas_pos.1);
+
+        let window_pos = canvas.canvas_to_window(canvas_pos);
+
+        assert_eq!(canvas.window_to_canvas(window_pos), canvas_pos);
+    }
+
+    #[test]
+    fn window_positions_inside_a_scaled_pixel_map_to_that_pixel() {
+        let canvas = scaled_canvas();
+
+        assert_eq!(canvas.canvas_to_window(point(1, 2)), point(40, 18));
+        assert_eq!(canvas.window_to_canvas(point(40, 18)), point(1, 2));
+        assert_eq!(canvas.window_to_canvas(point(42, 20)), point(1, 2));
+        assert_eq!(canvas.window_to_canvas(point(36, 11)), point(-1, -1));
+    }
+
+    #[test]
+    fn line_start_is_offset_by_half_a_pixel() {
+        let vertices = line_vertices(1, 2, 3, 4, Color::BLACK);
+
+        assert_eq!(vertex_positions(&vertices), vec![(0.5, 1.5), (3.0, 4.0)]);
+    }
+
+    #[test]
+    fn each_point_gets_its_own_color() {
+        let points = [
+            (point(0, 0), Color::RED),
+            (point(1, 0), Color::GREEN),
+            (point(0, 1), Color::BLUE),
+        ];
+
+        let vertices = point_vertices(&points);
+
+        assert_eq!(
+            vertices,
+            vec![
+                Vertex::with_color(Position(0.0, 0.0, 0.0), Color::RED),
+                Vertex::with_color(Position(1.0, 0.0, 0.0), Color::GREEN),
+                Vertex::with_color(Position(0.0, 1.0, 0.0), Color::BLUE),
+            ]
+        );
+    }
+
+    #[test]
+    fn line_strip_has_one_segment_per_consecutive_point_pair() {
+        let points = [point(0, 0), point(10, 0), point(10, 10), point(0, 10)];
+
+        let vertices = line_strip_vertices(&points, Color::WHITE);
+
+        assert_eq!(
+            vertex_positions(&vertices),
+            vec![
+                (-0.5, -0.5),
+                (10.0, 0.0),
+                (9.5, -0.5),
+                (10.0, 10.0),
+                (9.5, 9.5),
+                (0.0, 10.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn texture_quad_corners_get_matching_uvs() {
+        let vertices = texture_quad_vertices(rect(0, 0, 8, 8), FULL_TEXTURE_UVS, Color::WHITE);
+        let uvs = vertices
+            .iter()
+            .map(|vertex| (vertex.texture_uv.u, vertex.texture_uv.v))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            uvs,
+            vec![
+                (0.0, 1.0),
+                (1.0, 1.0),
+                (0.0, 0.0),
+                (1.0, 1.0),
+                (0.0, 0.0),
+                (1.0, 0.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn pixel_buffer_quad_covers_dest_rect_with_first_row_on_top() {
+        let vertices = texture_quad_vertices(rect(4, 2, 16, 8), PIXEL_BUFFER_UVS, Color::WHITE);
+        let uvs = vertices
+            .iter()
+            .map(|vertex| (vertex.texture_uv.u, vertex.texture_uv.v))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vertex_positions(&vertices),
+            vec![
+                (4.0, 2.0),
+                (20.0, 2.0),
+                (4.0, 10.0),
+                (20.0, 2.0),
+                (4.0, 10.0),
+                (20.0, 10.0),
+            ]
+        );
+        assert_eq!(
+            uvs,
+            vec![
+                (0.0, 0.0),
+                (1.0, 0.0),
+                (0.0, 1.0),
+                (1.0, 0.0),
+                (0.0, 1.0),
+                (1.0, 1.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn flipping_rows_puts_last_row_first() {
+        let rgba_data = [1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4];
+
+        assert_eq!(
+            flip_rows(&rgba_data, 2),
+            vec![3, 3, 3, 3, 4, 4, 4, 4, 1, 1, 1, 1, 2, 2, 2, 2]
+        );
+    }
+
+    #[test]
+    fn new_pixel_buffer_texture_needs_storage() {
+        assert!(needs_texture_storage(None, 4, 4));
+    }
+
+    #[test]
+    fn same_size_pixel_buffer_reuses_storage() {
+        assert!(!needs_texture_storage(Some((4, 4)), 4, 4));
+    }
+
+    #[parameterized(size = { (8, 4), (4, 8), (2, 2) })]
+    fn resized_pixel_buffer_needs_new_storage(size: (u32, u32)) {
+        let (width, height) = size;
+
+        assert!(needs_texture_storage(Some((4, 4)), width, height));
+    }
+
+    #[test]
+    fn pixel_buffer_of_matching_size_is_accepted() {
+        assert_eq!(check_pixel_buffer_size(4 * 3 * 4, 4, 3), Ok(()));
+    }
+
+    #[parameterized(len = { 0, 4 * 3 * 4 - 1, 4 * 3 * 4 + 4, 4 * 3 * 3 })]
+    fn pixel_buffer_of_wrong_size_is_an_error(len: usize) {
+        assert_eq!(
+            check_pixel_buffer_size(len, 4, 3),
+            Err(BlitError::BufferSizeMismatch {
+                expected: 48,
+                actual: len,
+            })
+        );
+    }
+
+    fn section_on_layer(length: usize, layer: i32) -> VertexSection {
+        VertexSection {
+            length,
+            primitive: PrimitiveType::Triangle,
+            texture_id: 0,
+            color_key: Color::TRANSPARENT,
+            color_mod: ColorMod::default(),
+            camera: None,
+            layer,
+        }
+    }
+
+    #[test]
+    fn sections_are_drawn_in_layer_order_keeping_submission_order_within_layer() {
+        let sections = [
+            section_on_layer(6, 1),  // vertices 0..6
+            section_on_layer(2, 0),  // vertices 6..8
+            section_on_layer(3, -1), // vertices 8..11
+            section_on_layer(1, 1),  // vertices 11..12
+            section_on_layer(6, 0),  // vertices 12..18
+        ];
+
+        let order = section_draw_order(&sections)
+            .into_iter()
+            .map(|(start, section)| (start, section.layer))
+            .collect::<Vec<(usize, i32)>>();
+
+        assert_eq!(order, vec![(8, -1), (6, 0), (12, 0), (0, 1), (11, 1)]);
+    }
+
+    #[test]
+    fn loading_missing_image_returns_error_with_path() {
+        let path = Path::new("does/not/exist.png");
+
+        let result = load_image_rgba(path);
+
+        assert!(
+            matches!(result, Err(LoadError::ImageError { path: error_path, .. }) if error_path == path)
+        );
+    }
+
+    #[test]
+    fn tiled_texture_uvs_repeat_texture_across_larger_draw_rect() {
+        // 16x16 texture repeated 2.5 times horizontally and 2 times vertically
+        let draw_rect = rect(100, 50, 40, 32);
+
+        let clip_rect = tiled_clip_rect(draw_rect, point(0, 0));
+        let uvs = clip_rect_uvs(clip_rect, 16, 16);
+
+        assert_eq!(
+            uvs,
+            [
+                TextureUV(0.0, 1.0),
+                TextureUV(2.5, 1.0),
+                TextureUV(0.0, -1.0),
+                TextureUV(2.5, -1.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn tiled_texture_uvs_are_shifted_by_scroll_offset() {
+        let draw_rect = rect(0, 0, 32, 16);
+
+        let clip_rect = tiled_clip_rect(draw_rect, point(8, -4));
+        let uvs = clip_rect_uvs(clip_rect, 16, 16);
+
+        assert_eq!(
+            uvs,
+            [
+                TextureUV(0.5, 1.25),
+                TextureUV(2.5, 1.25),
+                TextureUV(0.5, 0.25),
+                TextureUV(2.5, 0.25),
+            ]
+        );
+    }
+
+    #[test]
+    fn nine_slice_corners_keep_size_while_edges_and_center_stretch() {
+        //  texture (12x12)      draw rect (40x20 at 100,50)
+        //  ┌──┬──────┬────┐    ┌──┬────────────────────────────┬────┐
+        //  ├──┼──────┼────┤    ├──┼────────────────────────────┼────┤
+        //  │  │      │    │    │  │                            │    │
+        //  ├──┼──────┼────┤    ├──┼────────────────────────────┼────┤
+        //  └──┴──────┴────┘    └──┴────────────────────────────┴────┘
+        let texture_size = Dimension {
+            width: 12,
+            height: 12,
+        };
+        let draw_rect = rect(100, 50, 40, 20);
+        let border = (2, 4, 3, 5); // left, right, top, bottom
+
+        let rects = nine_slice_rects(texture_size, draw_rect, border);
+
+        let expected = [
+            // top row
+            (rect(0, 0, 2, 3), rect(100, 50, 2, 3)),
+            (rect(2, 0, 6, 3), rect(102, 50, 34, 3)),
+            (rect(8, 0, 4, 3), rect(136, 50, 4, 3)),
+            // middle row
+            (rect(0, 3, 2, 4), rect(100, 53, 2, 12)),
+            (rect(2, 3, 6, 4), rect(102, 53, 34, 12)),
+            (rect(8, 3, 4, 4), rect(136, 53, 4, 12)),
+            // bottom row
+            (rect(0, 7, 2, 5), rect(100, 65, 2, 5)),
+            (rect(2, 7, 6, 5), rect(102, 65, 34, 5)),
+            (rect(8, 7, 4, 5), rect(136, 65, 4, 5)),
+        ];
+        assert_eq!(rects, expected);
+    }
+
+    #[test]
+    fn nine_slice_center_collapses_when_draw_rect_is_smaller_than_border() {
+        let texture_size = Dimension {
+            width: 12,
+            height: 12,
+        };
+        let draw_rect = rect(0, 0, 4, 4);
+        let border = (3, 3, 3, 3);
+
+        let rects = nine_slice_rects(texture_size, draw_rect, border);
+
+        let (_, center) = rects[4];
+        assert_eq!((center.w, center.h), (0, 0));
+    }
+
+    #[test]
+    fn nine_slice_borders_shrink_to_fit_draw_rect_smaller_than_border() {
+        let texture_size = Dimension {
+            width: 12,
+            height: 12,
+        };
+        let draw_rect = rect(100, 50, 6, 4);
+        let border = (2, 4, 3, 5); // left, right, top, bottom
+
+        let rects = nine_slice_rects(texture_size, draw_rect, border);
+
+        let destinations = rects.map(|(_, destination)| destination);
+        let expected = [
+            // top row
+            rect(100, 50, 2, 1),
+            rect(102, 50, 0, 1),
+            rect(102, 50, 4, 1),
+            // middle row
+            rect(100, 51, 2, 0),
+            rect(102, 51, 0, 0),
+            rect(102, 51, 4, 0),
+            // bottom row
+            rect(100, 51, 2, 3),
+            rect(102, 51, 0, 3),
+            rect(102, 51, 4, 3),
+        ];
+        assert_eq!(destinations, expected);
+    }
+
+    #[parameterized(severity = {
+        gl::DEBUG_SEVERITY_HIGH, gl::DEBUG_SEVERITY_MEDIUM, gl::DEBUG_SEVERITY_LOW
+    }, expected_level = {
+        log::Level::Error, log::Level::Error, log::Level::Warn
+    })]
+    fn debug_messages_are_logged_with_severity_level(severity: GLenum, expected_level: log::Level) {
+        let level = debug_message_log_level(severity, GlDebugSeverity::Low);
+
+        assert_eq!(level, Some(expected_level));
+    }
+
+    #[parameterized(min_severity = {
+        GlDebugSeverity::Low, GlDebugSeverity::Medium, GlDebugSeverity::High
+    })]
+    fn debug_notifications_are_never_logged(min_severity: GlDebugSeverity) {
+        let level = debug_message_log_level(gl::DEBUG_SEVERITY_NOTIFICATION, min_severity);
+
+        assert_eq!(level, None);
+    }
+
+    #[parameterized(severity = {
+        gl::DEBUG_SEVERITY_LOW, gl::DEBUG_SEVERITY_MEDIUM
+    })]
+    fn debug_messages_below_min_severity_are_not_logged(severity: GLenum) {
+        let level = debug_message_log_level(severity, GlDebugSeverity::High);
+
+        assert_eq!(level, None);
+    }
+}
+
+#[cfg(all(test, feature = "gl-tests"))]
+pub(crate) mod gl_tests {
+    use super::*;
+    use crate::geometry::point;
+
+    pub(crate) struct TestGlContext {
+        _sdl: sdl2::Sdl,
+        _window: sdl2::video::Window,
+        pub(crate) gl_context: GLContext,
+    }
+
+    /// Creates a hidden window with an OpenGL context, for tests of code
+    /// that needs one
+    pub(crate) fn init_test_gl_context() -> TestGlContext {
+        let sdl = sdl2::init().unwrap();
+        let sdl_video = sdl.video().unwrap();
+        let gl_attr = sdl_video.gl_attr();
+        gl_attr.set_context_version(3, 3);
+        gl_attr.set_context_profile(sdl2::video::GLProfile::Core);
+        let window = sdl_video
+            .window("gl-tests", 1, 1)
+            .opengl()
+            .hidden()
+            .build()
+            .unwrap();
+        let gl_context = window.gl_create_context().unwrap();
+        gl::load_with(|s| sdl_video.gl_get_proc_address(s) as _);
+
+        TestGlContext {
+            _sdl: sdl,
+            _window: window,
+            gl_context,
+        }
+    }
+
+    fn window_pixel_after_render(letterbox_visible: bool) -> [u8; 4] {
+        let context = init_test_gl_context();
+        let mut renderer = Renderer::new(&context.gl_context, 1, 1).unwrap();
+        renderer.set_viewport_letterbox_visible(letterbox_visible);
+        renderer.set_canvas_drawn_to_window(true);
+        // Center the 1x1 canvas in a wider window, leaving the read pixel
+        // outside of it
+        renderer.on_window_resize(3, 1);
+        let mut pixel = [0u8; 4];
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl::ClearColor(1.0, 0.0, 0.0, 1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+            renderer.render(&context.gl_context);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl::ReadPixels(
+                0,
+                0,
+                1,
+                1,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixel.as_mut_ptr() as *mut std::ffi::c_void,
+            );
+        }
+        pixel
+    }
+
+    #[test]
+    fn color_key_of_last_section_is_not_applied_to_presented_canvas() {
+        let context = init_test_gl_context();
+        let mut renderer = Renderer::new(&context.gl_context, 1, 1).unwrap();
+        renderer.set_viewport_letterbox_visible(false);
+        renderer.set_canvas_drawn_to_window(true);
+        let texture = renderer.add_texture(&context.gl_context, &[0, 0, 0, 255], 1, 1);
+        // keys out the black canvas too, unless reset before presenting
+        renderer.set_color_key(Color::BLACK);
+        renderer.draw_texture(texture, crate::geometry::rect(0, 0, 1, 1), None);
+        let mut pixel = [0u8; 4];
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl::ClearColor(1.0, 0.0, 0.0, 1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+            renderer.render(&context.gl_context);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl::ReadPixels(
+                0,
+                0,
+                1,
+                1,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixel.as_mut_ptr() as *mut std::ffi::c_void,
+            );
+        }
+
+        assert_eq!(pixel, [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn visible_letterbox_clears_window() {
+        assert_eq!(window_pixel_after_render(true), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn hidden_letterbox_keeps_window_contents() {
+        assert_eq!(window_pixel_after_render(false), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn nearest_upscaler_uses_regular_program() {
+        let context = init_test_gl_context();
+        let mut renderer = Renderer::new(&context.gl_context, 1, 1).unwrap();
+
+        renderer
+            .set_canvas_upscaler(CanvasUpscaler::Scale2x)
+            .unwrap();
+        renderer
+            .set_canvas_upscaler(CanvasUpscaler::Nearest)
+            .unwrap();
+
+        assert_eq!(renderer.canvas_upscaler(), CanvasUpscaler::Nearest);
+        assert_eq!(renderer.canvas_program(), renderer.shader.program.0);
+    }
+
+    #[test]
+    fn upscaler_program_is_compiled_once() {
+        let context = init_test_gl_context();
+        let mut renderer = Renderer::new(&context.gl_context, 1, 1).unwrap();
+
+        renderer
+            .set_canvas_upscaler(CanvasUpscaler::Scale2x)
+            .unwrap();
+        let program = renderer.canvas_program();
+        renderer
+            .set_canvas_upscaler(CanvasUpscaler::Nearest)
+            .unwrap();
+        renderer
+            .set_canvas_upscaler(CanvasUpscaler::Scale2x)
+            .unwrap();
+
+        assert_eq!(renderer.canvas_upscaler(), CanvasUpscaler::Scale2x);
+        assert_eq!(renderer.canvas_program(), program);
+        assert_ne!(program, renderer.shader.program.0);
+        assert_eq!(renderer.upscaling.programs.len(), 1);
+    }
+
+    #[test]
+    fn sampleable_texture_keeps_pixels_when_reloaded() {
+        let context = init_test_gl_context();
+        let mut renderer = Renderer::new(&context.gl_context, 1, 1).unwrap();
+        let path = std::env::temp_dir().join("engine_sampleable_texture_gl_test.png");
+        let image = image::RgbaImage::from_fn(2, 1, |x, _| match x {
+            0 => image::Rgba([255, 0, 0, 255]),
+            _ => image::Rgba([0, 255, 0, 255]),
+        });
+        image.save(&path).unwrap();
+
+        let id = load_sampleable_texture(&context.gl_context, &mut renderer, &path).unwrap();
+        assert_eq!(
+            renderer.sample_texture(id, 1, 0),
+            Some(Color::rgba(0, 255, 0, 255))
+        );
+
+        renderer.reload_texture(id, &[0, 0, 255, 255], 1, 1);
+        assert_eq!(
+            renderer.sample_texture(id, 0, 0),
+            Some(Color::rgba(0, 0, 255, 255))
+        );
+        assert_eq!(renderer.sample_texture(id, 1, 0), None);
+    }
+
+    #[test]
+    fn regular_texture_can_not_be_sampled() {
+        let context = init_test_gl_context();
+        let mut renderer = Renderer::new(&context.gl_context, 1, 1).unwrap();
+
+        let id = renderer.add_texture(&context.gl_context, &[255, 0, 0, 255], 1, 1);
+        renderer.reload_texture(id, &[0, 255, 0, 255], 1, 1);
+
+        assert_eq!(renderer.sample_texture(id, 0, 0), None);
+    }
+
+    #[test]
+    fn renderer_enables_multisampling() {
+        let context = init_test_gl_context();
+        let _renderer = Renderer::new(&context.gl_context, 1, 1).unwrap();
+
+        assert_eq!(unsafe { gl::IsEnabled(gl::MULTISAMPLE) }, gl::TRUE);
+    }
+
+    const INVALID_SHADER_SRC: &str = "#version 330 core\nvoid main() { not glsl }";
+
+    #[test]
+    fn compiling_invalid_shader_returns_error_with_log() {
+        let _context = init_test_gl_context();
+
+        let result = compile_shader(INVALID_SHADER_SRC, gl::FRAGMENT_SHADER);
+
+        assert!(matches!(result, Err(ShaderError::CompileError(log)) if !log.is_empty()));
+    }
+
+    #[test]
+    fn reloading_valid_shaders_replaces_program() {
+        let context = init_test_gl_context();
+        let mut renderer = Renderer::new(&context.gl_context, 100, 100).unwrap();
+        let program_before = renderer.shader.program.0;
+
+        let result = renderer.reload_shaders(VERTEX_SHADER_SRC, FRAGMENT_SHADER_SRC);
+
+        assert!(result.is_ok());
+        assert_ne!(renderer.shader.program.0, program_before);
+    }
+
+    #[test]
+    fn reloading_invalid_shader_returns_error_and_keeps_program() {
+        let context = init_test_gl_context();
+        let mut renderer = Renderer::new(&context.gl_context, 100, 100).unwrap();
+        let program_before = renderer.shader.program.0;
+
+        let result = renderer.reload_shaders(VERTEX_SHADER_SRC, INVALID_SHADER_SRC);
+
+        assert!(matches!(result, Err(ShaderError::CompileError(log)) if !log.is_empty()));
+        assert_eq!(renderer.shader.program.0, program_before);
+    }
+
+    #[test]
+    fn canvas_changes_through_canvas_mut_are_visible_through_canvas() {
+        let context = init_test_gl_context();
+        let mut renderer = Renderer::new(&context.gl_context, 100, 100).unwrap();
+
+        renderer.canvas_mut().pos = glam::ivec2(12, 34);
+        renderer.canvas_mut().scale = 2.0;
+
+        assert_eq!(renderer.canvas().pos, glam::ivec2(12, 34));
+        assert_eq!(renderer.canvas().scale, 2.0);
+    }
+
+    #[test]
+    fn sprite_batch_merges_sprites_into_one_section_per_texture() {
+        let context = init_test_gl_context();
+        let mut renderer = Renderer::new(&context.gl_context, 100, 100).unwrap();
+        let mut sprites = crate::graphics::sprites::SpriteSystem::new();
+        let pixels = [255; 16 * 16 * 4];
+        let first_texture = renderer.add_texture(&context.gl_context, &pixels, 16, 16);
+        let second_texture = renderer.add_texture(&context.gl_context, &pixels, 16, 16);
+        let frames = [Rect {
+            x: 0,
+            y: 0,
+            w: 16,
+            h: 16,
+        }];
+        let first_sheet = sprites.add_spritesheet(first_texture, &frames, None);
+        let second_sheet = sprites.add_spritesheet(second_texture, &frames, None);
+
+        let mut batch = sprites.begin_batch();
+        batch.draw(first_sheet, 0, 0, 0);
+        batch.draw(second_sheet, 0, 16, 0);
+        batch.draw(first_sheet, 0, 32, 0);
+        batch.draw(second_sheet, 0, 48, 0);
+        batch.flush(&mut renderer);
+
+        assert_eq!(renderer.draw.sections.len(), 2);
+        assert!(renderer
+            .draw
+            .sections
+            .iter()
+            .all(|section| section.length == 12));
+    }
+
+    #[test]
+    fn line_strip_is_drawn_as_one_section() {
+        let context = init_test_gl_context();
+        let mut renderer = Renderer::new(&context.gl_context, 100, 100).unwrap();
+        let points = [point(0, 0), point(10, 0), point(10, 10), point(0, 10)];
+
+        renderer.draw_line_strip(&points);
+
+        assert_eq!(renderer.draw.sections.len(), 1);
+        assert_eq!(renderer.draw.sections[0].length, 2 * (points.len() - 1));
+    }
+
+    #[test]
+    fn points_are_drawn_as_one_section() {
+        let context = init_test_gl_context();
+        let mut renderer = Renderer::new(&context.gl_context, 100, 100).unwrap();
+        let points = (0..1000)
+            .map(|i| (point(i % 100, i / 100), Color::WHITE))
+            .collect::<Vec<_>>();
+
+        renderer.draw_points(&points);
+
+        assert_eq!(renderer.draw.sections.len(), 1);
+        assert_eq!(renderer.draw.sections[0].length, points.len());
+        assert_eq!(renderer.draw.vertices.len(), points.len());
+    }
+
+    #[test]
+    fn blits_before_a_flush_get_separate_textures() {
+        let context = init_test_gl_context();
+        let mut renderer = Renderer::new(&context.gl_context, 100, 100).unwrap();
+        let dest_rect = Rect {
+            x: 0,
+            y: 0,
+            w: 100,
+            h: 100,
+        };
+
+        renderer
+            .blit_pixels(&context.gl_context, &[255; 4 * 4 * 4], 4, 4, dest_rect)
+            .unwrap();
+        renderer
+            .blit_pixels(&context.gl_context, &[255; 8 * 2 * 4], 8, 2, dest_rect)
+            .unwrap();
+
+        let textures = &renderer.shader.pixel_buffer_textures;
+        assert_eq!(textures.len(), 2);
+        assert_eq!(renderer.draw.sections[0].texture_id, textures[0].0);
+        assert_eq!(renderer.draw.sections[1].texture_id, textures[1].0);
+    }
+
+    #[test]
+    fn blits_after_a_flush_reuse_textures() {
+        let context = init_test_gl_context();
+        let mut renderer = Renderer::new(&context.gl_context, 100, 100).unwrap();
+        let dest_rect = Rect {
+            x: 0,
+            y: 0,
+            w: 100,
+            h: 100,
+        };
+
+        renderer
+            .blit_pixels(&context.gl_context, &[255; 4 * 4 * 4], 4, 4, dest_rect)
+            .unwrap();
+        renderer.flush_to_target(&context.gl_context);
+        renderer
+            .blit_pixels(&context.gl_context, &[0; 4 * 4 * 4], 4, 4, dest_rect)
+            .unwrap();
+
+        let textures = &renderer.shader.pixel_buffer_textures;
+        assert_eq!(textures.len(), 1);
+        assert_eq!(renderer.draw.sections[0].texture_id, textures[0].0);
+    }
+
+    #[test]
+    fn flushing_clears_pending_sections() {
+        let context = init_test_gl_context();
+        let mut renderer = Renderer::new(&context.gl_context, 100, 100).unwrap();
+        renderer.draw_points(&[(point(1, 1), Color::WHITE)]);
+        renderer.draw_line_strip(&[point(0, 0), point(10, 0)]);
+
+        renderer.flush_to_target(&context.gl_context);
+
+        assert!(renderer.draw.sections.is_empty());
+        assert!(renderer.draw.vertices.is_empty());
+    }
+
+    #[test]
+    fn later_flushes_draw_on_top_of_earlier_ones() {
+        let context = init_test_gl_context();
+        let mut renderer = Renderer::new(&context.gl_context, 100, 100).unwrap();
+        let size = renderer.canvas().size;
+        let canvas_rect = Rect {
+            x: 0,
+            y: 0,
+            w: size.width,
+            h: size.height,
+        };
+
+        renderer
+            .blit_pixels(&context.gl_context, &[255, 0, 0, 255], 1, 1, canvas_rect)
+            .unwrap();
+        renderer.flush_to_target(&context.gl_context);
+        renderer.flush_to_target(&context.gl_context);
+
+        assert_eq!(renderer.read_canvas_pixels()[..4], [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn blitting_wrong_size_buffer_draws_nothing() {
+        let context = init_test_gl_context();
+        let mut renderer = Renderer::new(&context.gl_context, 100, 100).unwrap();
+        let dest_rect = Rect {
+            x: 0,
+            y: 0,
+            w: 100,
+            h: 100,
+        };
+
+        let result = renderer.blit_pixels(&context.gl_context, &[255; 10], 4, 4, dest_rect);
+
+        assert!(matches!(result, Err(BlitError::BufferSizeMismatch { .. })));
+        assert!(renderer.draw.sections.is_empty());
+        assert!(renderer.shader.pixel_buffer_textures.is_empty());
+    }
+}
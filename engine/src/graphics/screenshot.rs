@@ -0,0 +1,81 @@
+//! Saving the canvas as timestamped PNG files
+
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+
+use crate::graphics::rendering::Renderer;
+
+/// Directory screenshots are saved to unless configured otherwise
+pub const DEFAULT_SCREENSHOT_DIR: &str = "screenshots";
+
+#[derive(Debug)]
+pub enum ScreenshotError {
+    IoError(std::io::Error),
+    ImageError(image::ImageError),
+}
+
+/// Saves the canvas contents from the last render to a new PNG file in `dir`,
+/// creating the directory if needed. Returns the path of the saved file.
+pub fn save_screenshot(renderer: &Renderer, dir: &Path) -> Result<PathBuf, ScreenshotError> {
+    std::fs::create_dir_all(dir).map_err(ScreenshotError::IoError)?;
+    let path = screenshot_path(dir, Local::now());
+    let size = renderer.canvas().size;
+    image::save_buffer(
+        &path,
+        &renderer.read_canvas_pixels(),
+        size.width,
+        size.height,
+        image::ColorType::Rgba8,
+    )
+    .map_err(ScreenshotError::ImageError)?;
+    Ok(path)
+}
+
+fn screenshot_path(dir: &Path, time: DateTime<Local>) -> PathBuf {
+    dir.join(screenshot_file_name(time))
+}
+
+/// Includes milliseconds, so that screenshots taken in quick succession
+/// don't overwrite each other
+fn screenshot_file_name(time: DateTime<Local>) -> String {
+    format!("screenshot_{}.png", time.format("%Y-%m-%d_%H-%M-%S-%3f"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn test_time() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2023, 6, 1, 9, 5, 30).unwrap() + Duration::milliseconds(42)
+    }
+
+    #[test]
+    fn file_name_is_timestamped() {
+        assert_eq!(
+            screenshot_file_name(test_time()),
+            "screenshot_2023-06-01_09-05-30-042.png"
+        );
+    }
+
+    #[test]
+    fn screenshots_in_same_second_get_different_names() {
+        let later = test_time() + Duration::milliseconds(1);
+
+        assert_ne!(
+            screenshot_file_name(test_time()),
+            screenshot_file_name(later)
+        );
+    }
+
+    #[test]
+    fn screenshot_is_saved_in_dir() {
+        let path = screenshot_path(Path::new("captures/today"), test_time());
+
+        assert_eq!(
+            path,
+            PathBuf::from("captures/today/screenshot_2023-06-01_09-05-30-042.png")
+        );
+    }
+}
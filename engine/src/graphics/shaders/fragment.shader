@@ -5,6 +5,8 @@ in vec2 texture_uv;
 
 uniform sampler2D in_texture;
 uniform vec4 color_key; // set alpha to zero to disable
+uniform vec4 color_mult;
+uniform vec4 color_add;
 
 out vec4 frag_color;
 
@@ -14,5 +16,5 @@ void main()
     if (texel == color_key) {
         discard;
     }
-    frag_color = vert_color * texture(in_texture, texture_uv);
+    frag_color = vert_color * (texel * color_mult + color_add);
 }
@@ -0,0 +1,37 @@
+#version 330 core
+
+in vec2 texture_uv;
+
+uniform sampler2D in_texture;
+uniform vec2 texture_size;
+
+out vec4 frag_color;
+
+// Scale2x (EPX): each texel is split into four quadrants, where a quadrant
+// takes the color of its two neighbours if they agree, smoothing diagonals.
+void main()
+{
+    vec2 texel_size = 1.0 / texture_size;
+    vec2 texel_pos = texture_uv * texture_size;
+    vec2 center_uv = (floor(texel_pos) + 0.5) * texel_size;
+    vec2 quadrant = fract(texel_pos);
+
+    vec4 center = texture(in_texture, center_uv);
+    vec4 above = texture(in_texture, center_uv - vec2(0.0, texel_size.y));
+    vec4 below = texture(in_texture, center_uv + vec2(0.0, texel_size.y));
+    vec4 left = texture(in_texture, center_uv - vec2(texel_size.x, 0.0));
+    vec4 right = texture(in_texture, center_uv + vec2(texel_size.x, 0.0));
+
+    bool is_top = quadrant.y < 0.5;
+    bool is_left = quadrant.x < 0.5;
+    vec4 vertical = is_top ? above : below;
+    vec4 horizontal = is_left ? left : right;
+    vec4 opposite_vertical = is_top ? below : above;
+    vec4 opposite_horizontal = is_left ? right : left;
+
+    if (vertical == horizontal && vertical != opposite_horizontal && horizontal != opposite_vertical) {
+        frag_color = vertical;
+    } else {
+        frag_color = center;
+    }
+}
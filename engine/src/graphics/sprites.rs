@@ -1,8 +1,22 @@
-use std::{collections::HashMap, path::Path};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
-use crate::{geometry::Rect, graphics::rendering::Renderer};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 
-use super::rendering::TextureID;
+use crate::{
+    geometry::{Dimension, Point, Rect},
+    graphics::rendering::Renderer,
+    resources::resolve_resource_path,
+};
+
+use super::{
+    animation::{AnimationID, AnimationSystem},
+    color::Color,
+    rendering::TextureID,
+};
 
 #[derive(Debug)]
 pub struct SpriteSystem {
@@ -12,7 +26,43 @@ pub struct SpriteSystem {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct SpriteSheetID(u32);
+pub struct SpriteSheetID(pub(crate) u32);
+
+/// A sprite sheet frame placed at a position, optionally animated. Drawn with
+/// `SpriteSystem::draw_instance`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sprite {
+    pub sprite_sheet: SpriteSheetID,
+    /// The frame drawn when there's no animation
+    pub sprite_index: usize,
+    /// If set, the current frame of the animation is drawn
+    pub animation: Option<AnimationID>,
+    pub pos: Point,
+}
+
+/// Collects sprite draw calls and submits them grouped by texture, see
+/// `SpriteSystem::begin_batch`.
+#[derive(Debug)]
+pub struct SpriteBatch<'a> {
+    sprite_system: &'a SpriteSystem,
+    sprites: Vec<BatchedSprite>,
+}
+
+#[derive(Debug)]
+struct BatchedSprite {
+    texture_id: TextureID,
+    color_key: Option<Color>,
+    draw_rect: Rect,
+    clip_rect: Rect,
+}
+
+/// Sprites of a batch drawn with a single draw call
+#[derive(Debug, PartialEq)]
+struct BatchGroup {
+    texture_id: TextureID,
+    color_key: Option<Color>,
+    quads: Vec<(Rect, Option<Rect>)>,
+}
 
 #[derive(Debug)]
 pub enum LoadError {
@@ -20,18 +70,65 @@ pub enum LoadError {
     JsonError(serde_json::error::Error),
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub enum GridError {
+    /// A frame width or height of 0, which no grid can be made of
+    ZeroFrameSize,
+}
+
+/// Engine native sprite sheet definition, for tools that shouldn't depend on
+/// the Aseprite JSON format
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpriteSheetDef {
+    /// The sprite sheet image, if known
+    #[serde(default)]
+    pub image: Option<PathBuf>,
+    pub frames: Vec<FrameDef>,
+    #[serde(default)]
+    pub tags: Vec<TagDef>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FrameDef {
+    pub x: i32,
+    pub y: i32,
+    pub w: u32,
+    pub h: u32,
+    pub duration_ms: u32,
+}
+
+/// A named range of frames, e.g. an animation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagDef {
+    pub name: String,
+    pub from: usize,
+    pub to: usize,
+}
+
 #[derive(Debug, Clone)]
 struct SpriteSheetData {
     texture_id: TextureID,
     sprites: Vec<Rect>,
-    color_key: Option<(u8, u8, u8)>,
+    color_key: Option<Color>,
 }
 
 pub fn load_aseprite_sprite_sheet(
     json_path: &Path,
 ) -> Result<aseprite::SpritesheetData, LoadError> {
-    let json_file = std::fs::File::open(json_path).map_err(|e| LoadError::IoError(e))?;
-    serde_json::from_reader(json_file).map_err(|e| LoadError::JsonError(e))
+    let json_file =
+        std::fs::File::open(resolve_resource_path(json_path)).map_err(LoadError::IoError)?;
+    serde_json::from_reader(json_file).map_err(LoadError::JsonError)
+}
+
+pub fn load_spritesheet_def(path: &Path) -> Result<SpriteSheetDef, LoadError> {
+    let json_file = std::fs::File::open(resolve_resource_path(path)).map_err(LoadError::IoError)?;
+    serde_json::from_reader(json_file).map_err(LoadError::JsonError)
+}
+
+pub fn save_spritesheet_def(path: &Path, def: &SpriteSheetDef) -> Result<(), LoadError> {
+    let json_file =
+        std::fs::File::create(resolve_resource_path(path)).map_err(LoadError::IoError)?;
+    serde_json::to_writer_pretty(json_file, def).map_err(LoadError::JsonError)
 }
 
 pub fn aseprite_sprite_sheet_frames(sprite_sheet_data: &aseprite::SpritesheetData) -> Vec<Rect> {
@@ -47,6 +144,128 @@ pub fn aseprite_sprite_sheet_frames(sprite_sheet_data: &aseprite::SpritesheetDat
         .collect()
 }
 
+/// Finds the sprite index of the frame `offset` frames into the frame tag
+/// `tag`, to pass to `SpriteSystem::draw_sprite`.
+pub fn sprite_index_for_tag(
+    sprite_sheet_data: &aseprite::SpritesheetData,
+    tag: &str,
+    offset: usize,
+) -> Option<usize> {
+    let frame_tag = sprite_sheet_data
+        .meta
+        .frame_tags
+        .as_ref()?
+        .iter()
+        .find(|frame_tag| frame_tag.name == tag)?;
+    let index = frame_tag.from as usize + offset;
+    (index <= frame_tag.to as usize).then_some(index)
+}
+
+/// Finds the sprite index of the frame with the given exported filename, to
+/// pass to `SpriteSystem::draw_sprite`.
+pub fn sprite_index_for_filename(
+    sprite_sheet_data: &aseprite::SpritesheetData,
+    name: &str,
+) -> Option<usize> {
+    sprite_sheet_data
+        .frames
+        .iter()
+        .position(|frame| frame.filename == name)
+}
+
+impl SpriteSheetDef {
+    /// The frame rects, to pass to `SpriteSystem::add_spritesheet`
+    pub fn frame_rects(&self) -> Vec<Rect> {
+        self.frames.iter().map(FrameDef::rect).collect()
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&TagDef> {
+        self.tags.iter().find(|tag| tag.name == name)
+    }
+}
+
+impl FrameDef {
+    pub fn rect(&self) -> Rect {
+        Rect {
+            x: self.x,
+            y: self.y,
+            w: self.w,
+            h: self.h,
+        }
+    }
+}
+
+impl From<&aseprite::SpritesheetData> for SpriteSheetDef {
+    fn from(sprite_sheet_data: &aseprite::SpritesheetData) -> Self {
+        let frames = sprite_sheet_data
+            .frames
+            .iter()
+            .map(|frame| FrameDef {
+                x: frame.frame.x as i32,
+                y: frame.frame.y as i32,
+                w: frame.frame.w,
+                h: frame.frame.h,
+                duration_ms: frame.duration,
+            })
+            .collect();
+        let tags = sprite_sheet_data
+            .meta
+            .frame_tags
+            .iter()
+            .flatten()
+            .map(|frame_tag| TagDef {
+                name: frame_tag.name.clone(),
+                from: frame_tag.from as usize,
+                to: frame_tag.to as usize,
+            })
+            .collect();
+
+        SpriteSheetDef {
+            image: sprite_sheet_data.meta.image.as_ref().map(PathBuf::from),
+            frames,
+            tags,
+        }
+    }
+}
+
+/// Computes the frames of a sprite sheet laid out as a uniform grid, row by
+/// row, with `spacing` pixels between frames.
+pub fn grid_sprite_sheet_frames(
+    frame_width: u32,
+    frame_height: u32,
+    columns: u32,
+    rows: u32,
+    spacing: u32,
+) -> Vec<Rect> {
+    (0..rows)
+        .flat_map(|row| {
+            (0..columns).map(move |column| Rect {
+                x: (column * (frame_width + spacing)) as i32,
+                y: (row * (frame_height + spacing)) as i32,
+                w: frame_width,
+                h: frame_height,
+            })
+        })
+        .collect()
+}
+
+/// Computes how many full frames fit in a texture horizontally and vertically,
+/// ignoring any partially filled last column or row.
+pub fn grid_dimensions(
+    texture_width: u32,
+    texture_height: u32,
+    frame_width: u32,
+    frame_height: u32,
+    spacing: u32,
+) -> Result<(u32, u32), GridError> {
+    if frame_width == 0 || frame_height == 0 {
+        return Err(GridError::ZeroFrameSize);
+    }
+    let columns = (texture_width + spacing) / (frame_width + spacing);
+    let rows = (texture_height + spacing) / (frame_height + spacing);
+    Ok((columns, rows))
+}
+
 impl SpriteSystem {
     pub fn new() -> Self {
         SpriteSystem {
@@ -60,7 +279,7 @@ impl SpriteSystem {
         &mut self,
         texture: TextureID,
         sprites: &[Rect],
-        color_key: Option<(u8, u8, u8)>,
+        color_key: Option<Color>,
     ) -> SpriteSheetID {
         let id = self.make_id();
         let sprite_sheet = SpriteSheetData {
@@ -72,6 +291,27 @@ impl SpriteSystem {
         id
     }
 
+    /// Adds a sprite sheet laid out as a uniform grid, without needing any
+    /// Aseprite JSON data. Only frames fully inside the texture are added.
+    pub fn add_grid_spritesheet(
+        &mut self,
+        texture: TextureID,
+        texture_size: Dimension,
+        frame_width: u32,
+        frame_height: u32,
+        spacing: u32,
+    ) -> Result<SpriteSheetID, GridError> {
+        let (columns, rows) = grid_dimensions(
+            texture_size.width,
+            texture_size.height,
+            frame_width,
+            frame_height,
+            spacing,
+        )?;
+        let frames = grid_sprite_sheet_frames(frame_width, frame_height, columns, rows, spacing);
+        Ok(self.add_spritesheet(texture, &frames, None))
+    }
+
     /// Updates an existing sprite sheet in the system
     /// Used for hot reloading.
     pub fn reload_sprite_sheet(
@@ -79,7 +319,7 @@ impl SpriteSystem {
         id: SpriteSheetID,
         texture: TextureID,
         sprites: &[Rect],
-        color_key: Option<(u8, u8, u8)>,
+        color_key: Option<Color>,
     ) {
         let sprite_sheet = SpriteSheetData {
             texture_id: texture,
@@ -108,28 +348,471 @@ impl SpriteSystem {
         let sprite_sheet = &self.sprite_sheets[&sprite_sheet];
         let sprite_rect = sprite_sheet.sprites[sprite_index];
 
-        if let Some((r, g, b)) = sprite_sheet.color_key {
-            renderer.set_color_key(r, g, b);
+        if let Some(color_key) = sprite_sheet.color_key {
+            renderer.set_color_key(color_key);
         }
 
-        renderer.set_texture_blend_color(255, 255, 255, 255);
+        renderer.set_texture_blend_color(Color::WHITE);
         renderer.draw_texture(
             sprite_sheet.texture_id,
-            Rect {
-                x,
-                y,
-                w: f32::round(sprite_rect.w as f32 * self.scaling) as u32,
-                h: f32::round(sprite_rect.h as f32 * self.scaling) as u32,
-            },
+            self.sprite_draw_rect(sprite_rect, x, y),
             Some(sprite_rect),
         );
 
         renderer.disable_color_key();
     }
 
+    /// Draw `sprite` with the current frame of its animation, if any. Sprites
+    /// with a removed animation aren't drawn.
+    pub fn draw_instance(
+        &self,
+        renderer: &mut Renderer,
+        animation_system: &AnimationSystem,
+        sprite: &Sprite,
+    ) {
+        if let Some(sprite_index) = sprite.current_sprite_index(animation_system) {
+            self.draw_sprite(
+                renderer,
+                sprite.sprite_sheet,
+                sprite_index,
+                sprite.pos.x,
+                sprite.pos.y,
+            );
+        }
+    }
+
+    /// Start collecting sprites to draw with as few draw calls as possible,
+    /// e.g. for tile maps. Sprites sharing a texture are drawn in the order
+    /// they were added, but sprites of different textures may be reordered.
+    pub fn begin_batch(&self) -> SpriteBatch<'_> {
+        SpriteBatch {
+            sprite_system: self,
+            sprites: Vec::new(),
+        }
+    }
+
+    fn sprite_draw_rect(&self, sprite_rect: Rect, x: i32, y: i32) -> Rect {
+        Rect {
+            x,
+            y,
+            w: f32::round(sprite_rect.w as f32 * self.scaling) as u32,
+            h: f32::round(sprite_rect.h as f32 * self.scaling) as u32,
+        }
+    }
+
     fn make_id(&mut self) -> SpriteSheetID {
         let id = self.next_id;
         self.next_id += 1;
         SpriteSheetID(id)
     }
 }
+
+impl Sprite {
+    pub fn new(sprite_sheet: SpriteSheetID, sprite_index: usize, pos: Point) -> Self {
+        Sprite {
+            sprite_sheet,
+            sprite_index,
+            animation: None,
+            pos,
+        }
+    }
+
+    pub fn with_animation(self, animation: AnimationID) -> Self {
+        Sprite {
+            animation: Some(animation),
+            ..self
+        }
+    }
+
+    /// The frame to draw, None if the sprite's animation has been removed
+    pub fn current_sprite_index(&self, animation_system: &AnimationSystem) -> Option<usize> {
+        match self.animation {
+            Some(animation) => animation_system.current_frame(animation),
+            None => Some(self.sprite_index),
+        }
+    }
+}
+
+impl<'a> SpriteBatch<'a> {
+    pub fn draw(&mut self, sprite_sheet: SpriteSheetID, sprite_index: usize, x: i32, y: i32) {
+        let sprite_sheet = &self.sprite_system.sprite_sheets[&sprite_sheet];
+        let sprite_rect = sprite_sheet.sprites[sprite_index];
+        self.sprites.push(BatchedSprite {
+            texture_id: sprite_sheet.texture_id,
+            color_key: sprite_sheet.color_key,
+            draw_rect: self.sprite_system.sprite_draw_rect(sprite_rect, x, y),
+            clip_rect: sprite_rect,
+        });
+    }
+
+    /// Submits all sprites to the renderer, one draw call per texture
+    pub fn flush(self, renderer: &mut Renderer) {
+        renderer.set_texture_blend_color(Color::WHITE);
+        for group in self.into_groups() {
+            if let Some(color_key) = group.color_key {
+                renderer.set_color_key(color_key);
+            }
+            renderer.draw_texture_quads(group.texture_id, &group.quads);
+            renderer.disable_color_key();
+        }
+    }
+
+    fn into_groups(mut self) -> Vec<BatchGroup> {
+        // stable sort, keeps the draw order of sprites sharing a texture
+        self.sprites
+            .sort_by_key(|sprite| (sprite.texture_id, sprite.color_key));
+
+        self.sprites
+            .iter()
+            .group_by(|sprite| (sprite.texture_id, sprite.color_key))
+            .into_iter()
+            .map(|((texture_id, color_key), sprites)| BatchGroup {
+                texture_id,
+                color_key,
+                quads: sprites
+                    .map(|sprite| (sprite.draw_rect, Some(sprite.clip_rect)))
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::rect;
+
+    const SPRITE_SHEET_JSON: &str = r##"{ "frames": [
+        {
+            "filename": "player 0.aseprite",
+            "frame": { "x": 0, "y": 0, "w": 16, "h": 16 },
+            "rotated": false,
+            "trimmed": false,
+            "spriteSourceSize": { "x": 0, "y": 0, "w": 16, "h": 16 },
+            "sourceSize": { "w": 16, "h": 16 },
+            "duration": 100
+        },
+        {
+            "filename": "player 1.aseprite",
+            "frame": { "x": 16, "y": 0, "w": 16, "h": 16 },
+            "rotated": false,
+            "trimmed": false,
+            "spriteSourceSize": { "x": 0, "y": 0, "w": 16, "h": 16 },
+            "sourceSize": { "w": 16, "h": 16 },
+            "duration": 100
+        },
+        {
+            "filename": "player 2.aseprite",
+            "frame": { "x": 32, "y": 0, "w": 16, "h": 16 },
+            "rotated": false,
+            "trimmed": false,
+            "spriteSourceSize": { "x": 0, "y": 0, "w": 16, "h": 16 },
+            "sourceSize": { "w": 16, "h": 16 },
+            "duration": 100
+        }
+    ],
+    "meta": {
+        "app": "https://www.aseprite.org/",
+        "version": "1.2.40-x64",
+        "image": "player.png",
+        "format": "RGBA8888",
+        "size": { "w": 48, "h": 16 },
+        "scale": "1",
+        "frameTags": [
+            { "name": "Idle", "from": 0, "to": 0, "direction": "forward" },
+            { "name": "Walk", "from": 1, "to": 2, "direction": "forward" }
+        ]
+    }
+    }"##;
+
+    fn sprite_sheet_data() -> aseprite::SpritesheetData {
+        serde_json::from_str(SPRITE_SHEET_JSON).unwrap()
+    }
+
+    #[parameterized(tag = {
+        "Idle", "Walk", "Walk"
+    }, offset = {
+        0, 0, 1
+    }, expected_index = {
+        0, 1, 2
+    })]
+    fn sprite_index_for_tag_is_offset_from_tag_start(
+        tag: &str,
+        offset: usize,
+        expected_index: usize,
+    ) {
+        let index = sprite_index_for_tag(&sprite_sheet_data(), tag, offset);
+
+        assert_eq!(index, Some(expected_index));
+    }
+
+    #[test]
+    fn sprite_index_for_tag_outside_tag_is_none() {
+        let index = sprite_index_for_tag(&sprite_sheet_data(), "Walk", 2);
+
+        assert_eq!(index, None);
+    }
+
+    #[test]
+    fn sprite_index_for_missing_tag_is_none() {
+        let index = sprite_index_for_tag(&sprite_sheet_data(), "Jump", 0);
+
+        assert_eq!(index, None);
+    }
+
+    #[test]
+    fn sprite_index_for_filename_is_position_of_frame() {
+        let index = sprite_index_for_filename(&sprite_sheet_data(), "player 1.aseprite");
+
+        assert_eq!(index, Some(1));
+    }
+
+    #[test]
+    fn sprite_index_for_missing_filename_is_none() {
+        let index = sprite_index_for_filename(&sprite_sheet_data(), "enemy 0.aseprite");
+
+        assert_eq!(index, None);
+    }
+
+    #[test]
+    fn grid_frames_without_spacing() {
+        //   0   16  32  48
+        // 0 ┌───┬───┬───┐
+        //   │ 0 │ 1 │ 2 │
+        // 16├───┼───┼───┤
+        //   │ 3 │ 4 │ 5 │
+        // 32└───┴───┴───┘
+        let frames = grid_sprite_sheet_frames(16, 16, 3, 2, 0);
+
+        let expected = [
+            rect(0, 0, 16, 16),
+            rect(16, 0, 16, 16),
+            rect(32, 0, 16, 16),
+            rect(0, 16, 16, 16),
+            rect(16, 16, 16, 16),
+            rect(32, 16, 16, 16),
+        ];
+        assert_eq!(frames, expected);
+    }
+
+    #[test]
+    fn grid_frames_with_spacing() {
+        //   0   16 18  34 36  52
+        // 0 ┌───┐ ┌───┐ ┌───┐
+        //   │ 0 │ │ 1 │ │ 2 │
+        // 16└───┘ └───┘ └───┘
+        // 18┌───┐ ┌───┐ ┌───┐
+        //   │ 3 │ │ 4 │ │ 5 │
+        // 34└───┘ └───┘ └───┘
+        let frames = grid_sprite_sheet_frames(16, 16, 3, 2, 2);
+
+        let expected = [
+            rect(0, 0, 16, 16),
+            rect(18, 0, 16, 16),
+            rect(36, 0, 16, 16),
+            rect(0, 18, 16, 16),
+            rect(18, 18, 16, 16),
+            rect(36, 18, 16, 16),
+        ];
+        assert_eq!(frames, expected);
+    }
+
+    #[parameterized(texture_size = {
+        (48, 32), (52, 34), (60, 40)
+    }, spacing = {
+        0, 2, 2
+    })]
+    fn grid_dimensions_only_count_full_frames(texture_size: (u32, u32), spacing: u32) {
+        let (texture_width, texture_height) = texture_size;
+
+        let dimensions = grid_dimensions(texture_width, texture_height, 16, 16, spacing);
+
+        assert_eq!(dimensions, Ok((3, 2)));
+    }
+
+    #[parameterized(frame_size = { (0, 16), (16, 0), (0, 0) })]
+    fn grid_with_zero_frame_size_is_an_error(frame_size: (u32, u32)) {
+        let (frame_width, frame_height) = frame_size;
+
+        let dimensions = grid_dimensions(48, 32, frame_width, frame_height, 0);
+
+        assert_eq!(dimensions, Err(GridError::ZeroFrameSize));
+    }
+
+    #[test]
+    fn grid_sprite_sheet_gets_frames_fitting_in_texture() {
+        let mut sprite_system = SpriteSystem::new();
+        let texture_size = Dimension {
+            width: 60,
+            height: 40,
+        };
+
+        let id = sprite_system
+            .add_grid_spritesheet(TextureID(0), texture_size, 16, 16, 2)
+            .unwrap();
+
+        let sprites = &sprite_system.sprite_sheets[&id].sprites;
+        assert_eq!(sprites.len(), 6);
+        assert_eq!(sprites[5], rect(36, 18, 16, 16));
+    }
+
+    #[test]
+    fn sprite_batch_merges_sprites_into_one_group_per_texture() {
+        let mut sprite_system = SpriteSystem::new();
+        let frames = [rect(0, 0, 16, 16)];
+        let first_sheet = sprite_system.add_spritesheet(TextureID(0), &frames, None);
+        let second_sheet = sprite_system.add_spritesheet(TextureID(1), &frames, None);
+
+        let mut batch = sprite_system.begin_batch();
+        batch.draw(first_sheet, 0, 0, 0);
+        batch.draw(second_sheet, 0, 16, 0);
+        batch.draw(first_sheet, 0, 32, 0);
+        batch.draw(second_sheet, 0, 48, 0);
+        let groups = batch.into_groups();
+
+        let quad = |x| (rect(x, 0, 16, 16), Some(rect(0, 0, 16, 16)));
+        assert_eq!(
+            groups,
+            [
+                BatchGroup {
+                    texture_id: TextureID(0),
+                    color_key: None,
+                    quads: vec![quad(0), quad(32)],
+                },
+                BatchGroup {
+                    texture_id: TextureID(1),
+                    color_key: None,
+                    quads: vec![quad(16), quad(48)],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn sprite_batch_keeps_sprites_of_different_color_keys_apart() {
+        let mut sprite_system = SpriteSystem::new();
+        let frames = [rect(0, 0, 16, 16)];
+        let plain_sheet = sprite_system.add_spritesheet(TextureID(0), &frames, None);
+        let keyed_sheet =
+            sprite_system.add_spritesheet(TextureID(0), &frames, Some(Color::rgb(255, 0, 255)));
+
+        let mut batch = sprite_system.begin_batch();
+        batch.draw(plain_sheet, 0, 0, 0);
+        batch.draw(keyed_sheet, 0, 16, 0);
+
+        assert_eq!(batch.into_groups().len(), 2);
+    }
+
+    fn sprite_sheet_def() -> SpriteSheetDef {
+        SpriteSheetDef {
+            image: Some(PathBuf::from("player.png")),
+            frames: vec![
+                FrameDef {
+                    x: 0,
+                    y: 0,
+                    w: 16,
+                    h: 16,
+                    duration_ms: 100,
+                },
+                FrameDef {
+                    x: 16,
+                    y: 0,
+                    w: 16,
+                    h: 16,
+                    duration_ms: 150,
+                },
+            ],
+            tags: vec![TagDef {
+                name: "Walk".to_owned(),
+                from: 0,
+                to: 1,
+            }],
+        }
+    }
+
+    #[test]
+    fn sprite_sheet_def_round_trips_through_json() {
+        let def = sprite_sheet_def();
+
+        let json = serde_json::to_string(&def).unwrap();
+        let parsed: SpriteSheetDef = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(parsed, def);
+    }
+
+    #[test]
+    fn sprite_sheet_def_round_trips_through_file() {
+        let def = sprite_sheet_def();
+        let path = std::env::temp_dir().join("sprite_sheet_def_round_trip.json");
+
+        save_spritesheet_def(&path, &def).unwrap();
+        let loaded = load_spritesheet_def(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.unwrap(), def);
+    }
+
+    #[test]
+    fn sprite_sheet_def_without_image_or_tags_is_parsed() {
+        let json = r#"{ "frames": [{ "x": 0, "y": 0, "w": 8, "h": 8, "duration_ms": 0 }] }"#;
+
+        let def: SpriteSheetDef = serde_json::from_str(json).unwrap();
+
+        assert_eq!(def.image, None);
+        assert_eq!(def.frame_rects(), vec![rect(0, 0, 8, 8)]);
+        assert!(def.tags.is_empty());
+    }
+
+    #[test]
+    fn aseprite_data_converts_to_sprite_sheet_def() {
+        let def = SpriteSheetDef::from(&sprite_sheet_data());
+
+        assert_eq!(def.image, Some(PathBuf::from("player.png")));
+        assert_eq!(
+            def.frame_rects(),
+            aseprite_sprite_sheet_frames(&sprite_sheet_data())
+        );
+        assert!(def.frames.iter().all(|frame| frame.duration_ms == 100));
+        assert_eq!(
+            def.tag("Walk"),
+            Some(&TagDef {
+                name: "Walk".to_owned(),
+                from: 1,
+                to: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn sprite_without_animation_resolves_to_its_sprite_index() {
+        let animation_system = AnimationSystem::new();
+        let sprite = Sprite::new(SpriteSheetID(0), 3, Point::new(0, 0));
+
+        assert_eq!(sprite.current_sprite_index(&animation_system), Some(3));
+    }
+
+    #[test]
+    fn animated_sprite_resolves_to_current_animation_frame() {
+        let mut animation_system = AnimationSystem::new();
+        let animation_id = animation_system.add_animation(4, 6, &[100, 100, 100]);
+        let sprite =
+            Sprite::new(SpriteSheetID(0), 0, Point::new(0, 0)).with_animation(animation_id);
+
+        animation_system.start_animation(animation_id);
+        animation_system.update(100);
+
+        assert_eq!(sprite.current_sprite_index(&animation_system), Some(5));
+    }
+
+    #[test]
+    fn sprite_with_removed_animation_has_no_sprite_index() {
+        let mut animation_system = AnimationSystem::new();
+        let animation_id = animation_system.add_animation(0, 1, &[100, 100]);
+        let sprite =
+            Sprite::new(SpriteSheetID(0), 0, Point::new(0, 0)).with_animation(animation_id);
+
+        animation_system.remove_animation(animation_id);
+
+        assert_eq!(sprite.current_sprite_index(&animation_system), None);
+    }
+}
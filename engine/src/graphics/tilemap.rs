@@ -0,0 +1,210 @@
+use std::ops::Range;
+
+use crate::{
+    geometry::{Dimension, Point},
+    graphics::{
+        rendering::Renderer,
+        sprites::{SpriteSheetID, SpriteSystem},
+    },
+};
+
+/// Sprite index of tiles that aren't drawn
+pub const EMPTY_TILE: usize = usize::MAX;
+
+/// A grid of sprite indices into a sprite sheet, stored row by row
+#[derive(Debug, Clone)]
+pub struct TileMap {
+    pub sprite_sheet: SpriteSheetID,
+    tile_size: Dimension,
+    columns: u32,
+    rows: u32,
+    tiles: Vec<usize>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TileMapError {
+    /// A tile width or height of 0, which no tiles can be culled with
+    ZeroTileSize,
+}
+
+impl TileMap {
+    /// Creates a map with all tiles empty
+    pub fn new(
+        sprite_sheet: SpriteSheetID,
+        tile_size: Dimension,
+        columns: u32,
+        rows: u32,
+    ) -> Result<Self, TileMapError> {
+        if tile_size.width == 0 || tile_size.height == 0 {
+            return Err(TileMapError::ZeroTileSize);
+        }
+        Ok(TileMap {
+            sprite_sheet,
+            tile_size,
+            columns,
+            rows,
+            tiles: vec![EMPTY_TILE; (columns * rows) as usize],
+        })
+    }
+
+    pub fn tile_size(&self) -> Dimension {
+        self.tile_size
+    }
+
+    pub fn columns(&self) -> u32 {
+        self.columns
+    }
+
+    pub fn rows(&self) -> u32 {
+        self.rows
+    }
+
+    pub fn tile(&self, column: u32, row: u32) -> usize {
+        self.tiles[self.tile_index(column, row)]
+    }
+
+    pub fn set_tile(&mut self, column: u32, row: u32, sprite_index: usize) {
+        let index = self.tile_index(column, row);
+        self.tiles[index] = sprite_index;
+    }
+
+    fn tile_index(&self, column: u32, row: u32) -> usize {
+        assert!(
+            column < self.columns && row < self.rows,
+            "tile ({}, {}) outside of {}x{} tile map",
+            column,
+            row,
+            self.columns,
+            self.rows
+        );
+        (column + row * self.columns) as usize
+    }
+}
+
+/// Draws the tiles of `map` visible on the canvas, with the top left corner of
+/// the map at `offset`.
+pub fn draw_tilemap(renderer: &mut Renderer, sprites: &SpriteSystem, map: &TileMap, offset: Point) {
+    let canvas_size = renderer.canvas().size;
+    let mut batch = sprites.begin_batch();
+    for (column, row, sprite_index) in visible_tiles(map, offset, canvas_size) {
+        let x = offset.x + (column * map.tile_size.width) as i32;
+        let y = offset.y + (row * map.tile_size.height) as i32;
+        batch.draw(map.sprite_sheet, sprite_index, x, y);
+    }
+    batch.flush(renderer);
+}
+
+/// The non-empty tiles intersecting the canvas, as (column, row, sprite index)
+fn visible_tiles(map: &TileMap, offset: Point, canvas_size: Dimension) -> Vec<(u32, u32, usize)> {
+    let (columns, rows) = visible_tile_range(map, offset, canvas_size);
+    rows.flat_map(|row| columns.clone().map(move |column| (column, row)))
+        .map(|(column, row)| (column, row, map.tile(column, row)))
+        .filter(|(_, _, sprite_index)| *sprite_index != EMPTY_TILE)
+        .collect()
+}
+
+/// The columns and rows of the tiles intersecting the canvas
+fn visible_tile_range(
+    map: &TileMap,
+    offset: Point,
+    canvas_size: Dimension,
+) -> (Range<u32>, Range<u32>) {
+    let columns = visible_range(
+        offset.x,
+        map.tile_size.width,
+        canvas_size.width,
+        map.columns,
+    );
+    let rows = visible_range(offset.y, map.tile_size.height, canvas_size.height, map.rows);
+    (columns, rows)
+}
+
+fn visible_range(offset: i32, tile_size: u32, canvas_size: u32, num_tiles: u32) -> Range<u32> {
+    let tile_size = tile_size as i32;
+    let first = (-offset).div_euclid(tile_size);
+    let end = (canvas_size as i32 - offset + tile_size - 1).div_euclid(tile_size);
+    let clamp = |index: i32| index.clamp(0, num_tiles as i32) as u32;
+    clamp(first)..clamp(end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::point;
+
+    const CANVAS_SIZE: Dimension = Dimension {
+        width: 64,
+        height: 48,
+    };
+
+    /// A 10x10 map of 16x16 tiles, where every tile uses sprite 0
+    fn filled_tile_map() -> TileMap {
+        let tile_size = Dimension {
+            width: 16,
+            height: 16,
+        };
+        let mut map = TileMap::new(SpriteSheetID(0), tile_size, 10, 10).unwrap();
+        for row in 0..map.rows() {
+            for column in 0..map.columns() {
+                map.set_tile(column, row, 0);
+            }
+        }
+        map
+    }
+
+    #[parameterized(tile_width = { 0, 16, 0 }, tile_height = { 16, 0, 0 })]
+    fn tile_map_with_zero_tile_size_is_rejected(tile_width: u32, tile_height: u32) {
+        let tile_size = Dimension {
+            width: tile_width,
+            height: tile_height,
+        };
+
+        let map = TileMap::new(SpriteSheetID(0), tile_size, 10, 10);
+
+        assert_eq!(map.unwrap_err(), TileMapError::ZeroTileSize);
+    }
+
+    #[parameterized(offset = {
+        point(0, 0), point(-20, -8), point(-16, -32), point(20, 8)
+    }, expected_columns = {
+        0..4, 1..6, 1..5, 0..3
+    }, expected_rows = {
+        0..3, 0..4, 2..5, 0..3
+    })]
+    fn only_tiles_intersecting_canvas_are_visible(
+        offset: Point,
+        expected_columns: Range<u32>,
+        expected_rows: Range<u32>,
+    ) {
+        let map = filled_tile_map();
+
+        let (columns, rows) = visible_tile_range(&map, offset, CANVAS_SIZE);
+
+        assert_eq!(columns, expected_columns);
+        assert_eq!(rows, expected_rows);
+    }
+
+    #[parameterized(offset = {
+        point(100, 0), point(0, 100), point(-1000, 0), point(0, -1000)
+    })]
+    fn no_tiles_are_visible_when_map_is_outside_canvas(offset: Point) {
+        let map = filled_tile_map();
+
+        let tiles = visible_tiles(&map, offset, CANVAS_SIZE);
+
+        assert!(tiles.is_empty());
+    }
+
+    #[test]
+    fn empty_tiles_are_skipped() {
+        let mut map = filled_tile_map();
+        map.set_tile(1, 0, EMPTY_TILE);
+
+        let tiles = visible_tiles(&map, point(0, 0), CANVAS_SIZE);
+
+        assert_eq!(tiles.len(), 4 * 3 - 1);
+        assert!(!tiles
+            .iter()
+            .any(|(column, row, _)| (*column, *row) == (1, 0)));
+    }
+}
@@ -4,18 +4,22 @@ use std::{
 };
 
 use crate::{
-    audio::{AudioSystem, SoundID},
-    input::file::is_same_file,
+    audio::{AudioSystem, MusicID, SoundID},
+    input::file::FileChangeKind,
 };
 
+use super::changed_registered_ids;
+
 pub struct AudioReloader {
     sounds: HashMap<SoundID, PathBuf>,
+    music: HashMap<MusicID, PathBuf>,
 }
 
 impl AudioReloader {
     pub fn new() -> Self {
         AudioReloader {
             sounds: HashMap::new(),
+            music: HashMap::new(),
         }
     }
 
@@ -28,15 +32,45 @@ impl AudioReloader {
         self.sounds.insert(id, PathBuf::from(path));
     }
 
-    pub fn update(&self, updated_files: &[PathBuf], audio_player: &mut AudioSystem) {
-        for updated_file in updated_files {
-            // if reloader containts file, reload sound
-            for (id, path) in &self.sounds {
-                if is_same_file(&updated_file, path) {
-                    audio_player.reload_sound(*id, path);
-                    continue;
+    pub fn register_music(&mut self, id: MusicID, path: &Path) {
+        assert!(
+            !self.music.contains_key(&id),
+            "music with ID {:?} already registered",
+            id
+        );
+        self.music.insert(id, PathBuf::from(path));
+    }
+
+    /// Reloads the sounds and music whose files changed, returning the
+    /// changed files that were reloaded
+    pub fn update(
+        &self,
+        updated_files: &[(PathBuf, FileChangeKind)],
+        audio_player: &mut AudioSystem,
+    ) -> Vec<PathBuf> {
+        let mut reloaded_files = Vec::new();
+        let sounds = self.sounds.iter().map(|(id, path)| (*id, path.as_path()));
+        for (id, changed_file) in changed_registered_ids(updated_files, sounds, "Sound") {
+            let path = &self.sounds[&id];
+            match audio_player.reload_sound(id, path) {
+                Ok(()) => reloaded_files.push(changed_file),
+                Err(error) => {
+                    log::error!("Could not reload sound \"{}\": {:?}", path.display(), error)
                 }
             }
         }
+
+        let music = self.music.iter().map(|(id, path)| (*id, path.as_path()));
+        for (id, changed_file) in changed_registered_ids(updated_files, music, "Music") {
+            let path = &self.music[&id];
+            match audio_player.reload_music(id, path) {
+                Ok(()) => reloaded_files.push(changed_file),
+                Err(error) => {
+                    log::error!("Could not reload music \"{}\": {:?}", path.display(), error)
+                }
+            }
+        }
+
+        reloaded_files
     }
 }
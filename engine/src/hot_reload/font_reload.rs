@@ -0,0 +1,108 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    graphics::{
+        fonts::{FontID, TextSystem},
+        rendering::Renderer,
+    },
+    input::file::FileChangeKind,
+};
+
+use super::changed_registered_ids;
+
+pub struct FontReloader {
+    fonts: HashMap<FontID, WatchedFont>,
+}
+
+struct WatchedFont {
+    path: PathBuf,
+    font_size: u32,
+}
+
+impl Default for FontReloader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FontReloader {
+    pub fn new() -> Self {
+        FontReloader {
+            fonts: HashMap::new(),
+        }
+    }
+
+    pub fn register_font(&mut self, id: FontID, path: &Path, font_size: u32) {
+        assert!(
+            !self.fonts.contains_key(&id),
+            "font with ID {:?} already registered",
+            id
+        );
+        self.fonts.insert(
+            id,
+            WatchedFont {
+                path: PathBuf::from(path),
+                font_size,
+            },
+        );
+    }
+
+    /// Reloads the fonts whose files changed, returning the changed files
+    /// that were reloaded
+    pub fn update(
+        &self,
+        updated_files: &[(PathBuf, FileChangeKind)],
+        text_system: &mut TextSystem,
+        renderer: &mut Renderer,
+    ) -> Vec<PathBuf> {
+        let mut reloaded_files = Vec::new();
+        for (id, changed_file) in self.changed_fonts(updated_files) {
+            let font = &self.fonts[&id];
+            match text_system.reload_font(id, renderer, &font.path, font.font_size) {
+                Ok(()) => reloaded_files.push(changed_file),
+                Err(error) => log::error!(
+                    "Could not reload font \"{}\": {:?}",
+                    font.path.display(),
+                    error
+                ),
+            }
+        }
+        reloaded_files
+    }
+
+    /// The registered fonts whose files were created or modified, along with
+    /// the changed file
+    fn changed_fonts(&self, updated_files: &[(PathBuf, FileChangeKind)]) -> Vec<(FontID, PathBuf)> {
+        let fonts = self
+            .fonts
+            .iter()
+            .map(|(id, font)| (*id, font.path.as_path()));
+        changed_registered_ids(updated_files, fonts, "Font")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn font_created_and_modified_is_reloaded_once() {
+        let path = std::env::temp_dir().join("font_reload_twice.ttf");
+        std::fs::write(&path, []).unwrap();
+        let mut reloader = FontReloader::new();
+        reloader.register_font(FontID(1), &path, 16);
+
+        let updated_files = [
+            (path.clone(), FileChangeKind::Created),
+            (path.clone(), FileChangeKind::Modified),
+        ];
+
+        assert_eq!(
+            reloader.changed_fonts(&updated_files),
+            vec![(FontID(1), path)]
+        );
+    }
+}
@@ -1,29 +1,103 @@
 pub mod audio_reload;
+pub mod font_reload;
+pub mod shader_reload;
 pub mod sprite_reload;
+pub mod texture_reload;
 
-use std::{path::PathBuf, time::Duration};
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
 
 use crate::{
     audio::AudioSystem,
-    graphics::{animation::AnimationSystem, rendering::Renderer, sprites::SpriteSystem},
-    input::file::FileWatcher,
+    graphics::{
+        animation::AnimationSystem, fonts::TextSystem, rendering::Renderer, sprites::SpriteSystem,
+    },
+    input::file::{is_same_file, is_same_path, FileChangeKind, FileWatcher},
+    resources::resolve_resource_path,
 };
 
-use self::{audio_reload::AudioReloader, sprite_reload::AsepriteReloader};
+use self::{
+    audio_reload::AudioReloader, font_reload::FontReloader, sprite_reload::AsepriteReloader,
+    texture_reload::TextureReloader,
+};
 
 pub struct ResourceReloader {
-    file_watcher: FileWatcher,
+    /// None if the resource directory couldn't be watched, disabling reloading
+    file_watcher: Option<FileWatcher>,
     audio_reloader: AudioReloader,
+    font_reloader: FontReloader,
     sprite_reloader: AsepriteReloader,
+    texture_reloader: TextureReloader,
+    handlers: Vec<Box<dyn ReloadHandler>>,
+    reload_history: Vec<ReloadRecord>,
+}
+
+/// Debounce time used for file changes unless configured otherwise
+pub const DEFAULT_DEBOUNCE_TIME: Duration = Duration::from_millis(1000);
+
+/// How many reloaded files `ResourceReloader` keeps in its history
+const RELOAD_HISTORY_LENGTH: usize = 32;
+
+/// A changed file that was reloaded, for debugging reloads
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReloadRecord {
+    pub path: PathBuf,
+    pub kind: FileChangeKind,
+    pub time: SystemTime,
+}
+
+/// Custom handler for reloading resources not covered by the built-in
+/// reloaders, e.g. level files.
+pub trait ReloadHandler {
+    /// Called with the changed files on updates that have any. Returns
+    /// whether the handler should `reload`.
+    fn on_files_changed(&mut self, files: &[(PathBuf, FileChangeKind)]) -> bool;
+
+    fn reload(&mut self, ctx: &mut dyn ReloadContext);
+}
+
+/// Gives reload handlers access to the engine systems
+pub trait ReloadContext {
+    fn renderer(&mut self) -> &mut Renderer;
+    fn sprites(&mut self) -> &mut SpriteSystem;
+    fn animation(&mut self) -> &mut AnimationSystem;
+}
+
+struct SystemsReloadContext<'a> {
+    renderer: &'a mut Renderer,
+    sprites: &'a mut SpriteSystem,
+    animation: &'a mut AnimationSystem,
 }
 
 impl ResourceReloader {
-    pub fn new(resource_dir: &PathBuf) -> Self {
-        let file_watcher_debounce = Duration::from_millis(1000);
+    /// Changes to files in `resource_dir` are reloaded once no new changes
+    /// have been seen for `debounce_time`. Reloading is disabled if the
+    /// directory can't be watched, e.g. when it's missing in a packaged build.
+    pub fn new(resource_dir: &PathBuf, debounce_time: Duration) -> Self {
+        match FileWatcher::new(&resource_dir, debounce_time) {
+            Ok(file_watcher) => Self::with_file_watcher(Some(file_watcher)),
+            Err(error) => {
+                log::warn!(
+                    "Can't watch resource directory \"{}\", hot reloading is disabled: {:?}",
+                    resource_dir.display(),
+                    error
+                );
+                Self::with_file_watcher(None)
+            }
+        }
+    }
+
+    fn with_file_watcher(file_watcher: Option<FileWatcher>) -> Self {
         ResourceReloader {
-            file_watcher: FileWatcher::new(&resource_dir, file_watcher_debounce),
+            file_watcher,
             audio_reloader: AudioReloader::new(),
+            font_reloader: FontReloader::new(),
             sprite_reloader: AsepriteReloader::new(),
+            texture_reloader: TextureReloader::new(),
+            handlers: Vec::new(),
+            reload_history: Vec::new(),
         }
     }
 
@@ -34,18 +108,381 @@ impl ResourceReloader {
         sprite_system: &mut SpriteSystem,
         animation_system: &mut AnimationSystem,
         audio_player: &mut AudioSystem,
+        text_system: &mut TextSystem,
     ) {
-        let updated_files = self.file_watcher.update(delta_time_ms);
-        self.audio_reloader.update(&updated_files, audio_player);
-        self.sprite_reloader
-            .update(&updated_files, renderer, sprite_system, animation_system);
+        let updated_files = self.poll_changed_files(delta_time_ms);
+        let mut reloaded_files = self.audio_reloader.update(&updated_files, audio_player);
+        reloaded_files.extend(self.sprite_reloader.update(
+            &updated_files,
+            renderer,
+            sprite_system,
+            animation_system,
+        ));
+        reloaded_files.extend(self.texture_reloader.update(&updated_files, renderer));
+        reloaded_files.extend(
+            self.font_reloader
+                .update(&updated_files, text_system, renderer),
+        );
+
+        let reloading_handlers = self.notify_handlers(&updated_files);
+        if !reloading_handlers.is_empty() {
+            // Handlers don't report which files they reload, so count them all
+            reloaded_files.extend(updated_files.iter().map(|(path, _)| path.clone()));
+        }
+        let mut ctx = SystemsReloadContext {
+            renderer,
+            sprites: sprite_system,
+            animation: animation_system,
+        };
+        for handler_index in reloading_handlers {
+            self.handlers[handler_index].reload(&mut ctx);
+        }
+
+        self.record_reloads(&updated_files, &reloaded_files, SystemTime::now());
+    }
+
+    pub fn set_debounce_time(&mut self, debounce_time: Duration) {
+        if let Some(file_watcher) = &mut self.file_watcher {
+            file_watcher.set_debounce_time(debounce_time);
+        }
+    }
+
+    /// Whether changed files are being watched for
+    pub fn is_enabled(&self) -> bool {
+        self.file_watcher.is_some()
     }
 
     pub fn audio_reloader(&mut self) -> &mut AudioReloader {
         &mut self.audio_reloader
     }
 
+    pub fn font_reloader(&mut self) -> &mut FontReloader {
+        &mut self.font_reloader
+    }
+
     pub fn sprite_reloader(&mut self) -> &mut AsepriteReloader {
         &mut self.sprite_reloader
     }
+
+    pub fn texture_reloader(&mut self) -> &mut TextureReloader {
+        &mut self.texture_reloader
+    }
+
+    /// The most recently reloaded files, oldest first
+    pub fn reload_history(&self) -> &[ReloadRecord] {
+        &self.reload_history
+    }
+
+    pub fn register_handler(&mut self, handler: Box<dyn ReloadHandler>) {
+        self.handlers.push(handler);
+    }
+
+    /// The files changed once debounced
+    fn poll_changed_files(&mut self, delta_time_ms: u128) -> Vec<(PathBuf, FileChangeKind)> {
+        match &mut self.file_watcher {
+            Some(file_watcher) => file_watcher.update(delta_time_ms),
+            None => Vec::new(),
+        }
+    }
+
+    /// Records the changed files that were reloaded in the reload history.
+    /// Removed files are never reloaded and are skipped.
+    fn record_reloads(
+        &mut self,
+        updated_files: &[(PathBuf, FileChangeKind)],
+        reloaded_files: &[PathBuf],
+        time: SystemTime,
+    ) {
+        let reloaded = updated_files.iter().filter(|(path, kind)| {
+            *kind != FileChangeKind::Removed && reloaded_files.contains(path)
+        });
+        self.reload_history
+            .extend(reloaded.map(|(path, kind)| ReloadRecord {
+                path: path.clone(),
+                kind: *kind,
+                time,
+            }));
+        let overflow = self
+            .reload_history
+            .len()
+            .saturating_sub(RELOAD_HISTORY_LENGTH);
+        self.reload_history.drain(..overflow);
+    }
+
+    /// Passes the changed files to every handler, returning the indices of
+    /// the handlers that should reload
+    fn notify_handlers(&mut self, updated_files: &[(PathBuf, FileChangeKind)]) -> Vec<usize> {
+        if updated_files.is_empty() {
+            return Vec::new();
+        }
+        self.handlers
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, handler)| handler.on_files_changed(updated_files).then_some(index))
+            .collect()
+    }
+}
+
+/// The IDs of the `registered` assets whose files were created or modified,
+/// each listed once along with its changed file. Removed files are logged and
+/// skipped.
+fn changed_registered_ids<'a, ID: Copy + PartialEq + 'a>(
+    updated_files: &[(PathBuf, FileChangeKind)],
+    registered: impl Iterator<Item = (ID, &'a Path)> + Clone,
+    asset_kind: &str,
+) -> Vec<(ID, PathBuf)> {
+    let mut changed = Vec::new();
+    for (updated_file, change_kind) in updated_files {
+        for (id, path) in registered.clone() {
+            if *change_kind == FileChangeKind::Removed {
+                if is_same_path(updated_file, &resolve_resource_path(path)) {
+                    log::warn!(
+                        "{} \"{}\" was removed, skipping reload",
+                        asset_kind,
+                        path.display()
+                    );
+                }
+                continue;
+            }
+            if is_same_file(updated_file, &resolve_resource_path(path))
+                && !changed.iter().any(|(changed_id, _)| *changed_id == id)
+            {
+                changed.push((id, updated_file.clone()));
+            }
+        }
+    }
+    changed
+}
+
+impl<'a> ReloadContext for SystemsReloadContext<'a> {
+    fn renderer(&mut self) -> &mut Renderer {
+        self.renderer
+    }
+
+    fn sprites(&mut self) -> &mut SpriteSystem {
+        self.sprites
+    }
+
+    fn animation(&mut self) -> &mut AnimationSystem {
+        self.animation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc, sync::mpsc};
+
+    use super::*;
+
+    fn new_test_resource_reloader() -> ResourceReloader {
+        ResourceReloader::new(&std::env::temp_dir(), DEFAULT_DEBOUNCE_TIME)
+    }
+
+    #[test]
+    fn file_change_from_watcher_reaches_reloader() {
+        let (tx, rx) = mpsc::channel();
+        let file_watcher = FileWatcher::from_receiver(rx, Duration::ZERO);
+        let mut resource_reloader = ResourceReloader::with_file_watcher(Some(file_watcher));
+
+        tx.send(notify::Event {
+            kind: notify::EventKind::Modify(notify::event::ModifyKind::Any),
+            paths: vec![PathBuf::from("./resources/smiley.png")],
+            attrs: notify::event::EventAttributes::new(),
+        })
+        .unwrap();
+        let changed_files = resource_reloader.poll_changed_files(16);
+
+        let expected_files = vec![(
+            PathBuf::from("./resources/smiley.png"),
+            FileChangeKind::Modified,
+        )];
+        assert_eq!(changed_files, expected_files);
+    }
+
+    /// Creates an empty file, since only existing files can be changed
+    fn temp_file(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, []).unwrap();
+        path
+    }
+
+    #[test]
+    fn only_assets_of_changed_file_are_reloaded() {
+        let changed_path = temp_file("reload_changed.png");
+        let unchanged_path = temp_file("reload_unchanged.png");
+        let registered = [(1, changed_path.as_path()), (2, unchanged_path.as_path())];
+
+        let updated_files = [(changed_path.clone(), FileChangeKind::Modified)];
+        let changed = changed_registered_ids(&updated_files, registered.into_iter(), "Asset");
+
+        assert_eq!(changed, vec![(1, changed_path)]);
+    }
+
+    #[test]
+    fn removed_asset_file_is_not_reloaded() {
+        let path = temp_file("reload_removed.png");
+        let registered = [(1, path.as_path())];
+
+        let updated_files = [(path.clone(), FileChangeKind::Removed)];
+        let changed = changed_registered_ids(&updated_files, registered.into_iter(), "Asset");
+
+        assert!(changed.is_empty());
+    }
+
+    #[test]
+    fn asset_changed_several_times_is_reloaded_once() {
+        let path = temp_file("reload_twice.png");
+        let registered = [(1, path.as_path())];
+
+        let updated_files = [
+            (path.clone(), FileChangeKind::Created),
+            (path.clone(), FileChangeKind::Modified),
+        ];
+        let changed = changed_registered_ids(&updated_files, registered.into_iter(), "Asset");
+
+        assert_eq!(changed, vec![(1, path)]);
+    }
+
+    #[test]
+    fn missing_resource_dir_disables_reloading() {
+        let resource_dir = PathBuf::from("does/not/exist");
+
+        let mut resource_reloader = ResourceReloader::new(&resource_dir, DEFAULT_DEBOUNCE_TIME);
+
+        assert!(!resource_reloader.is_enabled());
+        assert!(resource_reloader.poll_changed_files(16).is_empty());
+    }
+
+    #[test]
+    fn reloaded_files_are_recorded_in_reload_history() {
+        let mut resource_reloader = new_test_resource_reloader();
+        let time = SystemTime::now();
+
+        let updated_files = vec![
+            (
+                PathBuf::from("./resources/smiley.png"),
+                FileChangeKind::Modified,
+            ),
+            (
+                PathBuf::from("./resources/notes.txt"),
+                FileChangeKind::Modified,
+            ),
+        ];
+        let reloaded_files = [PathBuf::from("./resources/smiley.png")];
+        resource_reloader.record_reloads(&updated_files, &reloaded_files, time);
+
+        assert_eq!(
+            resource_reloader.reload_history(),
+            &[ReloadRecord {
+                path: PathBuf::from("./resources/smiley.png"),
+                kind: FileChangeKind::Modified,
+                time,
+            }]
+        );
+    }
+
+    #[test]
+    fn reload_history_keeps_most_recent_records() {
+        let mut resource_reloader = new_test_resource_reloader();
+
+        for i in 0..RELOAD_HISTORY_LENGTH + 1 {
+            let path = PathBuf::from(format!("{}.png", i));
+            let updated_files = vec![(path.clone(), FileChangeKind::Created)];
+            resource_reloader.record_reloads(&updated_files, &[path], SystemTime::now());
+        }
+
+        let history = resource_reloader.reload_history();
+        assert_eq!(history.len(), RELOAD_HISTORY_LENGTH);
+        assert_eq!(history[0].path, PathBuf::from("1.png"));
+    }
+
+    #[test]
+    fn removed_files_are_not_recorded_in_reload_history() {
+        let mut resource_reloader = new_test_resource_reloader();
+
+        let updated_files = vec![(
+            PathBuf::from("./resources/smiley.png"),
+            FileChangeKind::Removed,
+        )];
+        let reloaded_files = [PathBuf::from("./resources/smiley.png")];
+        resource_reloader.record_reloads(&updated_files, &reloaded_files, SystemTime::now());
+
+        assert!(resource_reloader.reload_history().is_empty());
+    }
+
+    type ReceivedFiles = Rc<RefCell<Vec<(PathBuf, FileChangeKind)>>>;
+
+    struct MockReloadHandler {
+        received_files: ReceivedFiles,
+        wants_reload: bool,
+    }
+
+    impl ReloadHandler for MockReloadHandler {
+        fn on_files_changed(&mut self, files: &[(PathBuf, FileChangeKind)]) -> bool {
+            self.received_files.borrow_mut().extend_from_slice(files);
+            self.wants_reload
+        }
+
+        fn reload(&mut self, _ctx: &mut dyn ReloadContext) {}
+    }
+
+    fn register_mock_handler(
+        resource_reloader: &mut ResourceReloader,
+        wants_reload: bool,
+    ) -> ReceivedFiles {
+        let received_files = ReceivedFiles::default();
+        resource_reloader.register_handler(Box::new(MockReloadHandler {
+            received_files: received_files.clone(),
+            wants_reload,
+        }));
+        received_files
+    }
+
+    #[test]
+    fn registered_handlers_receive_changed_files() {
+        let mut resource_reloader = ResourceReloader::with_file_watcher(None);
+        let first_handler_files = register_mock_handler(&mut resource_reloader, true);
+        let second_handler_files = register_mock_handler(&mut resource_reloader, true);
+
+        let updated_files = vec![
+            (
+                PathBuf::from("./resources/level.json"),
+                FileChangeKind::Modified,
+            ),
+            (
+                PathBuf::from("./resources/shader.frag"),
+                FileChangeKind::Created,
+            ),
+        ];
+        resource_reloader.notify_handlers(&updated_files);
+
+        assert_eq!(*first_handler_files.borrow(), updated_files);
+        assert_eq!(*second_handler_files.borrow(), updated_files);
+    }
+
+    #[test]
+    fn only_handlers_wanting_to_reload_are_reloaded() {
+        let mut resource_reloader = ResourceReloader::with_file_watcher(None);
+        register_mock_handler(&mut resource_reloader, false);
+        register_mock_handler(&mut resource_reloader, true);
+
+        let updated_files = [(
+            PathBuf::from("./resources/level.json"),
+            FileChangeKind::Modified,
+        )];
+        let reloading = resource_reloader.notify_handlers(&updated_files);
+
+        assert_eq!(reloading, vec![1]);
+    }
+
+    #[test]
+    fn handlers_are_not_notified_without_changed_files() {
+        let mut resource_reloader = ResourceReloader::with_file_watcher(None);
+        let received_files = register_mock_handler(&mut resource_reloader, true);
+
+        let reloading = resource_reloader.notify_handlers(&[]);
+
+        assert!(reloading.is_empty());
+        assert!(received_files.borrow().is_empty());
+    }
 }
@@ -0,0 +1,47 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    graphics::rendering,
+    hot_reload::{ReloadContext, ReloadHandler},
+    input::file::{is_same_file, FileChangeKind},
+};
+
+/// Reloads the renderer shaders when either shader source file changes
+pub struct ShaderReloader {
+    vertex_shader_path: PathBuf,
+    fragment_shader_path: PathBuf,
+}
+
+impl ShaderReloader {
+    pub fn new(vertex_shader_path: &Path, fragment_shader_path: &Path) -> Self {
+        ShaderReloader {
+            vertex_shader_path: PathBuf::from(vertex_shader_path),
+            fragment_shader_path: PathBuf::from(fragment_shader_path),
+        }
+    }
+}
+
+impl ReloadHandler for ShaderReloader {
+    fn on_files_changed(&mut self, files: &[(PathBuf, FileChangeKind)]) -> bool {
+        files.iter().any(|(path, kind)| {
+            *kind != FileChangeKind::Removed
+                && (is_same_file(path, &self.vertex_shader_path)
+                    || is_same_file(path, &self.fragment_shader_path))
+        })
+    }
+
+    fn reload(&mut self, ctx: &mut dyn ReloadContext) {
+        log::info!(
+            "Reloading shaders from \"{}\" and \"{}\"",
+            self.vertex_shader_path.display(),
+            self.fragment_shader_path.display()
+        );
+        if let Err(error) = rendering::load_shaders_from_paths(
+            ctx.renderer(),
+            &self.vertex_shader_path,
+            &self.fragment_shader_path,
+        ) {
+            log::error!("Failed to reload shaders, keeping old ones: {:?}", error);
+        }
+    }
+}
@@ -5,7 +5,8 @@ use crate::graphics::{
         aseprite_sprite_sheet_frames, load_aseprite_sprite_sheet, SpriteSheetID, SpriteSystem,
     },
 };
-use crate::input::file::is_same_file;
+use crate::input::file::{is_same_file, is_same_path, FileChangeKind};
+use crate::resources::resolve_resource_path;
 use ::aseprite::SpritesheetData;
 use std::{
     collections::HashMap,
@@ -23,7 +24,8 @@ struct AsepriteWatchData {
     texture_id: TextureID,
     sprite_sheet_id: SpriteSheetID,
     animations: Vec<AsepriteAnimationData>,
-    should_update: bool,
+    /// The changed file of the sprite sheet, if it should be reloaded
+    changed_file: Option<PathBuf>,
 }
 
 #[derive(Debug)]
@@ -53,7 +55,7 @@ impl AsepriteReloader {
                 texture_id,
                 sprite_sheet_id,
                 animations: Vec::new(),
-                should_update: false,
+                changed_file: None,
             },
         );
         if previous.is_some() {
@@ -83,38 +85,71 @@ impl AsepriteReloader {
         }
     }
 
+    /// Reloads the sprite sheets whose texture or JSON file changed, returning
+    /// the changed files that were reloaded
     pub fn update(
         &mut self,
-        updated_files: &[PathBuf],
+        updated_files: &[(PathBuf, FileChangeKind)],
         renderer: &mut Renderer,
         sprite_system: &mut SpriteSystem,
         animation_system: &mut AnimationSystem,
-    ) {
-        for updated_file_path in updated_files {
+    ) -> Vec<PathBuf> {
+        let mut reloaded_files = Vec::new();
+        for (updated_file_path, change_kind) in updated_files {
             for (json_path, watched_sprite_sheet) in &mut self.watched_sprite_sheets {
-                watched_sprite_sheet.should_update =
-                    is_same_file(&updated_file_path, &watched_sprite_sheet.texture_path)
-                        || is_same_file(&updated_file_path, &json_path);
+                if *change_kind == FileChangeKind::Removed {
+                    if is_same_path(updated_file_path, &resolve_resource_path(json_path))
+                        || is_same_path(
+                            updated_file_path,
+                            &resolve_resource_path(&watched_sprite_sheet.texture_path),
+                        )
+                    {
+                        log::warn!(
+                            "Sprite sheet file \"{}\" was removed, skipping reload",
+                            updated_file_path.display()
+                        );
+                    }
+                    continue;
+                }
+                if is_same_file(
+                    updated_file_path,
+                    &resolve_resource_path(&watched_sprite_sheet.texture_path),
+                ) || is_same_file(updated_file_path, &resolve_resource_path(json_path))
+                {
+                    watched_sprite_sheet.changed_file = Some(updated_file_path.clone());
+                }
             }
         }
 
         for (json_path, watched_sprite_sheet) in &self.watched_sprite_sheets {
-            if watched_sprite_sheet.should_update {
+            if let Some(changed_file) = &watched_sprite_sheet.changed_file {
                 log::info!("Reloading sprite sheet from \"{}\"", json_path.display());
                 if let Ok(sprite_sheet_data) = load_aseprite_sprite_sheet(&json_path) {
-                    self.reload_sprite_sheet(
-                        renderer,
-                        sprite_system,
-                        &sprite_sheet_data,
-                        &watched_sprite_sheet,
-                    );
+                    if self
+                        .reload_sprite_sheet(
+                            renderer,
+                            sprite_system,
+                            &sprite_sheet_data,
+                            &watched_sprite_sheet,
+                        )
+                        .is_some()
+                    {
+                        reloaded_files.push(changed_file.clone());
+                    }
                     for animation in &watched_sprite_sheet.animations {
-                        reload_aseperite_sprite_sheet_animation(
+                        if let Err(error) = reload_aseperite_sprite_sheet_animation(
                             animation.animation_id,
                             animation_system,
                             &sprite_sheet_data,
                             &animation.frame_tag_name,
-                        );
+                        ) {
+                            log::error!(
+                                "Could not reload animation \"{}\": {:?}",
+                                animation.frame_tag_name,
+                                error
+                            );
+                            continue;
+                        }
                         if animation_system.is_playing(animation.animation_id) {
                             animation_system.restart_animation(animation.animation_id);
                         }
@@ -124,8 +159,10 @@ impl AsepriteReloader {
         }
 
         for (_, watched_sprite_sheet) in &mut self.watched_sprite_sheets {
-            watched_sprite_sheet.should_update = false;
+            watched_sprite_sheet.changed_file = None;
         }
+
+        reloaded_files
     }
 
     fn reload_sprite_sheet(
@@ -0,0 +1,63 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    graphics::rendering::{self, Renderer, TextureID},
+    input::file::FileChangeKind,
+};
+
+use super::changed_registered_ids;
+
+/// Reloads textures loaded on their own, i.e. not as part of a sprite sheet
+pub struct TextureReloader {
+    textures: HashMap<TextureID, PathBuf>,
+}
+
+impl Default for TextureReloader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TextureReloader {
+    pub fn new() -> Self {
+        TextureReloader {
+            textures: HashMap::new(),
+        }
+    }
+
+    pub fn register_texture(&mut self, id: TextureID, path: &Path) {
+        assert!(
+            !self.textures.contains_key(&id),
+            "texture with ID {:?} already registered",
+            id
+        );
+        self.textures.insert(id, PathBuf::from(path));
+    }
+
+    /// Reloads the textures whose files changed, returning the changed files
+    /// that were reloaded
+    pub fn update(
+        &self,
+        updated_files: &[(PathBuf, FileChangeKind)],
+        renderer: &mut Renderer,
+    ) -> Vec<PathBuf> {
+        let mut reloaded_files = Vec::new();
+        let textures = self.textures.iter().map(|(id, path)| (*id, path.as_path()));
+        for (id, changed_file) in changed_registered_ids(updated_files, textures, "Texture") {
+            let path = &self.textures[&id];
+            log::info!("Reloading texture from \"{}\"", path.display());
+            match rendering::reload_texture_from_image_path(id, renderer, path) {
+                Ok(()) => reloaded_files.push(changed_file),
+                Err(error) => log::error!(
+                    "Could not reload texture \"{}\": {:?}",
+                    path.display(),
+                    error
+                ),
+            }
+        }
+        reloaded_files
+    }
+}
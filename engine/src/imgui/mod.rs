@@ -2,7 +2,7 @@ pub mod dock;
 
 use sdl2::video::GLContext;
 
-use crate::Engine;
+use crate::{input::InputCapture, Engine};
 
 pub struct ImGui {
     imgui: imgui::Context,
@@ -44,6 +44,23 @@ impl ImGui {
         }
     }
 
+    /// True if ImGui is using the mouse, e.g. hovering or dragging a window
+    pub fn wants_mouse(&self) -> bool {
+        self.imgui.io().want_capture_mouse
+    }
+
+    /// True if ImGui is using the keyboard, e.g. typing in a text field
+    pub fn wants_keyboard(&self) -> bool {
+        self.imgui.io().want_capture_keyboard
+    }
+
+    pub fn input_capture(&self) -> InputCapture {
+        InputCapture {
+            mouse: self.wants_mouse(),
+            keyboard: self.wants_keyboard(),
+        }
+    }
+
     pub fn render(&mut self, _gl: &GLContext) {
         self.imgui_renderer.render(&mut self.imgui);
     }
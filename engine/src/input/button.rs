@@ -1,7 +1,11 @@
+use serde::{Deserialize, Serialize};
+
 #[derive(Debug, Clone, Copy, Default)]
 pub struct Button {
     state: ButtonState,
     event: Option<ButtonEvent>,
+    /// Time the button has been held down since it was pressed
+    held_ms: u128,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -10,7 +14,7 @@ pub enum ButtonEvent {
     Up,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ButtonState {
     Released,
     ReleasedNow,
@@ -23,16 +27,47 @@ impl Button {
         Button {
             state: ButtonState::Released,
             event: None,
+            held_ms: 0,
         }
     }
 
+    /// A button in the given state without a pending event, e.g. when
+    /// restoring recorded input
+    pub fn from_state(state: ButtonState, held_ms: u128) -> Self {
+        Button {
+            state,
+            event: None,
+            held_ms,
+        }
+    }
+
+    pub fn state(&self) -> ButtonState {
+        self.state
+    }
+
     pub fn register_event(&mut self, event: ButtonEvent) {
         self.event = Some(event);
     }
 
-    pub fn update(&mut self) {
+    pub fn update(&mut self, delta_time_ms: u128) {
         self.state = self.state.next_state(self.event);
         self.event = None;
+        self.held_ms = match self.state {
+            ButtonState::Pressed => self.held_ms + delta_time_ms,
+            _ => 0,
+        };
+    }
+
+    /// How long the button has been held, counted from the frame after it
+    /// was pressed. Zero while released.
+    pub fn held_ms(&self) -> u128 {
+        self.held_ms
+    }
+
+    /// Whether the button has been held down for at least `threshold_ms`,
+    /// e.g. for "hold to confirm" interactions
+    pub fn is_held_for(&self, threshold_ms: u128) -> bool {
+        self.is_pressed() && self.held_ms >= threshold_ms
     }
 
     #[allow(dead_code)]
@@ -96,7 +131,7 @@ mod tests {
     #[test]
     fn button_stays_released_if_no_input() {
         let mut button = Button::new();
-        button.update();
+        button.update(0);
         assert!(button.is_released());
     }
 
@@ -106,13 +141,13 @@ mod tests {
 
         // initial press
         button.register_event(ButtonEvent::Down);
-        button.update();
+        button.update(0);
         assert!(!button.is_released());
         assert!(button.is_pressed());
         assert!(button.is_pressed_now());
 
         // continue to press
-        button.update();
+        button.update(0);
         assert!(!button.is_pressed_now());
         assert!(button.is_pressed());
     }
@@ -123,18 +158,58 @@ mod tests {
 
         // initial press
         button.register_event(ButtonEvent::Down);
-        button.update();
+        button.update(0);
 
         // release button
         button.register_event(ButtonEvent::Up);
-        button.update();
+        button.update(0);
         assert!(!button.is_pressed());
         assert!(button.is_released_now());
         assert!(button.is_released());
 
         // continue to hold up button
-        button.update();
+        button.update(0);
         assert!(!button.is_released_now());
         assert!(button.is_released());
     }
+
+    #[test]
+    fn held_time_accumulates_while_pressed() {
+        let mut button = Button::new();
+
+        button.register_event(ButtonEvent::Down);
+        button.update(16);
+        assert_eq!(button.held_ms(), 0);
+
+        button.update(16);
+        button.update(20);
+        assert_eq!(button.held_ms(), 36);
+    }
+
+    #[test]
+    fn button_is_held_for_threshold_once_enough_time_has_passed() {
+        let mut button = Button::new();
+
+        button.register_event(ButtonEvent::Down);
+        button.update(16);
+        button.update(400);
+        assert!(!button.is_held_for(500));
+
+        button.update(100);
+        assert!(button.is_held_for(500));
+    }
+
+    #[test]
+    fn held_time_is_reset_on_release() {
+        let mut button = Button::new();
+
+        button.register_event(ButtonEvent::Down);
+        button.update(16);
+        button.update(1000);
+        button.register_event(ButtonEvent::Up);
+        button.update(16);
+
+        assert_eq!(button.held_ms(), 0);
+        assert!(!button.is_held_for(0));
+    }
 }
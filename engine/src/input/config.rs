@@ -1,14 +1,58 @@
-use std::path::{Path, PathBuf};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 use configparser::ini::Ini;
+use sdl2::keyboard::Keycode;
+
+use crate::{
+    audio::AudioVolumes,
+    graphics::{fullscreen::WindowMode, screenshot::DEFAULT_SCREENSHOT_DIR},
+    hot_reload::DEFAULT_DEBOUNCE_TIME,
+};
 
 pub struct ProgramConfig {
     pub show_debug_ui: bool,
     pub monitor: u64,
+    pub fullscreen_mode: WindowMode,
+    pub hot_reload_debounce: Duration,
+    /// Samples per pixel of the window framebuffer, 0 disables multisampling.
+    /// Only smooths what's drawn straight to the window, since the canvas
+    /// is rendered without multisampling to keep pixel art crisp.
+    pub msaa_samples: u8,
+    pub hotkeys: Hotkeys,
+    pub audio_volumes: AudioVolumes,
+    pub screenshot_dir: PathBuf,
     config: Ini,
     path: PathBuf,
 }
 
+/// Keys for engine and debug functionality, kept configurable so they don't
+/// conflict with game controls. `None` disables a hotkey.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hotkeys {
+    pub fullscreen: Option<Keycode>,
+    pub quit: Option<Keycode>,
+    pub debug_ui: Option<Keycode>,
+    pub editor_ui: Option<Keycode>,
+    pub rebuild_game: Option<Keycode>,
+    pub screenshot: Option<Keycode>,
+}
+
+impl Default for Hotkeys {
+    fn default() -> Self {
+        Hotkeys {
+            fullscreen: Some(Keycode::F11),
+            quit: Some(Keycode::Escape),
+            debug_ui: Some(Keycode::F3),
+            editor_ui: Some(Keycode::F2),
+            rebuild_game: Some(Keycode::F5),
+            screenshot: Some(Keycode::F12),
+        }
+    }
+}
+
 impl ProgramConfig {
     pub fn from_file(path: &Path) -> Self {
         let mut config = Ini::new();
@@ -17,6 +61,24 @@ impl ProgramConfig {
             ProgramConfig {
                 show_debug_ui: config.getbool("Debug UI", "Show").unwrap().unwrap_or(false),
                 monitor: config.getuint("Video", "Monitor").unwrap().unwrap_or(0),
+                fullscreen_mode: config
+                    .get("Video", "FullscreenMode")
+                    .and_then(|mode| mode.parse().ok())
+                    .unwrap_or(WindowMode::BorderlessFullscreen),
+                hot_reload_debounce: config
+                    .getuint("Hot Reload", "DebounceMs")
+                    .unwrap()
+                    .map(Duration::from_millis)
+                    .unwrap_or(DEFAULT_DEBOUNCE_TIME),
+                msaa_samples: config
+                    .getuint("Video", "MsaaSamples")
+                    .unwrap()
+                    .map_or(0, |samples| samples.min(u8::MAX as u64) as u8),
+                hotkeys: read_hotkeys(&config),
+                audio_volumes: read_audio_volumes(&config),
+                screenshot_dir: config
+                    .get("Screenshots", "Directory")
+                    .map_or(PathBuf::from(DEFAULT_SCREENSHOT_DIR), PathBuf::from),
                 config,
                 path: PathBuf::from(path),
             }
@@ -24,6 +86,12 @@ impl ProgramConfig {
             ProgramConfig {
                 show_debug_ui: false,
                 monitor: 0,
+                fullscreen_mode: WindowMode::BorderlessFullscreen,
+                hot_reload_debounce: DEFAULT_DEBOUNCE_TIME,
+                msaa_samples: 0,
+                hotkeys: Hotkeys::default(),
+                audio_volumes: AudioVolumes::default(),
+                screenshot_dir: PathBuf::from(DEFAULT_SCREENSHOT_DIR),
                 config,
                 path: PathBuf::from(path),
             }
@@ -35,6 +103,172 @@ impl ProgramConfig {
             .set("Debug UI", "Show", Some(self.show_debug_ui.to_string()));
         self.config
             .set("Video", "Monitor", Some(self.monitor.to_string()));
+        self.config.set(
+            "Video",
+            "FullscreenMode",
+            Some(self.fullscreen_mode.to_string()),
+        );
+        self.config.set(
+            "Hot Reload",
+            "DebounceMs",
+            Some(self.hot_reload_debounce.as_millis().to_string()),
+        );
+        self.config
+            .set("Video", "MsaaSamples", Some(self.msaa_samples.to_string()));
+        let volumes = self.audio_volumes;
+        for (name, volume) in [
+            ("MasterVolume", volumes.master),
+            ("MusicVolume", volumes.music),
+            ("SfxVolume", volumes.sfx),
+        ] {
+            self.config.set("Audio", name, Some(volume.to_string()));
+        }
+        self.config
+            .set("Audio", "Muted", Some(volumes.muted.to_string()));
+        self.config.set(
+            "Screenshots",
+            "Directory",
+            Some(self.screenshot_dir.to_string_lossy().into_owned()),
+        );
+        for (name, hotkey) in hotkey_entries(&self.hotkeys) {
+            self.config.set("Hotkeys", name, Some(hotkey_name(hotkey)));
+        }
         self.config.write(&self.path).unwrap();
     }
 }
+
+fn hotkey_entries(hotkeys: &Hotkeys) -> [(&'static str, Option<Keycode>); 6] {
+    [
+        ("Fullscreen", hotkeys.fullscreen),
+        ("Quit", hotkeys.quit),
+        ("DebugUi", hotkeys.debug_ui),
+        ("EditorUi", hotkeys.editor_ui),
+        ("RebuildGame", hotkeys.rebuild_game),
+        ("Screenshot", hotkeys.screenshot),
+    ]
+}
+
+/// Reads the [Hotkeys] section, keeping the default for missing or unknown keys
+fn read_hotkeys(config: &Ini) -> Hotkeys {
+    let defaults = Hotkeys::default();
+    let read = |name: &str, default: Option<Keycode>| {
+        config
+            .get("Hotkeys", name)
+            .and_then(|value| parse_hotkey(&value))
+            .unwrap_or(default)
+    };
+    Hotkeys {
+        fullscreen: read("Fullscreen", defaults.fullscreen),
+        quit: read("Quit", defaults.quit),
+        debug_ui: read("DebugUi", defaults.debug_ui),
+        editor_ui: read("EditorUi", defaults.editor_ui),
+        rebuild_game: read("RebuildGame", defaults.rebuild_game),
+        screenshot: read("Screenshot", defaults.screenshot),
+    }
+}
+
+/// Reads the [Audio] section, keeping the default for missing values
+fn read_audio_volumes(config: &Ini) -> AudioVolumes {
+    let defaults = AudioVolumes::default();
+    let read = |name: &str, default: u8| {
+        config
+            .getuint("Audio", name)
+            .unwrap()
+            .map_or(default, |volume| volume.min(u8::MAX as u64) as u8)
+    };
+    AudioVolumes {
+        master: read("MasterVolume", defaults.master),
+        music: read("MusicVolume", defaults.music),
+        sfx: read("SfxVolume", defaults.sfx),
+        muted: config
+            .getbool("Audio", "Muted")
+            .unwrap()
+            .unwrap_or(defaults.muted),
+    }
+}
+
+/// Parses an SDL key name, or "None" for a disabled hotkey
+fn parse_hotkey(value: &str) -> Option<Option<Keycode>> {
+    if value.eq_ignore_ascii_case("none") {
+        Some(None)
+    } else {
+        Keycode::from_name(value).map(Some)
+    }
+}
+
+fn hotkey_name(hotkey: Option<Keycode>) -> String {
+    hotkey.map_or("None".to_owned(), |key| key.name())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn msaa_samples_are_read_from_config_file() {
+        let path = std::env::temp_dir().join("engine_msaa_config_test.ini");
+        std::fs::write(&path, "[Video]\nMsaaSamples = 4\n").unwrap();
+
+        let config = ProgramConfig::from_file(&path);
+
+        assert_eq!(config.msaa_samples, 4);
+    }
+
+    #[test]
+    fn msaa_is_disabled_without_config_file() {
+        let config = ProgramConfig::from_file(Path::new("does/not/exist.ini"));
+
+        assert_eq!(config.msaa_samples, 0);
+    }
+
+    #[test]
+    fn audio_volumes_are_read_from_config() {
+        let mut config = Ini::new();
+        config.set("Audio", "MasterVolume", Some("100".to_owned()));
+        config.set("Audio", "SfxVolume", Some("32".to_owned()));
+        config.set("Audio", "Muted", Some("true".to_owned()));
+
+        let volumes = read_audio_volumes(&config);
+
+        assert_eq!(
+            volumes,
+            AudioVolumes {
+                master: 100,
+                music: AudioVolumes::default().music,
+                sfx: 32,
+                muted: true,
+            }
+        );
+    }
+
+    #[test]
+    fn hotkeys_are_parsed_by_key_name() {
+        assert_eq!(parse_hotkey("F8"), Some(Some(Keycode::F8)));
+    }
+
+    #[test]
+    fn none_disables_hotkey() {
+        assert_eq!(parse_hotkey("None"), Some(None));
+    }
+
+    #[test]
+    fn unknown_key_name_is_not_parsed() {
+        assert_eq!(parse_hotkey("NotAKey"), None);
+    }
+
+    #[test]
+    fn missing_hotkeys_section_uses_defaults() {
+        assert_eq!(read_hotkeys(&Ini::new()), Hotkeys::default());
+    }
+
+    #[test]
+    fn configured_hotkey_overrides_default() {
+        let mut config = Ini::new();
+        config.set("Hotkeys", "Fullscreen", Some("F8".to_owned()));
+
+        let hotkeys = read_hotkeys(&config);
+
+        assert_eq!(hotkeys.fullscreen, Some(Keycode::F8));
+        assert_eq!(hotkeys.quit, Some(Keycode::Escape));
+    }
+}
@@ -0,0 +1,205 @@
+use super::button::ButtonEvent;
+use sdl2::{event::Event, keyboard::Keycode, mouse::MouseButton};
+
+/// Engine level input event, translated from SDL events so that games can
+/// react to input without depending on `sdl2::event::Event` directly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputEvent {
+    KeyDown {
+        keycode: Keycode,
+        repeat: bool,
+    },
+    KeyUp {
+        keycode: Keycode,
+    },
+    /// Mouse position in window coordinates
+    MouseMove {
+        x: i32,
+        y: i32,
+    },
+    MouseButton {
+        button: MouseButton,
+        event: ButtonEvent,
+        x: i32,
+        y: i32,
+    },
+    Wheel {
+        x: i32,
+        y: i32,
+    },
+    Resize {
+        width: u32,
+        height: u32,
+    },
+    Quit,
+}
+
+/// Translates an SDL event, returning `None` for events the engine doesn't expose
+pub fn translate_event(event: &Event) -> Option<InputEvent> {
+    match *event {
+        Event::KeyDown {
+            keycode: Some(keycode),
+            repeat,
+            ..
+        } => Some(InputEvent::KeyDown { keycode, repeat }),
+        Event::KeyUp {
+            keycode: Some(keycode),
+            ..
+        } => Some(InputEvent::KeyUp { keycode }),
+        Event::MouseMotion { x, y, .. } => Some(InputEvent::MouseMove { x, y }),
+        Event::MouseButtonDown {
+            mouse_btn, x, y, ..
+        } => Some(InputEvent::MouseButton {
+            button: mouse_btn,
+            event: ButtonEvent::Down,
+            x,
+            y,
+        }),
+        Event::MouseButtonUp {
+            mouse_btn, x, y, ..
+        } => Some(InputEvent::MouseButton {
+            button: mouse_btn,
+            event: ButtonEvent::Up,
+            x,
+            y,
+        }),
+        Event::MouseWheel { x, y, .. } => Some(InputEvent::Wheel { x, y }),
+        Event::Window {
+            win_event: sdl2::event::WindowEvent::Resized(width, height),
+            ..
+        } => Some(InputEvent::Resize {
+            width: width as u32,
+            height: height as u32,
+        }),
+        Event::Quit { .. } => Some(InputEvent::Quit),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sdl2::{
+        event::WindowEvent,
+        keyboard::Mod,
+        mouse::{MouseState, MouseWheelDirection},
+    };
+
+    #[test]
+    fn key_down_is_translated() {
+        let event = Event::KeyDown {
+            timestamp: 0,
+            window_id: 0,
+            keycode: Some(Keycode::Space),
+            scancode: None,
+            keymod: Mod::NOMOD,
+            repeat: true,
+        };
+
+        assert_eq!(
+            translate_event(&event),
+            Some(InputEvent::KeyDown {
+                keycode: Keycode::Space,
+                repeat: true
+            })
+        );
+    }
+
+    #[test]
+    fn key_up_without_keycode_is_ignored() {
+        let event = Event::KeyUp {
+            timestamp: 0,
+            window_id: 0,
+            keycode: None,
+            scancode: None,
+            keymod: Mod::NOMOD,
+            repeat: false,
+        };
+
+        assert_eq!(translate_event(&event), None);
+    }
+
+    #[test]
+    fn mouse_motion_is_translated() {
+        let event = Event::MouseMotion {
+            timestamp: 0,
+            window_id: 0,
+            which: 0,
+            mousestate: MouseState::from_sdl_state(0),
+            x: 10,
+            y: 20,
+            xrel: 1,
+            yrel: 2,
+        };
+
+        assert_eq!(
+            translate_event(&event),
+            Some(InputEvent::MouseMove { x: 10, y: 20 })
+        );
+    }
+
+    #[test]
+    fn mouse_button_up_is_translated() {
+        let event = Event::MouseButtonUp {
+            timestamp: 0,
+            window_id: 0,
+            which: 0,
+            mouse_btn: MouseButton::Right,
+            clicks: 1,
+            x: 3,
+            y: 4,
+        };
+
+        assert_eq!(
+            translate_event(&event),
+            Some(InputEvent::MouseButton {
+                button: MouseButton::Right,
+                event: ButtonEvent::Up,
+                x: 3,
+                y: 4
+            })
+        );
+    }
+
+    #[test]
+    fn mouse_wheel_is_translated() {
+        let event = Event::MouseWheel {
+            timestamp: 0,
+            window_id: 0,
+            which: 0,
+            x: 0,
+            y: -1,
+            direction: MouseWheelDirection::Normal,
+        };
+
+        assert_eq!(
+            translate_event(&event),
+            Some(InputEvent::Wheel { x: 0, y: -1 })
+        );
+    }
+
+    #[test]
+    fn window_resize_is_translated() {
+        let event = Event::Window {
+            timestamp: 0,
+            window_id: 0,
+            win_event: WindowEvent::Resized(800, 600),
+        };
+
+        assert_eq!(
+            translate_event(&event),
+            Some(InputEvent::Resize {
+                width: 800,
+                height: 600
+            })
+        );
+    }
+
+    #[test]
+    fn quit_is_translated() {
+        assert_eq!(
+            translate_event(&Event::Quit { timestamp: 0 }),
+            Some(InputEvent::Quit)
+        );
+    }
+}
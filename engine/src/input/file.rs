@@ -11,19 +11,77 @@ pub fn is_same_file(lhs: &Path, rhs: &Path) -> bool {
     same_file::is_same_file(lhs, rhs).unwrap_or(false)
 }
 
+/// Compares the normalized absolute paths. Unlike `is_same_file` the files
+/// don't have to exist, so that it works for files that were removed.
+pub fn is_same_path(lhs: &Path, rhs: &Path) -> bool {
+    normalized_path(lhs) == normalized_path(rhs)
+}
+
+/// The absolute form of `path`, with its parent directory canonicalized if
+/// it exists
+fn normalized_path(path: &Path) -> PathBuf {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_owned());
+    match (absolute.parent(), absolute.file_name()) {
+        (Some(parent), Some(file_name)) => match parent.canonicalize() {
+            Ok(parent) => parent.join(file_name),
+            Err(_) => absolute,
+        },
+        _ => absolute,
+    }
+}
+
+/// Removes "." components, so that e.g. "./resources/smiley.png" and
+/// "resources/smiley.png" compare equal
+pub fn without_cur_dir(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| *component != std::path::Component::CurDir)
+        .collect()
+}
+
 pub struct FileWatcher {
     _file_watcher: notify::RecommendedWatcher,
     event_receiver: Receiver<notify::Event>,
     debounce_time: Duration,
-    changed_files: Vec<PathBuf>,
+    changed_files: Vec<(PathBuf, FileChangeKind)>,
     elapsed_time_ms: u128,
 }
 
+#[derive(Debug)]
+pub enum WatchError {
+    NotifyError(notify::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileChangeKind {
+    Created,
+    Modified,
+    Removed,
+    Renamed,
+}
+
+impl FileChangeKind {
+    /// Collapses the notify event kinds into the ones we care about. Returns
+    /// `None` for events that don't change the file, e.g. file accesses.
+    fn from_event_kind(kind: &notify::EventKind) -> Option<Self> {
+        use notify::{event::ModifyKind, EventKind};
+        match kind {
+            EventKind::Create(_) => Some(FileChangeKind::Created),
+            EventKind::Modify(ModifyKind::Name(_)) => Some(FileChangeKind::Renamed),
+            EventKind::Modify(_) | EventKind::Any | EventKind::Other => {
+                Some(FileChangeKind::Modified)
+            }
+            EventKind::Remove(_) => Some(FileChangeKind::Removed),
+            EventKind::Access(_) => None,
+        }
+    }
+}
+
 impl FileWatcher {
     /// Creates a file watcher for the `path` file or directory, that will
     /// filter out any repeated file changes events in `debounce_time` after the
-    /// first file change received.
-    pub fn new(path: &Path, debounce_time: Duration) -> Self {
+    /// first file change received. Fails if `path` can't be watched, e.g.
+    /// because it doesn't exist.
+    pub fn new(path: &Path, debounce_time: Duration) -> Result<Self, WatchError> {
         let (tx, rx): (Sender<notify::Event>, Receiver<notify::Event>) = mpsc::channel();
         let on_file_changed = move |result: notify::Result<notify::Event>| match result {
             Ok(event) => {
@@ -32,27 +90,53 @@ impl FileWatcher {
             Err(e) => log::error!("file watch error: {:?}", e),
         };
 
-        let mut file_watcher = notify::recommended_watcher(on_file_changed).unwrap();
-        file_watcher.watch(path, RecursiveMode::Recursive).unwrap();
+        let mut file_watcher =
+            notify::recommended_watcher(on_file_changed).map_err(WatchError::NotifyError)?;
+        file_watcher
+            .watch(path, RecursiveMode::Recursive)
+            .map_err(WatchError::NotifyError)?;
 
-        FileWatcher {
+        Ok(FileWatcher {
             _file_watcher: file_watcher,
             event_receiver: rx,
             debounce_time,
             changed_files: Vec::new(),
             elapsed_time_ms: 0,
+        })
+    }
+
+    pub fn set_debounce_time(&mut self, debounce_time: Duration) {
+        self.debounce_time = debounce_time;
+    }
+
+    /// A file watcher receiving its events from `event_receiver` instead of
+    /// the file system, for tests
+    #[cfg(test)]
+    pub(crate) fn from_receiver(
+        event_receiver: Receiver<notify::Event>,
+        debounce_time: Duration,
+    ) -> Self {
+        let on_file_changed = |_: notify::Result<notify::Event>| {};
+        let file_watcher = notify::recommended_watcher(on_file_changed).unwrap();
+        FileWatcher {
+            _file_watcher: file_watcher,
+            event_receiver,
+            debounce_time,
+            changed_files: Vec::new(),
+            elapsed_time_ms: 0,
         }
     }
 
-    pub fn update(&mut self, delta_time_ms: u128) -> Vec<PathBuf> {
+    /// Returns the files changed since last debounce period together with the
+    /// kind of their latest change.
+    pub fn update(&mut self, delta_time_ms: u128) -> Vec<(PathBuf, FileChangeKind)> {
         // Add all new events to vector
-        self.changed_files.append(
-            &mut self
-                .event_receiver
-                .try_recv()
-                .map(|event| event.paths)
-                .unwrap_or(Vec::new()),
-        );
+        while let Ok(event) = self.event_receiver.try_recv() {
+            if let Some(kind) = FileChangeKind::from_event_kind(&event.kind) {
+                self.changed_files
+                    .extend(event.paths.into_iter().map(|path| (path, kind)));
+            }
+        }
 
         // Track debounce time while new events exist
         if !self.changed_files.is_empty() {
@@ -62,7 +146,15 @@ impl FileWatcher {
         // Return events after debouncing
         if self.elapsed_time_ms >= self.debounce_time.as_millis() {
             self.elapsed_time_ms = 0;
-            self.changed_files.drain(0..).unique().collect()
+            let changed_files = self.changed_files.drain(0..).collect::<Vec<_>>();
+            let paths = changed_files.iter().map(|(path, _)| path).unique();
+            paths
+                .map(|path| {
+                    // use the most recent change of each file
+                    let (_, kind) = changed_files.iter().rev().find(|(p, _)| p == path).unwrap();
+                    (path.clone(), *kind)
+                })
+                .collect()
         } else {
             Vec::new()
         }
@@ -74,26 +166,74 @@ mod tests {
     use super::*;
 
     fn new_test_file_watcher(debounce_time: Duration, rx: Receiver<notify::Event>) -> FileWatcher {
-        let on_file_changed = |_: notify::Result<notify::Event>| {};
-        let file_watcher = notify::recommended_watcher(on_file_changed).unwrap();
-        FileWatcher {
-            _file_watcher: file_watcher,
-            event_receiver: rx,
-            debounce_time,
-            changed_files: Vec::new(),
-            elapsed_time_ms: 0,
-        }
+        FileWatcher::from_receiver(rx, debounce_time)
     }
 
     fn send_file_update(tx: &Sender<notify::Event>, path: &str) {
+        send_file_event(
+            tx,
+            path,
+            notify::EventKind::Modify(notify::event::ModifyKind::Any),
+        );
+    }
+
+    fn send_file_event(tx: &Sender<notify::Event>, path: &str, kind: notify::EventKind) {
         tx.send(notify::Event {
-            kind: notify::EventKind::Modify(notify::event::ModifyKind::Any),
+            kind,
             paths: vec![PathBuf::from(path)],
             attrs: notify::event::EventAttributes::new(),
         })
         .unwrap();
     }
 
+    #[test]
+    fn watching_nonexistent_path_is_error() {
+        let result = FileWatcher::new(Path::new("does/not/exist"), Duration::from_millis(100));
+
+        assert!(matches!(result, Err(WatchError::NotifyError(_))));
+    }
+
+    #[parameterized(lhs = {
+        "./src/lib.rs", "src/lib.rs", "src/../src/lib.rs"
+    }, rhs = {
+        "src/lib.rs", "./src/lib.rs", "src/lib.rs"
+    })]
+    fn matching_paths_are_same_path(lhs: &str, rhs: &str) {
+        assert!(is_same_path(&PathBuf::from(lhs), &PathBuf::from(rhs)));
+    }
+
+    #[parameterized(lhs = {
+        "./src/lib.rs", "lib.rs", "src/input/mod.rs"
+    }, rhs = {
+        "src/main.rs", "src/lib.rs", "src/graphics/mod.rs"
+    })]
+    fn different_paths_are_not_same_path(lhs: &str, rhs: &str) {
+        assert!(!is_same_path(&PathBuf::from(lhs), &PathBuf::from(rhs)));
+    }
+
+    #[test]
+    fn relative_path_is_same_path_as_absolute_path() {
+        let absolute_path = std::env::current_dir().unwrap().join("src/lib.rs");
+
+        assert!(is_same_path(Path::new("./src/lib.rs"), &absolute_path));
+    }
+
+    #[test]
+    fn removed_files_are_compared_by_path() {
+        let temp_dir = std::env::temp_dir();
+        let removed_path = temp_dir.join("removed_smiley.png");
+        let _ = std::fs::remove_file(&removed_path);
+
+        assert!(is_same_path(
+            &removed_path,
+            &temp_dir.join(".").join("removed_smiley.png")
+        ));
+        assert!(!is_same_path(
+            &removed_path,
+            &temp_dir.join("removed").join("removed_smiley.png")
+        ));
+    }
+
     #[test]
     fn initially_contains_no_changed_files() {
         let (_, rx): (Sender<notify::Event>, Receiver<notify::Event>) = mpsc::channel();
@@ -128,8 +268,62 @@ mod tests {
         assert_eq!(
             updated_files,
             vec![
+                (
+                    PathBuf::from("./resources/my_image.png"),
+                    FileChangeKind::Modified
+                ),
+                (
+                    PathBuf::from("./resources/my_image2.png"),
+                    FileChangeKind::Modified
+                )
+            ]
+        );
+    }
+
+    #[parameterized(debounce_ms = { 10, 500, 2000 })]
+    fn custom_debounce_time_is_honored(debounce_ms: u64) {
+        let (tx, rx): (Sender<notify::Event>, Receiver<notify::Event>) = mpsc::channel();
+        let mut file_watcher = new_test_file_watcher(Duration::from_millis(debounce_ms), rx);
+
+        send_file_update(&tx, "./resources/my_image.png");
+        let before_debounce = file_watcher.update(debounce_ms as u128 - 1);
+        let after_debounce = file_watcher.update(1);
+
+        assert!(before_debounce.is_empty());
+        assert_eq!(
+            after_debounce,
+            vec![(
                 PathBuf::from("./resources/my_image.png"),
-                PathBuf::from("./resources/my_image2.png")
+                FileChangeKind::Modified
+            )]
+        );
+    }
+
+    #[test]
+    fn all_pending_events_are_tracked_in_single_update() {
+        let (tx, rx): (Sender<notify::Event>, Receiver<notify::Event>) = mpsc::channel();
+        let mut file_watcher = new_test_file_watcher(Duration::from_millis(100), rx);
+
+        send_file_update(&tx, "./resources/smiley.json");
+        send_file_update(&tx, "./resources/smiley.png");
+        send_file_update(&tx, "./resources/audio/click.wav");
+        let updated_files = file_watcher.update(100);
+
+        assert_eq!(
+            updated_files,
+            vec![
+                (
+                    PathBuf::from("./resources/smiley.json"),
+                    FileChangeKind::Modified
+                ),
+                (
+                    PathBuf::from("./resources/smiley.png"),
+                    FileChangeKind::Modified
+                ),
+                (
+                    PathBuf::from("./resources/audio/click.wav"),
+                    FileChangeKind::Modified
+                ),
             ]
         );
     }
@@ -147,7 +341,49 @@ mod tests {
 
         assert_eq!(
             updated_files,
-            vec![PathBuf::from("./resources/my_image.png"),]
+            vec![(
+                PathBuf::from("./resources/my_image.png"),
+                FileChangeKind::Modified
+            )]
         );
     }
+
+    #[parameterized(kind = {
+        notify::EventKind::Create(notify::event::CreateKind::File),
+        notify::EventKind::Modify(notify::event::ModifyKind::Data(notify::event::DataChange::Content)),
+        notify::EventKind::Remove(notify::event::RemoveKind::File),
+        notify::EventKind::Modify(notify::event::ModifyKind::Name(notify::event::RenameMode::Any)),
+    }, expected_kind = {
+        FileChangeKind::Created,
+        FileChangeKind::Modified,
+        FileChangeKind::Removed,
+        FileChangeKind::Renamed,
+    })]
+    fn changed_files_report_kind_of_change(kind: notify::EventKind, expected_kind: FileChangeKind) {
+        let (tx, rx): (Sender<notify::Event>, Receiver<notify::Event>) = mpsc::channel();
+        let mut file_watcher = new_test_file_watcher(Duration::from_millis(100), rx);
+
+        send_file_event(&tx, "./resources/my_image.png", kind);
+        let updated_files = file_watcher.update(100);
+
+        assert_eq!(
+            updated_files,
+            vec![(PathBuf::from("./resources/my_image.png"), expected_kind)]
+        );
+    }
+
+    #[test]
+    fn file_access_is_not_reported_as_change() {
+        let (tx, rx): (Sender<notify::Event>, Receiver<notify::Event>) = mpsc::channel();
+        let mut file_watcher = new_test_file_watcher(Duration::from_millis(100), rx);
+
+        send_file_event(
+            &tx,
+            "./resources/my_image.png",
+            notify::EventKind::Access(notify::event::AccessKind::Any),
+        );
+        let updated_files = file_watcher.update(100);
+
+        assert!(updated_files.is_empty());
+    }
 }
@@ -18,9 +18,18 @@ impl<T: PartialEq> InputStack<T> {
         }
     }
 
+    /// Removes and returns the item on top
+    pub fn pop(&mut self) -> Option<T> {
+        self.items.pop()
+    }
+
     pub fn top(&self) -> Option<&T> {
         self.items.last()
     }
+
+    pub fn contains(&self, item: &T) -> bool {
+        self.items.contains(item)
+    }
 }
 
 #[cfg(test)]
@@ -52,4 +61,44 @@ mod tests {
 
         assert_eq!(input_stack.top(), Some(&2));
     }
+
+    #[test]
+    fn most_recently_pushed_item_is_on_top() {
+        let mut input_stack = InputStack::<u32>::new();
+
+        input_stack.push(1);
+        input_stack.push(2);
+        input_stack.push(3);
+
+        assert_eq!(input_stack.top(), Some(&3));
+    }
+
+    #[test]
+    fn pop_removes_top_item_when_pushed_twice() {
+        let mut input_stack = InputStack::<u32>::new();
+
+        input_stack.push(1);
+        input_stack.push(2);
+        input_stack.push(1);
+
+        assert_eq!(input_stack.pop(), Some(1));
+        assert_eq!(input_stack.top(), Some(&2));
+        assert_eq!(input_stack.pop(), Some(2));
+        assert_eq!(input_stack.pop(), Some(1));
+        assert_eq!(input_stack.pop(), None);
+    }
+
+    #[test]
+    fn removing_item_in_middle_preserves_order_of_others() {
+        let mut input_stack = InputStack::<u32>::new();
+
+        input_stack.push(1);
+        input_stack.push(2);
+        input_stack.push(3);
+        input_stack.remove(&2);
+
+        assert_eq!(input_stack.top(), Some(&3));
+        input_stack.remove(&3);
+        assert_eq!(input_stack.top(), Some(&1));
+    }
 }
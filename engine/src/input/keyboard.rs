@@ -2,14 +2,30 @@ use std::cmp::Eq;
 use std::collections::HashMap;
 use std::hash::Hash;
 
+use serde::{Deserialize, Serialize};
+
 use crate::input::button::ButtonEvent;
 
-use super::button::Button;
+use super::button::{Button, ButtonState};
 
 pub struct Keyboard<T> {
     buttons: HashMap<T, Button>,
 }
 
+/// The state of a keyboard for a single frame, e.g. for recording input to
+/// replay later. Keys that are released are left out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputSnapshot<T> {
+    pub keys: Vec<KeySnapshot<T>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeySnapshot<T> {
+    pub key: T,
+    pub state: ButtonState,
+    pub held_ms: u128,
+}
+
 impl<T: PartialEq + Eq + Hash> Keyboard<T> {
     pub fn new() -> Self {
         Keyboard {
@@ -24,12 +40,27 @@ impl<T: PartialEq + Eq + Hash> Keyboard<T> {
             .register_event(event);
     }
 
-    pub fn update(&mut self) {
+    pub fn update(&mut self, delta_time_ms: u128) {
         for (_, button) in &mut self.buttons {
-            button.update();
+            button.update(delta_time_ms);
         }
     }
 
+    /// How long `key` has been held, see `Button::held_ms`
+    pub fn held_ms(&self, key: T) -> u128 {
+        self.buttons
+            .get(&key)
+            .map(|button| button.held_ms())
+            .unwrap_or(0)
+    }
+
+    pub fn is_held_for(&self, key: T, threshold_ms: u128) -> bool {
+        self.buttons
+            .get(&key)
+            .map(|button| button.is_held_for(threshold_ms))
+            .unwrap_or(false)
+    }
+
     #[allow(dead_code)]
     pub fn is_pressed(&self, key: T) -> bool {
         self.buttons
@@ -61,6 +92,82 @@ impl<T: PartialEq + Eq + Hash> Keyboard<T> {
             .map(|button| button.is_released_now())
             .unwrap_or(false)
     }
+
+    /// Whether `key` was pressed this frame while all of `modifiers` are held,
+    /// e.g. `chord_pressed_now(&[Keycode::LCtrl], Keycode::S)` for Ctrl+S
+    pub fn chord_pressed_now(&self, modifiers: &[T], key: T) -> bool
+    where
+        T: Copy,
+    {
+        modifiers.iter().all(|modifier| self.is_pressed(*modifier)) && self.is_pressed_now(key)
+    }
+
+    /// All keys currently held down, in no particular order
+    pub fn pressed_keys(&self) -> Vec<T>
+    where
+        T: Copy,
+    {
+        self.keys_matching(Button::is_pressed)
+    }
+
+    /// All keys pressed down this frame, in no particular order
+    pub fn just_pressed_keys(&self) -> Vec<T>
+    where
+        T: Copy,
+    {
+        self.keys_matching(Button::is_pressed_now)
+    }
+
+    pub fn snapshot(&self) -> InputSnapshot<T>
+    where
+        T: Copy,
+    {
+        let keys = self
+            .buttons
+            .iter()
+            .filter(|(_, button)| button.state() != ButtonState::Released)
+            .map(|(key, button)| KeySnapshot {
+                key: *key,
+                state: button.state(),
+                held_ms: button.held_ms(),
+            })
+            .collect();
+        InputSnapshot { keys }
+    }
+
+    /// Replaces the state of all keys with the snapshot, discarding any
+    /// events registered since the last update
+    pub fn apply_snapshot(&mut self, snapshot: &InputSnapshot<T>)
+    where
+        T: Copy,
+    {
+        self.buttons = snapshot
+            .keys
+            .iter()
+            .map(|key| (key.key, Button::from_state(key.state, key.held_ms)))
+            .collect();
+    }
+
+    /// Releases every pressed key right away, e.g. when input stops being
+    /// driven by something other than the physical keyboard
+    pub fn release_all(&mut self) {
+        for button in self.buttons.values_mut() {
+            if button.is_pressed() {
+                *button = Button::from_state(ButtonState::ReleasedNow, 0);
+            }
+        }
+    }
+
+    fn keys_matching(&self, predicate: impl Fn(&Button) -> bool) -> Vec<T>
+    where
+        T: Copy,
+    {
+        self.buttons
+            .iter()
+            .filter(|(_, button)| predicate(button))
+            .map(|(key, _)| *key)
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -88,7 +195,7 @@ mod tests {
 
         keyboard.register_event(key1, ButtonEvent::Down);
         keyboard.register_event(key2, ButtonEvent::Down);
-        keyboard.update();
+        keyboard.update(0);
 
         assert!(keyboard.is_pressed(key1));
         assert!(keyboard.is_pressed(key2));
@@ -107,15 +214,155 @@ mod tests {
         // press both buttons
         keyboard.register_event(key1, ButtonEvent::Down);
         keyboard.register_event(key2, ButtonEvent::Down);
-        keyboard.update();
+        keyboard.update(0);
 
         // release one of the buttons
         keyboard.register_event(key2, ButtonEvent::Up);
-        keyboard.update();
+        keyboard.update(0);
 
         assert!(keyboard.is_pressed(key1));
         assert!(!keyboard.is_pressed_now(key1));
         assert!(keyboard.is_released(key2));
         assert!(keyboard.is_released_now(key2));
     }
+
+    #[test]
+    fn pressed_keys_contains_all_held_keys() {
+        let mut keyboard = Keyboard::new();
+
+        keyboard.register_event(1, ButtonEvent::Down);
+        keyboard.register_event(2, ButtonEvent::Down);
+        keyboard.update(0);
+        let mut pressed_keys = keyboard.pressed_keys();
+        pressed_keys.sort();
+
+        assert_eq!(pressed_keys, vec![1, 2]);
+    }
+
+    #[test]
+    fn released_key_is_removed_from_pressed_keys() {
+        let mut keyboard = Keyboard::new();
+
+        keyboard.register_event(1, ButtonEvent::Down);
+        keyboard.register_event(2, ButtonEvent::Down);
+        keyboard.update(0);
+        keyboard.register_event(2, ButtonEvent::Up);
+        keyboard.update(0);
+
+        assert_eq!(keyboard.pressed_keys(), vec![1]);
+    }
+
+    #[test]
+    fn just_pressed_keys_only_contains_keys_pressed_this_frame() {
+        let mut keyboard = Keyboard::new();
+
+        keyboard.register_event(1, ButtonEvent::Down);
+        keyboard.update(0);
+        keyboard.register_event(2, ButtonEvent::Down);
+        keyboard.update(0);
+
+        assert_eq!(keyboard.just_pressed_keys(), vec![2]);
+    }
+
+    #[test]
+    fn held_key_is_held_for_threshold_after_enough_frames() {
+        let mut keyboard = Keyboard::new();
+
+        keyboard.register_event(1, ButtonEvent::Down);
+        keyboard.update(16);
+        keyboard.update(16);
+        keyboard.update(16);
+
+        assert_eq!(keyboard.held_ms(1), 32);
+        assert!(keyboard.is_held_for(1, 32));
+        assert!(!keyboard.is_held_for(1, 33));
+        assert!(!keyboard.is_held_for(2, 0));
+    }
+
+    #[test]
+    fn chord_is_pressed_when_key_is_pressed_while_modifier_is_held() {
+        let mut keyboard = Keyboard::new();
+
+        keyboard.register_event(1, ButtonEvent::Down);
+        keyboard.update(0);
+        keyboard.register_event(2, ButtonEvent::Down);
+        keyboard.update(0);
+
+        assert!(keyboard.chord_pressed_now(&[1], 2));
+    }
+
+    #[test]
+    fn chord_is_not_pressed_without_modifier() {
+        let mut keyboard = Keyboard::new();
+
+        keyboard.register_event(2, ButtonEvent::Down);
+        keyboard.update(0);
+
+        assert!(!keyboard.chord_pressed_now(&[1], 2));
+    }
+
+    #[test]
+    fn chord_is_not_pressed_when_key_was_already_down() {
+        let mut keyboard = Keyboard::new();
+
+        keyboard.register_event(1, ButtonEvent::Down);
+        keyboard.register_event(2, ButtonEvent::Down);
+        keyboard.update(0);
+        keyboard.update(0);
+
+        assert!(!keyboard.chord_pressed_now(&[1], 2));
+    }
+
+    fn assert_same_key_states(keyboard: &Keyboard<u32>, other: &Keyboard<u32>, keys: &[u32]) {
+        for key in keys {
+            assert_eq!(keyboard.is_pressed(*key), other.is_pressed(*key));
+            assert_eq!(keyboard.is_pressed_now(*key), other.is_pressed_now(*key));
+            assert_eq!(keyboard.is_released_now(*key), other.is_released_now(*key));
+            assert_eq!(keyboard.held_ms(*key), other.held_ms(*key));
+        }
+    }
+
+    #[test]
+    fn applied_snapshot_reproduces_key_states() {
+        let mut keyboard = Keyboard::new();
+        keyboard.register_event(1, ButtonEvent::Down);
+        keyboard.register_event(2, ButtonEvent::Down);
+        keyboard.update(16);
+        keyboard.register_event(2, ButtonEvent::Up);
+        keyboard.register_event(3, ButtonEvent::Down);
+        keyboard.update(16);
+
+        let mut replayed_keyboard = Keyboard::new();
+        replayed_keyboard.register_event(4, ButtonEvent::Down);
+        replayed_keyboard.update(0);
+        replayed_keyboard.apply_snapshot(&keyboard.snapshot());
+
+        assert_same_key_states(&keyboard, &replayed_keyboard, &[1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn snapshot_survives_serialization() {
+        let mut keyboard = Keyboard::new();
+        keyboard.register_event(1, ButtonEvent::Down);
+        keyboard.update(0);
+
+        let json = serde_json::to_string(&keyboard.snapshot()).unwrap();
+        let snapshot: InputSnapshot<u32> = serde_json::from_str(&json).unwrap();
+        let mut replayed_keyboard = Keyboard::new();
+        replayed_keyboard.apply_snapshot(&snapshot);
+
+        assert_same_key_states(&keyboard, &replayed_keyboard, &[1]);
+    }
+
+    #[test]
+    fn released_keys_are_left_out_of_snapshot() {
+        let mut keyboard = Keyboard::new();
+        keyboard.register_event(1, ButtonEvent::Down);
+        keyboard.update(0);
+        keyboard.register_event(1, ButtonEvent::Up);
+        keyboard.update(0);
+        keyboard.update(0);
+
+        assert!(keyboard.snapshot().keys.is_empty());
+    }
 }
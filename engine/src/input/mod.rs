@@ -1,18 +1,64 @@
 pub mod button;
 pub mod config;
+pub mod event;
 pub mod file;
 pub mod input_stack;
 pub mod keyboard;
 pub mod mouse;
+pub mod recording;
 
 use crate::input::button::ButtonEvent;
 
-use self::{keyboard::Keyboard, mouse::Mouse};
+use std::path::Path;
+
+use sdl2::event::EventType;
+
+use self::{
+    event::InputEvent,
+    input_stack::InputStack,
+    keyboard::Keyboard,
+    mouse::Mouse,
+    recording::{InputRecorder, RecordingError},
+};
 
 pub struct InputDevices {
     pub quit: bool,
     pub mouse: Mouse,
     pub keyboard: Keyboard<sdl2::keyboard::Keycode>,
+    /// Which part of the program input is currently meant for, e.g. a modal
+    /// dialog. No context means input goes to the game world.
+    contexts: InputStack<String>,
+    /// Events registered this frame, for games that prefer reacting to
+    /// events over polling device state
+    events: Vec<InputEvent>,
+    recorder: InputRecorder,
+}
+
+/// Which input devices are currently captured by an overlay, e.g. ImGui
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InputCapture {
+    pub mouse: bool,
+    pub keyboard: bool,
+}
+
+/// Which categories of high frequency SDL events the engine collects each
+/// frame. Events of a disabled category are disabled in SDL, so they're never
+/// queued.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventMask {
+    pub mouse_motion: bool,
+    pub text_input: bool,
+    pub controller: bool,
+}
+
+impl Default for EventMask {
+    fn default() -> Self {
+        EventMask {
+            mouse_motion: true,
+            text_input: true,
+            controller: true,
+        }
+    }
 }
 
 impl InputDevices {
@@ -21,11 +67,87 @@ impl InputDevices {
             quit: false,
             mouse: Mouse::new(),
             keyboard: Keyboard::new(),
+            contexts: InputStack::new(),
+            events: Vec::new(),
+            recorder: InputRecorder::new(),
         }
     }
 
+    /// Input events registered since the start of the current frame
+    pub fn events(&self) -> &[InputEvent] {
+        &self.events
+    }
+
+    pub fn clear_events(&mut self) {
+        self.events.clear();
+    }
+
+    pub fn push_context(&mut self, context: &str) {
+        self.contexts.push(context.to_owned());
+    }
+
+    /// Removes `context` from the stack, even if it's not on top
+    pub fn pop_context(&mut self, context: &str) {
+        self.contexts.remove(&context.to_owned());
+    }
+
+    pub fn active_context(&self) -> Option<&str> {
+        self.contexts.top().map(String::as_str)
+    }
+
+    /// Whether `context` is anywhere on the stack, e.g. for checking whether
+    /// a pushed context has been closed by the engine
+    pub fn has_context(&self, context: &str) -> bool {
+        self.contexts.contains(&context.to_owned())
+    }
+
+    /// Removes the context on top of the stack, returning the closed context.
+    /// The owner of the context can notice with `has_context`.
+    pub fn close_active_context(&mut self) -> Option<String> {
+        self.contexts.pop()
+    }
+
+    /// Whether a configurable hotkey was pressed this frame. A disabled
+    /// hotkey is never pressed.
+    pub fn is_hotkey_pressed_now(&self, hotkey: Option<sdl2::keyboard::Keycode>) -> bool {
+        hotkey.is_some_and(|key| self.keyboard.is_pressed_now(key))
+    }
+
+    /// Records the keyboard state every frame until `stop_recording`
+    pub fn start_recording(&mut self, path: &Path) {
+        self.recorder.start_recording(path);
+    }
+
+    /// Writes the ongoing recording to disk
+    pub fn stop_recording(&mut self) -> Result<(), RecordingError> {
+        self.recorder.stop_recording()
+    }
+
+    /// Drives the keyboard with a recording made by `start_recording`,
+    /// ignoring live keyboard input until the recording ends. Keys still held
+    /// when it ends are released.
+    pub fn play_recording(&mut self, path: &Path) -> Result<(), RecordingError> {
+        self.recorder.play_recording(path)
+    }
+
+    pub fn is_playing_recording(&self) -> bool {
+        self.recorder.is_playing()
+    }
+
+    /// Records or replays the keyboard state of this frame, called after
+    /// the keyboard has been updated
+    pub fn update_recording(&mut self) {
+        self.recorder.update(&mut self.keyboard);
+    }
+
     pub fn register_event(&mut self, event: &sdl2::event::Event) {
         use sdl2::mouse::MouseButton;
+        if self.recorder.is_playing() && is_keyboard_event(event) {
+            return;
+        }
+        if let Some(input_event) = event::translate_event(event) {
+            self.events.push(input_event);
+        }
         match event {
             sdl2::event::Event::Quit { .. } => {
                 self.quit = true;
@@ -83,3 +205,285 @@ impl InputDevices {
         }
     }
 }
+
+/// Whether `event` is meant for an overlay capturing input rather than the game.
+/// Release events are never captured, so that keys and buttons released over
+/// an overlay don't stay pressed.
+pub fn is_captured_event(event: &sdl2::event::Event, capture: InputCapture) -> bool {
+    use sdl2::event::Event;
+    match event {
+        Event::MouseButtonDown { .. } | Event::MouseMotion { .. } | Event::MouseWheel { .. } => {
+            capture.mouse
+        }
+        Event::KeyDown { .. } | Event::TextInput { .. } => capture.keyboard,
+        _ => false,
+    }
+}
+
+fn is_keyboard_event(event: &sdl2::event::Event) -> bool {
+    use sdl2::event::Event;
+    matches!(
+        event,
+        Event::KeyDown { .. } | Event::KeyUp { .. } | Event::TextInput { .. }
+    )
+}
+
+/// The event types of the categories in `EventMask`
+pub const MASKABLE_EVENT_TYPES: [EventType; 16] = [
+    EventType::MouseMotion,
+    EventType::TextInput,
+    EventType::TextEditing,
+    EventType::ControllerAxisMotion,
+    EventType::ControllerButtonDown,
+    EventType::ControllerButtonUp,
+    EventType::ControllerDeviceAdded,
+    EventType::ControllerDeviceRemoved,
+    EventType::ControllerDeviceRemapped,
+    EventType::JoyAxisMotion,
+    EventType::JoyBallMotion,
+    EventType::JoyHatMotion,
+    EventType::JoyButtonDown,
+    EventType::JoyButtonUp,
+    EventType::JoyDeviceAdded,
+    EventType::JoyDeviceRemoved,
+];
+
+/// Whether events of `event_type` belong to a category enabled in `mask`
+pub fn is_enabled_event_type(event_type: EventType, mask: EventMask) -> bool {
+    match event_type {
+        EventType::MouseMotion => mask.mouse_motion,
+        EventType::TextInput | EventType::TextEditing => mask.text_input,
+        EventType::ControllerAxisMotion
+        | EventType::ControllerButtonDown
+        | EventType::ControllerButtonUp
+        | EventType::ControllerDeviceAdded
+        | EventType::ControllerDeviceRemoved
+        | EventType::ControllerDeviceRemapped
+        | EventType::JoyAxisMotion
+        | EventType::JoyBallMotion
+        | EventType::JoyHatMotion
+        | EventType::JoyButtonDown
+        | EventType::JoyButtonUp
+        | EventType::JoyDeviceAdded
+        | EventType::JoyDeviceRemoved => mask.controller,
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sdl2::{
+        event::Event,
+        keyboard::{Keycode, Mod},
+        mouse::MouseButton,
+    };
+
+    fn mouse_down_event() -> Event {
+        Event::MouseButtonDown {
+            timestamp: 0,
+            window_id: 0,
+            which: 0,
+            mouse_btn: MouseButton::Left,
+            clicks: 1,
+            x: 0,
+            y: 0,
+        }
+    }
+
+    fn key_down_event() -> Event {
+        Event::KeyDown {
+            timestamp: 0,
+            window_id: 0,
+            keycode: Some(Keycode::A),
+            scancode: None,
+            keymod: Mod::NOMOD,
+            repeat: false,
+        }
+    }
+
+    #[test]
+    fn nothing_is_captured_without_capture_flags() {
+        let capture = InputCapture::default();
+
+        assert!(!is_captured_event(&mouse_down_event(), capture));
+        assert!(!is_captured_event(&key_down_event(), capture));
+    }
+
+    #[test]
+    fn mouse_events_are_captured_when_mouse_is_wanted() {
+        let capture = InputCapture {
+            mouse: true,
+            keyboard: false,
+        };
+
+        assert!(is_captured_event(&mouse_down_event(), capture));
+        assert!(!is_captured_event(&key_down_event(), capture));
+    }
+
+    #[test]
+    fn keyboard_events_are_captured_when_keyboard_is_wanted() {
+        let capture = InputCapture {
+            mouse: false,
+            keyboard: true,
+        };
+
+        assert!(!is_captured_event(&mouse_down_event(), capture));
+        assert!(is_captured_event(&key_down_event(), capture));
+    }
+
+    fn key_up_event() -> Event {
+        Event::KeyUp {
+            timestamp: 0,
+            window_id: 0,
+            keycode: Some(Keycode::A),
+            scancode: None,
+            keymod: Mod::NOMOD,
+            repeat: false,
+        }
+    }
+
+    fn mouse_up_event() -> Event {
+        Event::MouseButtonUp {
+            timestamp: 0,
+            window_id: 0,
+            which: 0,
+            mouse_btn: MouseButton::Left,
+            clicks: 1,
+            x: 0,
+            y: 0,
+        }
+    }
+
+    #[test]
+    fn release_events_are_never_captured() {
+        let capture = InputCapture {
+            mouse: true,
+            keyboard: true,
+        };
+
+        assert!(!is_captured_event(&key_up_event(), capture));
+        assert!(!is_captured_event(&mouse_up_event(), capture));
+    }
+
+    #[test]
+    fn live_keyboard_events_are_ignored_during_playback() {
+        let path = std::env::temp_dir().join("engine_input_playback_live_events_test.json");
+        let recording = recording::InputRecording {
+            frames: vec![keyboard::InputSnapshot { keys: Vec::new() }; 2],
+        };
+        recording::save_recording(&path, &recording).unwrap();
+        let mut input = InputDevices::new();
+        input.play_recording(&path).unwrap();
+
+        input.register_event(&key_down_event());
+        input.register_event(&mouse_down_event());
+        input.keyboard.update(16);
+        input.update_recording();
+
+        assert_eq!(input.events().len(), 1);
+        assert!(!input.keyboard.is_pressed(Keycode::A));
+    }
+
+    #[test]
+    fn quit_event_is_never_captured() {
+        let capture = InputCapture {
+            mouse: true,
+            keyboard: true,
+        };
+
+        assert!(!is_captured_event(&Event::Quit { timestamp: 0 }, capture));
+    }
+
+    const NO_EVENTS_MASK: EventMask = EventMask {
+        mouse_motion: false,
+        text_input: false,
+        controller: false,
+    };
+
+    #[test]
+    fn all_event_types_are_enabled_by_default() {
+        let mask = EventMask::default();
+
+        assert!(MASKABLE_EVENT_TYPES
+            .iter()
+            .all(|event_type| is_enabled_event_type(*event_type, mask)));
+    }
+
+    #[test]
+    fn event_types_of_disabled_categories_are_disabled() {
+        let mask = NO_EVENTS_MASK;
+
+        assert!(!is_enabled_event_type(EventType::MouseMotion, mask));
+        assert!(!is_enabled_event_type(EventType::TextInput, mask));
+        assert!(!is_enabled_event_type(
+            EventType::ControllerButtonDown,
+            mask
+        ));
+        assert!(!is_enabled_event_type(EventType::JoyAxisMotion, mask));
+    }
+
+    #[test]
+    fn only_event_types_of_disabled_category_are_disabled() {
+        let mask = EventMask {
+            mouse_motion: false,
+            ..EventMask::default()
+        };
+
+        let disabled = MASKABLE_EVENT_TYPES
+            .iter()
+            .filter(|event_type| !is_enabled_event_type(**event_type, mask))
+            .collect::<Vec<_>>();
+
+        assert_eq!(disabled, [&EventType::MouseMotion]);
+    }
+
+    #[test]
+    fn uncategorized_event_types_are_always_enabled() {
+        let mask = NO_EVENTS_MASK;
+
+        assert!(is_enabled_event_type(EventType::KeyDown, mask));
+        assert!(is_enabled_event_type(EventType::MouseButtonDown, mask));
+        assert!(is_enabled_event_type(EventType::Quit, mask));
+    }
+
+    #[test]
+    fn no_context_is_active_initially() {
+        let input = InputDevices::new();
+
+        assert_eq!(input.active_context(), None);
+    }
+
+    #[test]
+    fn last_pushed_context_is_active() {
+        let mut input = InputDevices::new();
+
+        input.push_context("pause_menu");
+        input.push_context("options");
+
+        assert_eq!(input.active_context(), Some("options"));
+    }
+
+    #[test]
+    fn popping_active_context_activates_previous_one() {
+        let mut input = InputDevices::new();
+        input.push_context("pause_menu");
+        input.push_context("options");
+
+        input.pop_context("options");
+
+        assert_eq!(input.active_context(), Some("pause_menu"));
+    }
+
+    #[test]
+    fn popping_covered_context_keeps_active_context() {
+        let mut input = InputDevices::new();
+        input.push_context("pause_menu");
+        input.push_context("options");
+
+        input.pop_context("pause_menu");
+
+        assert_eq!(input.active_context(), Some("options"));
+        assert!(!input.has_context("pause_menu"));
+    }
+}
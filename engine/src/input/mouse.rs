@@ -13,7 +13,9 @@ pub struct Mouse {
     pub x2_button: Button,
     /// Window relative mouse position
     pub window_pos: glam::IVec2,
-    cursor: Cursor,
+    is_over_canvas: bool,
+    /// None if the cursor couldn't be created, e.g. without a video subsystem
+    cursor: Option<Cursor>,
     cursor_type: SystemCursor,
 }
 
@@ -28,7 +30,8 @@ impl Mouse {
             x1_button: Button::new(),
             x2_button: Button::new(),
             window_pos: glam::IVec2::new(0, 0),
-            cursor: Cursor::from_system(SystemCursor::Arrow).unwrap(),
+            is_over_canvas: false,
+            cursor: Cursor::from_system(SystemCursor::Arrow).ok(),
             cursor_type: SystemCursor::Arrow,
         }
     }
@@ -40,28 +43,129 @@ impl Mouse {
     pub fn set_cursor(&mut self, cursor_type: SystemCursor) {
         // updating without this check causes weird flicker issues on the cursor
         if self.cursor_type != cursor_type {
-            self.cursor = Cursor::from_system(cursor_type).unwrap();
+            self.cursor = Cursor::from_system(cursor_type).ok();
             self.cursor_type = cursor_type;
         }
     }
 
     pub fn reset_cursor(&mut self) {
-        self.cursor = Cursor::from_system(SystemCursor::Arrow).unwrap();
+        self.cursor = Cursor::from_system(SystemCursor::Arrow).ok();
         self.cursor_type = SystemCursor::Arrow;
     }
 
-    pub fn update(&mut self, canvas: &Canvas) {
-        self.left_button.update();
-        self.right_button.update();
-        self.middle_button.update();
-        self.x1_button.update();
-        self.x2_button.update();
+    /// False if the cursor is outside the canvas, e.g. over the letterbox
+    /// bars. The canvas position is then clamped to the nearest canvas edge.
+    pub fn is_mouse_over_canvas(&self) -> bool {
+        self.is_over_canvas
+    }
+
+    pub fn update(&mut self, canvas: &Canvas, delta_time_ms: u128) {
+        self.left_button.update(delta_time_ms);
+        self.right_button.update(delta_time_ms);
+        self.middle_button.update(delta_time_ms);
+        self.x1_button.update(delta_time_ms);
+        self.x2_button.update(delta_time_ms);
+
+        let unclamped_pos = canvas.window_to_canvas(self.window_pos);
+        self.is_over_canvas = is_inside_canvas(unclamped_pos, canvas);
+        self.pos = clamp_to_canvas(unclamped_pos, canvas);
+
+        if let Some(cursor) = &self.cursor {
+            cursor.set();
+        }
+    }
+}
+
+fn is_inside_canvas(pos: glam::IVec2, canvas: &Canvas) -> bool {
+    (0..canvas.size.width as i32).contains(&pos.x)
+        && (0..canvas.size.height as i32).contains(&pos.y)
+}
+
+fn clamp_to_canvas(pos: glam::IVec2, canvas: &Canvas) -> glam::IVec2 {
+    let max_x = i32::max(canvas.size.width as i32 - 1, 0);
+    let max_y = i32::max(canvas.size.height as i32 - 1, 0);
+    glam::ivec2(pos.x.clamp(0, max_x), pos.y.clamp(0, max_y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{geometry::Dimension, input::InputDevices};
+
+    /// A 400x300 canvas scaled up 2x and centered in a 1000x700 window
+    fn scaled_canvas() -> Canvas {
+        Canvas {
+            pos: glam::ivec2(100, 50),
+            size: Dimension {
+                width: 400,
+                height: 300,
+            },
+            scaled_size: Dimension {
+                width: 800,
+                height: 600,
+            },
+            scale: 2.0,
+            max_scale: None,
+            fbo: 0,
+            vao: 0,
+            texture: 0,
+        }
+    }
+
+    #[test]
+    fn motion_event_sets_window_position() {
+        let mut input = InputDevices::new();
+
+        input.register_event(&sdl2::event::Event::MouseMotion {
+            timestamp: 0,
+            window_id: 0,
+            which: 0,
+            mousestate: sdl2::mouse::MouseState::from_sdl_state(0),
+            x: 300,
+            y: 250,
+            xrel: 0,
+            yrel: 0,
+        });
+
+        assert_eq!(input.mouse.window_pos, glam::ivec2(300, 250));
+    }
+
+    #[test]
+    fn update_derives_scaled_canvas_position_from_window_position() {
+        let mut mouse = Mouse::new();
+
+        mouse.set_window_pos(300, 250);
+        mouse.update(&scaled_canvas(), 0);
+
+        assert_eq!(mouse.pos, glam::ivec2(100, 100));
+    }
+
+    #[test]
+    fn cursor_inside_canvas_is_over_canvas() {
+        let mut mouse = Mouse::new();
+
+        mouse.set_window_pos(101, 51);
+        mouse.update(&scaled_canvas(), 0);
+
+        assert!(mouse.is_mouse_over_canvas());
+        assert_eq!(mouse.pos, glam::ivec2(0, 0));
+    }
+
+    #[parameterized(window_pos = {
+        (50, 300), (950, 300), (500, 20), (500, 680)
+    }, expected_pos = {
+        (0, 125), (399, 125), (200, 0), (200, 299)
+    })]
+    fn cursor_in_letterbox_area_is_clamped_to_canvas_edge(
+        window_pos: (i32, i32),
+        expected_pos: (i32, i32),
+    ) {
+        let mut mouse = Mouse::new();
 
-        let offset_x = (self.window_pos.x - canvas.pos.x) as f32;
-        let offset_y = (self.window_pos.y - canvas.pos.y) as f32;
-        self.pos.x = f32::round(offset_x / canvas.scale) as i32;
-        self.pos.y = f32::round(offset_y / canvas.scale) as i32;
+        mouse.set_window_pos(window_pos.0, window_pos.1);
+        mouse.update(&scaled_canvas(), 0);
 
-        self.cursor.set();
+        assert!(!mouse.is_mouse_over_canvas());
+        assert_eq!(mouse.pos, glam::ivec2(expected_pos.0, expected_pos.1));
     }
 }
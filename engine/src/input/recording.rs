@@ -0,0 +1,251 @@
+//! Frame accurate recording of keyboard input, so that a bug can be
+//! reproduced by replaying the input that caused it.
+
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+};
+
+use sdl2::keyboard::Keycode;
+use serde::{Deserialize, Serialize};
+
+use super::keyboard::{InputSnapshot, KeySnapshot, Keyboard};
+
+#[derive(Debug)]
+pub enum RecordingError {
+    IoError(std::io::Error),
+    JsonError(serde_json::error::Error),
+}
+
+/// Keyboard snapshots for each recorded frame. Keys are stored as SDL key
+/// codes, since `Keycode` itself can't be serialized.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputRecording {
+    pub frames: Vec<InputSnapshot<i32>>,
+}
+
+pub struct InputRecorder {
+    state: RecorderState,
+}
+
+enum RecorderState {
+    Idle,
+    Recording {
+        path: PathBuf,
+        recording: InputRecording,
+    },
+    Playing {
+        frames: VecDeque<InputSnapshot<i32>>,
+    },
+}
+
+impl Default for InputRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InputRecorder {
+    pub fn new() -> Self {
+        InputRecorder {
+            state: RecorderState::Idle,
+        }
+    }
+
+    /// Starts recording, replacing any ongoing recording or playback. The
+    /// recording is written to `path` by `stop_recording`.
+    pub fn start_recording(&mut self, path: &Path) {
+        self.state = RecorderState::Recording {
+            path: path.to_owned(),
+            recording: InputRecording::default(),
+        };
+    }
+
+    /// Writes the recorded frames to disk. Does nothing unless recording.
+    pub fn stop_recording(&mut self) -> Result<(), RecordingError> {
+        match std::mem::replace(&mut self.state, RecorderState::Idle) {
+            RecorderState::Recording { path, recording } => save_recording(&path, &recording),
+            state => {
+                self.state = state;
+                Ok(())
+            }
+        }
+    }
+
+    pub fn play_recording(&mut self, path: &Path) -> Result<(), RecordingError> {
+        let recording = load_recording(path)?;
+        self.state = RecorderState::Playing {
+            frames: recording.frames.into(),
+        };
+        Ok(())
+    }
+
+    pub fn is_recording(&self) -> bool {
+        matches!(self.state, RecorderState::Recording { .. })
+    }
+
+    pub fn is_playing(&self) -> bool {
+        matches!(self.state, RecorderState::Playing { .. })
+    }
+
+    /// Records the keyboard state of the current frame, or replaces it with
+    /// the next recorded frame during playback. Should be called after the
+    /// keyboard has been updated for the frame.
+    pub fn update(&mut self, keyboard: &mut Keyboard<Keycode>) {
+        match &mut self.state {
+            RecorderState::Idle => {}
+            RecorderState::Recording { recording, .. } => {
+                recording.frames.push(raw_snapshot(&keyboard.snapshot()));
+            }
+            RecorderState::Playing { frames } => match frames.pop_front() {
+                Some(snapshot) => keyboard.apply_snapshot(&keycode_snapshot(&snapshot)),
+                None => {
+                    log::info!("Input recording finished playing");
+                    keyboard.release_all();
+                    self.state = RecorderState::Idle;
+                }
+            },
+        }
+    }
+}
+
+pub fn save_recording(path: &Path, recording: &InputRecording) -> Result<(), RecordingError> {
+    let json = serde_json::to_string(recording).map_err(RecordingError::JsonError)?;
+    std::fs::write(path, json).map_err(RecordingError::IoError)
+}
+
+pub fn load_recording(path: &Path) -> Result<InputRecording, RecordingError> {
+    let json = std::fs::read_to_string(path).map_err(RecordingError::IoError)?;
+    serde_json::from_str(&json).map_err(RecordingError::JsonError)
+}
+
+fn raw_snapshot(snapshot: &InputSnapshot<Keycode>) -> InputSnapshot<i32> {
+    InputSnapshot {
+        keys: snapshot
+            .keys
+            .iter()
+            .map(|key| KeySnapshot {
+                key: key.key as i32,
+                state: key.state,
+                held_ms: key.held_ms,
+            })
+            .collect(),
+    }
+}
+
+/// Converts a recorded snapshot back to key codes, skipping unknown codes
+fn keycode_snapshot(snapshot: &InputSnapshot<i32>) -> InputSnapshot<Keycode> {
+    InputSnapshot {
+        keys: snapshot
+            .keys
+            .iter()
+            .filter_map(|key| {
+                Keycode::from_i32(key.key).map(|keycode| KeySnapshot {
+                    key: keycode,
+                    state: key.state,
+                    held_ms: key.held_ms,
+                })
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::button::ButtonEvent;
+
+    /// Key events for each frame of a test recording
+    const FRAME_EVENTS: [&[(Keycode, ButtonEvent)]; 5] = [
+        &[(Keycode::A, ButtonEvent::Down)],
+        &[],
+        &[(Keycode::Space, ButtonEvent::Down)],
+        &[(Keycode::A, ButtonEvent::Up)],
+        &[(Keycode::Space, ButtonEvent::Up)],
+    ];
+
+    fn pressed_keys(keyboard: &Keyboard<Keycode>) -> (bool, bool, bool) {
+        (
+            keyboard.is_pressed(Keycode::A),
+            keyboard.is_pressed_now(Keycode::A),
+            keyboard.is_pressed(Keycode::Space),
+        )
+    }
+
+    #[test]
+    fn replayed_recording_reproduces_pressed_keys() {
+        let path = std::env::temp_dir().join("engine_input_recording_test.json");
+        let mut keyboard = Keyboard::new();
+        let mut recorder = InputRecorder::new();
+
+        recorder.start_recording(&path);
+        let mut recorded_keys = Vec::new();
+        for events in FRAME_EVENTS {
+            for (key, event) in events {
+                keyboard.register_event(*key, *event);
+            }
+            keyboard.update(16);
+            recorder.update(&mut keyboard);
+            recorded_keys.push(pressed_keys(&keyboard));
+        }
+        recorder.stop_recording().unwrap();
+
+        let mut replayed_keyboard = Keyboard::new();
+        recorder.play_recording(&path).unwrap();
+        let mut replayed_keys = Vec::new();
+        for _ in FRAME_EVENTS {
+            replayed_keyboard.register_event(Keycode::Space, ButtonEvent::Down);
+            replayed_keyboard.update(16);
+            recorder.update(&mut replayed_keyboard);
+            replayed_keys.push(pressed_keys(&replayed_keyboard));
+        }
+
+        assert_eq!(replayed_keys, recorded_keys);
+    }
+
+    #[test]
+    fn playback_stops_after_last_frame() {
+        let path = std::env::temp_dir().join("engine_input_recording_stop_test.json");
+        save_recording(&path, &InputRecording::default()).unwrap();
+        let mut recorder = InputRecorder::new();
+
+        recorder.play_recording(&path).unwrap();
+        recorder.update(&mut Keyboard::new());
+
+        assert!(!recorder.is_playing());
+    }
+
+    #[test]
+    fn keys_held_at_end_of_playback_are_released() {
+        let path = std::env::temp_dir().join("engine_input_recording_release_test.json");
+        let mut keyboard = Keyboard::new();
+        keyboard.register_event(Keycode::A, ButtonEvent::Down);
+        keyboard.update(16);
+        let recording = InputRecording {
+            frames: vec![raw_snapshot(&keyboard.snapshot())],
+        };
+        save_recording(&path, &recording).unwrap();
+        let mut replayed_keyboard = Keyboard::new();
+        let mut recorder = InputRecorder::new();
+
+        recorder.play_recording(&path).unwrap();
+        recorder.update(&mut replayed_keyboard);
+        assert!(replayed_keyboard.is_pressed(Keycode::A));
+        replayed_keyboard.update(16);
+        recorder.update(&mut replayed_keyboard);
+
+        assert!(!recorder.is_playing());
+        assert!(!replayed_keyboard.is_pressed(Keycode::A));
+        assert!(replayed_keyboard.is_released_now(Keycode::A));
+    }
+
+    #[test]
+    fn keycodes_survive_raw_conversion() {
+        let mut keyboard = Keyboard::new();
+        keyboard.register_event(Keycode::Escape, ButtonEvent::Down);
+        keyboard.update(0);
+        let snapshot = keyboard.snapshot();
+
+        assert_eq!(keycode_snapshot(&raw_snapshot(&snapshot)), snapshot);
+    }
+}
@@ -2,6 +2,7 @@
 #[macro_use]
 extern crate parameterized;
 
+pub mod assets;
 pub mod audio;
 pub mod geometry;
 pub mod graphics;
@@ -9,26 +10,72 @@ pub mod hot_reload;
 pub mod imgui;
 pub mod input;
 pub mod logging;
+pub mod resources;
+pub mod tween;
 
-use crate::input::config::ProgramConfig;
+pub use resources::set_resource_root;
+
+use crate::input::config::{Hotkeys, ProgramConfig};
 use graphics::{
     fonts::FontID,
-    rendering::{self, TextureID},
+    rendering::{self, GlDebugSeverity, TextureID},
 };
 use sdl2::{keyboard::Keycode, video::GLContext};
 
 use crate::{
     audio::AudioSystem,
     graphics::{
-        animation::AnimationSystem, fonts::TextSystem, fullscreen::FullscreenSystem,
-        rendering::Renderer, sprites::SpriteSystem,
+        animation::AnimationSystem,
+        debug_draw::DebugDraw,
+        fonts::TextSystem,
+        fullscreen::{resize_on_window_mode_change, FullscreenSystem, WindowMode},
+        monitor,
+        rendering::Renderer,
+        screenshot::{self, ScreenshotError, DEFAULT_SCREENSHOT_DIR},
+        sprites::SpriteSystem,
     },
-    input::InputDevices,
+    hot_reload::{ResourceReloader, DEFAULT_DEBOUNCE_TIME},
+    input::{event::InputEvent, file::without_cur_dir, EventMask, InputCapture, InputDevices},
+    resources::resolve_resource_path,
 };
 use itertools::Itertools;
 use sdl2::video::GLProfile;
-use std::{collections::HashMap, path::PathBuf, time::SystemTime};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::Instant,
+};
 
+/// Whether to create a debug OpenGL context and log its debug messages
+const DEBUG_GL: bool = true;
+
+/// The engine systems are public fields, used directly by the game:
+///
+/// ```no_run
+/// fn update(engine: &mut engine::Engine) {
+///     let _log_statements = engine.captured_log.len();
+///     let _texture = engine.textures.get("resources/smiley.png");
+///     let _canvas_size = engine.renderer.canvas().size;
+///     let _sprites = &mut engine.sprites;
+///     let _animation = &mut engine.animation;
+///     let _audio = &mut engine.audio;
+///     let _text = &mut engine.text;
+///     let _fonts = &engine.fonts;
+///     let _input = &engine.input;
+/// }
+/// ```
+///
+/// Each frame the engine methods are called in this order, with the game
+/// updating and drawing in between:
+///
+/// 1. `begin_frame` measures the frame time and collects SDL events
+/// 2. `handle_input` updates the input devices from the events
+/// 3. the game updates, reading input and submitting draw calls
+/// 4. `update` handles engine hotkeys, advances the engine systems, e.g.
+///    animations, by the frame time and reloads changed resources
+/// 5. `render` draws the submitted draw calls, and saves a screenshot if
+///    the screenshot hotkey was pressed
+/// 6. `end_frame` presents the frame
 pub struct Engine<'a> {
     // Logging
     pub captured_log: &'static Vec<logging::LogStatement>,
@@ -46,6 +93,12 @@ pub struct Engine<'a> {
     pub renderer: Renderer,
     pub frame: FrameTime,
     should_quit: bool,
+    input_capture: InputCapture,
+    suppress_captured_input: bool,
+    event_mask: EventMask,
+    hotkeys: Hotkeys,
+    screenshot_dir: PathBuf,
+    screenshot_requested: bool,
 
     // Systems
     pub fullscreen_system: FullscreenSystem,
@@ -53,6 +106,8 @@ pub struct Engine<'a> {
     pub sprites: SpriteSystem,
     pub animation: AnimationSystem,
     pub text: TextSystem,
+    debug_draw: DebugDraw,
+    resource_reloader: ResourceReloader,
 
     // Assets
     pub fonts: LoadedFonts,
@@ -70,9 +125,14 @@ pub struct SdlContext {
 
 pub struct FrameTime {
     pub delta_ms: u128,
-    prev_time: SystemTime,
+    prev_time: Instant,
+    max_delta_ms: u128,
+    total_elapsed_ms: u128,
 }
 
+/// Longest frame time passed on to the systems unless configured otherwise
+pub const DEFAULT_MAX_DELTA_MS: u128 = 100;
+
 pub struct LoadedFonts {
     pub arial_16: FontID,
 }
@@ -84,16 +144,18 @@ pub fn init_sdl(
     window_height: u32,
 ) -> SdlContext {
     let sdl = sdl2::init().unwrap();
-    let sdl_video = init_video(&sdl);
+    let sdl_video = init_video(&sdl, config.msaa_samples);
     let sdl_audio = init_audio(&sdl);
     let sdl_mixer = init_mixer(&sdl_audio);
     let sdl_event_pump = sdl.event_pump().unwrap();
+    let monitors = monitor::list_monitors(&sdl_video);
+    let monitor = monitor::validate_monitor_index(&monitors, config.monitor);
     let window = init_window(
         &sdl_video,
         window_title,
         window_width,
         window_height,
-        config.monitor as i32,
+        monitor as i32,
     );
     log::info!("SDL initialized");
 
@@ -120,11 +182,10 @@ pub fn init_engine<'a>(sdl: SdlContext, gl: &GLContext) -> Engine<'a> {
     // Game Loop
     let (window_width, window_height) = sdl.window.size();
     let input = InputDevices::new();
-    let mut renderer = Renderer::new(gl, window_width, window_height);
-    let frame = FrameTime {
-        delta_ms: 0,
-        prev_time: SystemTime::now(),
-    };
+    let mut renderer =
+        Renderer::new(gl, window_width, window_height).expect("Could not initialize renderer");
+    renderer.set_debug_output(DEBUG_GL, GlDebugSeverity::Low);
+    let frame = FrameTime::new(Instant::now());
     let should_quit = false;
 
     // Systems
@@ -133,14 +194,20 @@ pub fn init_engine<'a>(sdl: SdlContext, gl: &GLContext) -> Engine<'a> {
     let sprite_system = SpriteSystem::new();
     let animation_system = AnimationSystem::new();
     let mut text_system = TextSystem::new();
+    let resource_reloader = ResourceReloader::new(
+        &resolve_resource_path(Path::new("resources")),
+        DEFAULT_DEBOUNCE_TIME,
+    );
 
     // Assets
-    let arial_16 = text_system.load_font(
-        gl,
-        &mut renderer,
-        &PathBuf::from("./resources/font/arial.ttf"),
-        16,
-    );
+    let arial_16 = text_system
+        .load_font(
+            gl,
+            &mut renderer,
+            &PathBuf::from("./resources/font/arial.ttf"),
+            16,
+        )
+        .expect("Could not load default font");
     let fonts = LoadedFonts { arial_16 };
 
     let mut textures = HashMap::new();
@@ -169,6 +236,12 @@ pub fn init_engine<'a>(sdl: SdlContext, gl: &GLContext) -> Engine<'a> {
         renderer,
         frame,
         should_quit,
+        input_capture: InputCapture::default(),
+        suppress_captured_input: false,
+        event_mask: EventMask::default(),
+        hotkeys: Hotkeys::default(),
+        screenshot_dir: PathBuf::from(DEFAULT_SCREENSHOT_DIR),
+        screenshot_requested: false,
 
         // Systems
         fullscreen_system,
@@ -176,6 +249,8 @@ pub fn init_engine<'a>(sdl: SdlContext, gl: &GLContext) -> Engine<'a> {
         sprites: sprite_system,
         animation: animation_system,
         text: text_system,
+        debug_draw: DebugDraw::new(),
+        resource_reloader,
 
         // Assets
         fonts,
@@ -183,16 +258,59 @@ pub fn init_engine<'a>(sdl: SdlContext, gl: &GLContext) -> Engine<'a> {
     }
 }
 
+impl FrameTime {
+    fn new(start_time: Instant) -> Self {
+        FrameTime {
+            delta_ms: 0,
+            prev_time: start_time,
+            max_delta_ms: DEFAULT_MAX_DELTA_MS,
+            total_elapsed_ms: 0,
+        }
+    }
+
+    /// Sum of all frame deltas so far, e.g. for scrolling backgrounds or
+    /// shader time. Not affected by pausing animations, and like `delta_ms`
+    /// it doesn't jump ahead after a stall.
+    pub fn total_elapsed_ms(&self) -> u128 {
+        self.total_elapsed_ms
+    }
+
+    fn advance(&mut self, time_now: Instant) {
+        self.delta_ms = frame_delta_ms(self.prev_time, time_now, self.max_delta_ms);
+        self.total_elapsed_ms += self.delta_ms;
+        self.prev_time = time_now;
+    }
+}
+
 impl<'a> Engine<'a> {
+    /// Starts a new frame, returning the SDL events since the last one
     pub fn begin_frame(&mut self) -> Vec<sdl2::event::Event> {
-        let time_now = SystemTime::now();
-        self.frame.delta_ms = time_now
-            .duration_since(self.frame.prev_time)
-            .map_or(self.frame.delta_ms, |time| time.as_millis());
-        self.frame.prev_time = time_now;
+        self.frame.advance(Instant::now());
         self.sdl_event_pump.poll_iter().collect_vec()
     }
 
+    /// Limit the frame time seen by the systems, so that a stall such as a
+    /// breakpoint or a hot reload doesn't make animations jump ahead
+    pub fn set_max_delta_ms(&mut self, max_delta_ms: u128) {
+        self.frame.max_delta_ms = max_delta_ms;
+    }
+
+    /// Choose which categories of high frequency events are collected each
+    /// frame, e.g. to skip mouse motion in scenes that don't use the mouse.
+    pub fn set_event_mask(&mut self, mask: EventMask) {
+        if mask == self.event_mask {
+            return;
+        }
+        for event_type in input::MASKABLE_EVENT_TYPES {
+            if input::is_enabled_event_type(event_type, mask) {
+                self.sdl_event_pump.enable_event(event_type);
+            } else {
+                self.sdl_event_pump.disable_event(event_type);
+            }
+        }
+        self.event_mask = mask;
+    }
+
     pub fn request_quit(&mut self) {
         log::info!("Shutting down");
         self.should_quit = true;
@@ -202,9 +320,29 @@ impl<'a> Engine<'a> {
         self.should_quit
     }
 
-    pub fn handle_input(&mut self, events: &Vec<sdl2::event::Event>) {
+    /// Tell the engine which devices an overlay such as ImGui is using.
+    /// Should be called before `handle_input` each frame.
+    pub fn set_input_capture(&mut self, capture: InputCapture) {
+        self.input_capture = capture;
+    }
+
+    /// Opt in to ignoring input captured by an overlay, so that e.g. typing
+    /// in an ImGui text field doesn't also move the player. Off by default.
+    pub fn set_suppress_captured_input(&mut self, suppress: bool) {
+        self.suppress_captured_input = suppress;
+    }
+
+    /// Registers this frame's SDL events with the input devices, returning
+    /// them translated to engine input events. The same events can be read
+    /// later in the frame through `input.events()`.
+    pub fn handle_input(&mut self, events: &Vec<sdl2::event::Event>) -> Vec<InputEvent> {
+        self.input.clear_events();
         for event in events {
-            self.input.register_event(&event);
+            let is_suppressed =
+                self.suppress_captured_input && input::is_captured_event(event, self.input_capture);
+            if !is_suppressed {
+                self.input.register_event(&event);
+            }
             match *event {
                 sdl2::event::Event::Window { win_event, .. } => {
                     if let sdl2::event::WindowEvent::Resized(width, height) = win_event {
@@ -214,36 +352,178 @@ impl<'a> Engine<'a> {
                 _ => (),
             }
         }
-        self.input.mouse.update(self.renderer.canvas());
-        self.input.keyboard.update();
+        self.input
+            .mouse
+            .update(self.renderer.canvas(), self.frame.delta_ms);
+        self.input.keyboard.update(self.frame.delta_ms);
+        self.input.update_recording();
+
+        self.input.events().to_vec()
     }
 
+    /// Handles engine hotkeys and advances the engine systems by the frame
+    /// time. Called after the game has updated.
     pub fn update(&mut self) {
         self.fullscreen_system.update(&self.window);
-        if self.input.keyboard.is_pressed_now(Keycode::F11) {
-            self.fullscreen_system
-                .toggle_fullscreen(&mut self.window, &self.sdl_video);
-            let (width, height) = self.window.size();
-            self.renderer.on_window_resize(width, height);
+        if self.input.is_hotkey_pressed_now(self.hotkeys.fullscreen) {
+            self.toggle_fullscreen();
+        }
+
+        if self.input.is_hotkey_pressed_now(self.hotkeys.screenshot) {
+            self.screenshot_requested = true;
         }
 
-        if self.input.quit {
-            self.should_quit = true;
+        if handle_quit_input(&mut self.input, self.hotkeys.quit) {
+            self.request_quit();
         }
 
-        self.animation.update(self.frame.delta_ms);
+        update_systems(&mut self.animation, &self.frame);
+        self.resource_reloader.update(
+            self.frame.delta_ms,
+            &mut self.renderer,
+            &mut self.sprites,
+            &mut self.animation,
+            &mut self.audio,
+            &mut self.text,
+        );
+    }
+
+    /// Reloads changed files in the resources directory during `update`.
+    /// Resources to reload are registered with its reloaders.
+    pub fn resource_reloader(&mut self) -> &mut ResourceReloader {
+        &mut self.resource_reloader
+    }
+
+    /// Toggles between windowed mode and the configured fullscreen mode
+    pub fn toggle_fullscreen(&mut self) {
+        let mode_changed = self
+            .fullscreen_system
+            .toggle_fullscreen(&mut self.window, &self.sdl_video);
+        self.on_window_mode_changed(mode_changed);
+    }
+
+    pub fn set_fullscreen(&mut self, fullscreen: bool) {
+        let mode = if fullscreen {
+            self.fullscreen_system.fullscreen_mode()
+        } else {
+            WindowMode::Windowed
+        };
+        let mode_changed =
+            self.fullscreen_system
+                .set_window_mode(&mut self.window, &self.sdl_video, mode);
+        self.on_window_mode_changed(mode_changed);
+    }
+
+    /// Engine and debug hotkeys, e.g. for toggling fullscreen in `update`
+    pub fn hotkeys(&self) -> &Hotkeys {
+        &self.hotkeys
+    }
+
+    pub fn set_hotkeys(&mut self, hotkeys: Hotkeys) {
+        self.hotkeys = hotkeys;
+    }
+
+    /// Directory screenshots are saved to, relative to the working directory
+    pub fn set_screenshot_dir(&mut self, dir: &Path) {
+        self.screenshot_dir = dir.to_owned();
+    }
+
+    /// Saves the canvas as last rendered to a timestamped PNG file in the
+    /// screenshot directory, returning the path of the file
+    pub fn screenshot_to_file(&self) -> Result<PathBuf, ScreenshotError> {
+        screenshot::save_screenshot(&self.renderer, &self.screenshot_dir)
+    }
+
+    fn on_window_mode_changed(&mut self, mode_changed: bool) {
+        resize_on_window_mode_change(mode_changed, self.window.size(), &mut self.renderer);
     }
 
     pub fn render(&mut self, gl: &GLContext) {
+        self.debug_draw
+            .flush(&mut self.renderer, &mut self.text, self.fonts.arial_16);
         self.renderer.render(gl);
+
+        if self.screenshot_requested {
+            self.screenshot_requested = false;
+            match self.screenshot_to_file() {
+                Ok(path) => log::info!("Saved screenshot \"{}\"", path.display()),
+                Err(error) => log::error!("Could not save screenshot: {:?}", error),
+            }
+        }
+    }
+
+    /// Submit debug shapes from anywhere during the frame, drawn on top of
+    /// the game in `render` while enabled
+    pub fn debug_draw(&mut self) -> &mut DebugDraw {
+        &mut self.debug_draw
     }
 
     pub fn end_frame(&mut self, _gl: &GLContext) {
         self.window.gl_swap_window();
     }
+
+    /// Loads a texture from an image file, or returns the already loaded
+    /// texture if the path has been loaded before. Loaded textures are kept
+    /// in `textures`, keyed by path, and reloaded when their file changes.
+    pub fn load_texture(
+        &mut self,
+        gl: &GLContext,
+        path: &Path,
+    ) -> Result<TextureID, rendering::LoadError> {
+        let renderer = &mut self.renderer;
+        let texture_reloader = self.resource_reloader.texture_reloader();
+        load_cached(&mut self.textures, path, |path| {
+            let texture_id = rendering::load_texture_from_image_path(gl, renderer, path)?;
+            texture_reloader.register_texture(texture_id, path);
+            Ok(texture_id)
+        })
+    }
+}
+
+/// Time elapsed between frames, clamped to `max_delta_ms`. A `time_now`
+/// before `prev_time` gives a delta of 0.
+fn frame_delta_ms(prev_time: Instant, time_now: Instant, max_delta_ms: u128) -> u128 {
+    time_now
+        .saturating_duration_since(prev_time)
+        .as_millis()
+        .min(max_delta_ms)
+}
+
+/// Advances the systems that progress over time by this frame's delta
+fn update_systems(animation: &mut AnimationSystem, frame: &FrameTime) {
+    animation.update(frame.delta_ms);
+}
+
+/// Whether this frame's input should quit the program. The quit key closes
+/// the active input context, e.g. an open menu, and only quits without one.
+fn handle_quit_input(input: &mut InputDevices, quit_key: Option<Keycode>) -> bool {
+    let quit_key_quits =
+        input.is_hotkey_pressed_now(quit_key) && input.close_active_context().is_none();
+    input.quit || quit_key_quits
 }
 
-fn init_video(sdl: &sdl2::Sdl) -> sdl2::VideoSubsystem {
+/// Gets the asset cached for `path`, loading and caching it if missing
+fn load_cached<T: Copy, E>(
+    cache: &mut HashMap<String, T>,
+    path: &Path,
+    load: impl FnOnce(&Path) -> Result<T, E>,
+) -> Result<T, E> {
+    let key = asset_key(path);
+    if let Some(asset) = cache.get(&key) {
+        return Ok(*asset);
+    }
+    let asset = load(path)?;
+    cache.insert(key, asset);
+    Ok(asset)
+}
+
+/// The key of an asset path in the asset caches, so that e.g.
+/// "./resources/smiley.png" and "resources/smiley.png" are the same asset
+fn asset_key(path: &Path) -> String {
+    without_cur_dir(path).to_string_lossy().replace('\\', "/")
+}
+
+fn init_video(sdl: &sdl2::Sdl, msaa_samples: u8) -> sdl2::VideoSubsystem {
     let sdl_video = sdl.video().unwrap();
 
     // hint that we'll use the "330 core" OpenGL profile
@@ -251,11 +531,15 @@ fn init_video(sdl: &sdl2::Sdl) -> sdl2::VideoSubsystem {
     gl_attr.set_context_version(3, 3);
     gl_attr.set_context_profile(GLProfile::Core);
 
-    let debug_gl = true;
-    if debug_gl {
+    if DEBUG_GL {
         gl_attr.set_context_flags().debug().set();
     }
 
+    if msaa_samples > 0 {
+        gl_attr.set_multisample_buffers(1);
+        gl_attr.set_multisample_samples(msaa_samples);
+    }
+
     sdl_video
 }
 
@@ -302,3 +586,222 @@ fn init_window(
 
     return window;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::fullscreen::{toggled_window_mode, WindowResizeListener};
+    use crate::input::button::ButtonEvent;
+    use std::time::Duration;
+
+    fn press_escape(input: &mut InputDevices) {
+        input
+            .keyboard
+            .register_event(Keycode::Escape, ButtonEvent::Down);
+        input.keyboard.update(0);
+    }
+
+    #[derive(Default)]
+    struct ResizeCounter {
+        resize_count: u32,
+    }
+
+    impl WindowResizeListener for ResizeCounter {
+        fn on_window_resize(&mut self, _width: u32, _height: u32) {
+            self.resize_count += 1;
+        }
+    }
+
+    fn toggle_window_mode(mode: &mut WindowMode, renderer: &mut ResizeCounter) {
+        let new_mode = toggled_window_mode(*mode, WindowMode::BorderlessFullscreen);
+        resize_on_window_mode_change(new_mode != *mode, (1920, 1080), renderer);
+        *mode = new_mode;
+    }
+
+    fn fullscreen_resize_count(pressed_key: Keycode, hotkeys: Hotkeys, frames: u32) -> u32 {
+        let mut input = InputDevices::new();
+        input
+            .keyboard
+            .register_event(pressed_key, ButtonEvent::Down);
+        let mut mode = WindowMode::Windowed;
+        let mut renderer = ResizeCounter::default();
+        for _ in 0..frames {
+            input.keyboard.update(0);
+            if input.is_hotkey_pressed_now(hotkeys.fullscreen) {
+                toggle_window_mode(&mut mode, &mut renderer);
+            }
+        }
+        renderer.resize_count
+    }
+
+    #[test]
+    fn holding_fullscreen_key_resizes_renderer_once() {
+        assert_eq!(
+            fullscreen_resize_count(Keycode::F11, Hotkeys::default(), 3),
+            1
+        );
+    }
+
+    #[test]
+    fn disabled_fullscreen_key_never_resizes_renderer() {
+        let hotkeys = Hotkeys {
+            fullscreen: None,
+            ..Hotkeys::default()
+        };
+
+        assert_eq!(fullscreen_resize_count(Keycode::F11, hotkeys, 3), 0);
+    }
+
+    #[test]
+    fn remapped_fullscreen_key_replaces_default() {
+        let hotkeys = Hotkeys {
+            fullscreen: Some(Keycode::F8),
+            ..Hotkeys::default()
+        };
+
+        assert_eq!(fullscreen_resize_count(Keycode::F8, hotkeys, 1), 1);
+        assert_eq!(fullscreen_resize_count(Keycode::F11, hotkeys, 1), 0);
+    }
+
+    #[test]
+    fn toggling_fullscreen_resizes_renderer_each_time() {
+        let mut mode = WindowMode::Windowed;
+        let mut renderer = ResizeCounter::default();
+
+        toggle_window_mode(&mut mode, &mut renderer);
+        toggle_window_mode(&mut mode, &mut renderer);
+
+        assert_eq!(mode, WindowMode::Windowed);
+        assert_eq!(renderer.resize_count, 2);
+    }
+
+    #[test]
+    fn unchanged_window_mode_does_not_resize_renderer() {
+        let mut renderer = ResizeCounter::default();
+
+        resize_on_window_mode_change(false, (1920, 1080), &mut renderer);
+
+        assert_eq!(renderer.resize_count, 0);
+    }
+
+    #[test]
+    fn escape_closes_active_context_instead_of_quitting() {
+        let mut input = InputDevices::new();
+        input.push_context("pause_menu");
+
+        press_escape(&mut input);
+        let should_quit = handle_quit_input(&mut input, Some(Keycode::Escape));
+
+        assert!(!should_quit);
+        assert_eq!(input.active_context(), None);
+    }
+
+    #[test]
+    fn escape_quits_without_active_context() {
+        let mut input = InputDevices::new();
+
+        press_escape(&mut input);
+
+        assert!(handle_quit_input(&mut input, Some(Keycode::Escape)));
+    }
+
+    #[test]
+    fn quit_event_quits_even_with_active_context() {
+        let mut input = InputDevices::new();
+        input.push_context("pause_menu");
+        input.quit = true;
+
+        assert!(handle_quit_input(&mut input, Some(Keycode::Escape)));
+        assert_eq!(input.active_context(), Some("pause_menu"));
+    }
+
+    #[parameterized(elapsed_ms = { 16, 100, 5000 }, expected_delta_ms = { 16, 100, 100 })]
+    fn frame_delta_is_clamped(elapsed_ms: u64, expected_delta_ms: u128) {
+        let prev_time = Instant::now();
+        let time_now = prev_time + Duration::from_millis(elapsed_ms);
+
+        let delta_ms = frame_delta_ms(prev_time, time_now, DEFAULT_MAX_DELTA_MS);
+
+        assert_eq!(delta_ms, expected_delta_ms);
+    }
+
+    #[test]
+    fn backwards_time_step_gives_zero_delta() {
+        let time_now = Instant::now();
+        let prev_time = time_now + Duration::from_millis(50);
+
+        assert_eq!(frame_delta_ms(prev_time, time_now, DEFAULT_MAX_DELTA_MS), 0);
+    }
+
+    #[test]
+    fn updating_systems_advances_running_animation() {
+        let mut animation = AnimationSystem::new();
+        let animation_id = animation.add_animation(0, 1, &[100, 100]);
+        animation.start_animation(animation_id);
+        let start_time = Instant::now();
+        let mut frame = FrameTime::new(start_time);
+
+        frame.advance(start_time + Duration::from_millis(100));
+        update_systems(&mut animation, &frame);
+
+        assert_eq!(animation.current_frame(animation_id), Some(1));
+    }
+
+    #[test]
+    fn total_elapsed_time_accumulates_frame_deltas() {
+        let start_time = Instant::now();
+        let mut frame = FrameTime::new(start_time);
+
+        for elapsed_ms in [16, 33, 50] {
+            frame.advance(start_time + Duration::from_millis(elapsed_ms));
+        }
+
+        assert_eq!(frame.delta_ms, 17);
+        assert_eq!(frame.total_elapsed_ms(), 50);
+    }
+
+    #[test]
+    fn total_elapsed_time_accumulates_clamped_deltas() {
+        let start_time = Instant::now();
+        let mut frame = FrameTime::new(start_time);
+
+        frame.advance(start_time + Duration::from_millis(16));
+        frame.advance(start_time + Duration::from_millis(5016));
+
+        assert_eq!(frame.total_elapsed_ms(), 16 + DEFAULT_MAX_DELTA_MS);
+    }
+
+    #[test]
+    fn loading_same_path_twice_returns_cached_asset() {
+        let mut cache = HashMap::new();
+        let mut load_count = 0;
+        let mut load = |_: &Path| -> Result<u32, ()> {
+            load_count += 1;
+            Ok(load_count)
+        };
+
+        let first = load_cached(&mut cache, Path::new("resources/smiley.png"), &mut load);
+        let second = load_cached(&mut cache, Path::new("resources/smiley.png"), &mut load);
+
+        assert_eq!(first, Ok(1));
+        assert_eq!(second, Ok(1));
+        assert_eq!(load_count, 1);
+    }
+
+    #[test]
+    fn failed_load_is_not_cached() {
+        let mut cache = HashMap::<String, u32>::new();
+
+        let result = load_cached(&mut cache, Path::new("missing.png"), |_| Err(()));
+
+        assert_eq!(result, Err(()));
+        assert!(cache.is_empty());
+    }
+
+    #[parameterized(path = {
+        "resources/smiley.png", "./resources/smiley.png", "./resources/./smiley.png"
+    })]
+    fn paths_to_same_asset_have_same_key(path: &str) {
+        assert_eq!(asset_key(Path::new(path)), "resources/smiley.png");
+    }
+}
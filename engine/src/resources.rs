@@ -0,0 +1,129 @@
+//! Resolving asset paths against a resource root directory, so that assets
+//! are found regardless of the working directory the program is launched from.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+/// Environment variable overriding the default resource root
+pub const RESOURCE_ROOT_ENV_VAR: &str = "RESOURCE_ROOT";
+
+static RESOURCE_ROOT: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Resolve relative asset paths against `root` from now on. Like the logger,
+/// this is global per binary, so the game library has its own root to set.
+pub fn set_resource_root(root: &Path) {
+    *RESOURCE_ROOT.write().unwrap() = Some(root.to_owned());
+}
+
+/// The directory relative asset paths are resolved against. Defaults to the
+/// `RESOURCE_ROOT` environment variable if set, else `default_root_dir`.
+pub fn resource_root() -> PathBuf {
+    let configured_root = RESOURCE_ROOT.read().unwrap().clone();
+    let env_root = std::env::var_os(RESOURCE_ROOT_ENV_VAR).map(PathBuf::from);
+    select_resource_root(configured_root, env_root, default_root_dir())
+}
+
+/// The working directory in debug builds, so that assets are loaded and hot
+/// reloaded from the project's `resources/` rather than the copy build.rs
+/// makes next to the executable. The executable directory in release builds.
+fn default_root_dir() -> Option<PathBuf> {
+    if cfg!(debug_assertions) {
+        std::env::current_dir().ok()
+    } else {
+        std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(Path::to_owned))
+    }
+}
+
+/// Resolves an asset path against the resource root, leaving absolute paths as is
+pub fn resolve_resource_path(path: &Path) -> PathBuf {
+    join_resource_path(&resource_root(), path)
+}
+
+fn select_resource_root(
+    configured_root: Option<PathBuf>,
+    env_root: Option<PathBuf>,
+    default_dir: Option<PathBuf>,
+) -> PathBuf {
+    configured_root
+        .or(env_root)
+        .or(default_dir)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+fn join_resource_path(root: &Path, path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        path.to_owned()
+    } else {
+        root.join(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_path_is_joined_with_absolute_root() {
+        let root = std::env::temp_dir().join("game");
+
+        let path = join_resource_path(&root, Path::new("resources/smiley.png"));
+
+        assert_eq!(path, root.join("resources").join("smiley.png"));
+        assert!(path.is_absolute());
+    }
+
+    #[test]
+    fn relative_path_is_joined_with_relative_root() {
+        let path = join_resource_path(Path::new("game"), Path::new("resources/smiley.png"));
+
+        assert_eq!(path, Path::new("game/resources/smiley.png"));
+    }
+
+    #[test]
+    fn absolute_path_ignores_root() {
+        let absolute = std::env::temp_dir().join("smiley.png");
+
+        let path = join_resource_path(Path::new("game"), &absolute);
+
+        assert_eq!(path, absolute);
+    }
+
+    #[test]
+    fn configured_root_takes_precedence() {
+        let root = select_resource_root(
+            Some(PathBuf::from("configured")),
+            Some(PathBuf::from("env")),
+            Some(PathBuf::from("default")),
+        );
+
+        assert_eq!(root, PathBuf::from("configured"));
+    }
+
+    #[test]
+    fn env_root_takes_precedence_over_default_dir() {
+        let root = select_resource_root(
+            None,
+            Some(PathBuf::from("env")),
+            Some(PathBuf::from("default")),
+        );
+
+        assert_eq!(root, PathBuf::from("env"));
+    }
+
+    #[test]
+    fn root_defaults_to_default_dir() {
+        let root = select_resource_root(None, None, Some(PathBuf::from("default")));
+
+        assert_eq!(root, PathBuf::from("default"));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn debug_builds_default_to_working_directory() {
+        assert_eq!(default_root_dir(), std::env::current_dir().ok());
+    }
+}
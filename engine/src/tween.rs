@@ -0,0 +1,155 @@
+//! Interpolation of scalar values over time, e.g. positions or alpha.
+//!
+//! Easing functions map a normalized time `t` in `[0, 1]` to a progress value,
+//! where `0` is the start and `1` is the end of the tween.
+
+pub type EasingFn = fn(f32) -> f32;
+
+pub fn linear(t: f32) -> f32 {
+    t
+}
+
+pub fn ease_in_quad(t: f32) -> f32 {
+    t * t
+}
+
+pub fn ease_out_quad(t: f32) -> f32 {
+    1.0 - (1.0 - t) * (1.0 - t)
+}
+
+pub fn ease_in_out_quad(t: f32) -> f32 {
+    if t < 0.5 {
+        2.0 * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+    }
+}
+
+pub fn ease_in_cubic(t: f32) -> f32 {
+    t * t * t
+}
+
+pub fn ease_out_cubic(t: f32) -> f32 {
+    1.0 - (1.0 - t).powi(3)
+}
+
+pub fn ease_in_out_cubic(t: f32) -> f32 {
+    if t < 0.5 {
+        4.0 * t * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Tween {
+    start: f32,
+    end: f32,
+    duration_ms: u128,
+    elapsed_ms: u128,
+    easing: EasingFn,
+}
+
+impl Tween {
+    pub fn new(start: f32, end: f32, duration_ms: u128, easing: EasingFn) -> Self {
+        Tween {
+            start,
+            end,
+            duration_ms,
+            elapsed_ms: 0,
+            easing,
+        }
+    }
+
+    /// Advance the tween, typically by `engine.frame.delta_ms`
+    pub fn update(&mut self, delta_time_ms: u128) {
+        self.elapsed_ms = u128::min(self.elapsed_ms + delta_time_ms, self.duration_ms);
+    }
+
+    pub fn value(&self) -> f32 {
+        let progress = (self.easing)(self.normalized_time());
+        self.start + (self.end - self.start) * progress
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed_ms >= self.duration_ms
+    }
+
+    pub fn restart(&mut self) {
+        self.elapsed_ms = 0;
+    }
+
+    fn normalized_time(&self) -> f32 {
+        if self.duration_ms == 0 {
+            1.0
+        } else {
+            self.elapsed_ms as f32 / self.duration_ms as f32
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[parameterized(easing = {
+        linear, ease_in_quad, ease_out_quad, ease_in_out_quad,
+        ease_in_cubic, ease_out_cubic, ease_in_out_cubic
+    })]
+    fn easing_functions_start_at_zero_and_end_at_one(easing: EasingFn) {
+        assert_eq!(easing(0.0), 0.0);
+        assert_eq!(easing(1.0), 1.0);
+    }
+
+    #[parameterized(easing = {
+        linear, ease_in_quad, ease_out_quad, ease_in_out_quad,
+        ease_in_cubic, ease_out_cubic, ease_in_out_cubic
+    })]
+    fn tween_goes_from_start_to_end_value(easing: EasingFn) {
+        let mut tween = Tween::new(10.0, 20.0, 1000, easing);
+
+        assert_eq!(tween.value(), 10.0);
+        assert!(!tween.is_finished());
+
+        tween.update(1000);
+
+        assert_eq!(tween.value(), 20.0);
+        assert!(tween.is_finished());
+    }
+
+    #[test]
+    fn ease_in_out_cubic_is_halfway_at_middle() {
+        let mut tween = Tween::new(0.0, 100.0, 1000, ease_in_out_cubic);
+
+        tween.update(500);
+
+        assert_eq!(tween.value(), 50.0);
+    }
+
+    #[test]
+    fn ease_in_quad_is_a_quarter_of_the_way_at_middle() {
+        let mut tween = Tween::new(0.0, 100.0, 1000, ease_in_quad);
+
+        tween.update(500);
+
+        assert_eq!(tween.value(), 25.0);
+    }
+
+    #[test]
+    fn tween_stops_at_end_value() {
+        let mut tween = Tween::new(0.0, 100.0, 1000, linear);
+
+        tween.update(600);
+        tween.update(600);
+
+        assert_eq!(tween.value(), 100.0);
+    }
+
+    #[test]
+    fn zero_length_tween_is_immediately_finished() {
+        let tween = Tween::new(0.0, 100.0, 0, linear);
+
+        assert!(tween.is_finished());
+        assert_eq!(tween.value(), 100.0);
+    }
+}
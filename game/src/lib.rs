@@ -6,11 +6,12 @@ use std::{collections::HashMap, path::PathBuf};
 use editor::Editor;
 use engine::{
     audio::MusicID,
-    geometry::Rect,
+    geometry::{point, Rect},
     graphics::{
         animation::{self, AnimationID},
+        color::Color,
         rendering::Renderer,
-        sprites::{self, SpriteSheetID},
+        sprites::{self, Sprite, SpriteSheetID},
     },
     imgui::ImGui,
     input::config::ProgramConfig,
@@ -65,6 +66,9 @@ pub fn init(
 ) -> GameState {
     set_global_contexts(logger, level, ctx);
 
+    // Don't react to input meant for the editor and debug windows
+    engine.set_suppress_captured_input(true);
+
     // Init smiley
     let smiley_json_path = &PathBuf::from(r"resources/smiley.json");
     let smiley_texture_id = *engine.textures.get("resources/smiley.png").unwrap();
@@ -94,7 +98,8 @@ pub fn init(
                 &mut engine.animation,
                 &smiley_sprite_sheet_data,
                 frame_tag_name,
-            ),
+            )
+            .unwrap(),
         );
     }
     let smiley_direction = Direction::Down;
@@ -108,7 +113,8 @@ pub fn init(
 
     let music_id = engine
         .audio
-        .add_music(&PathBuf::from("./resources/audio/music.wav"));
+        .add_music(&PathBuf::from("./resources/audio/music.wav"))
+        .unwrap();
 
     GameState {
         ui: GameUi::new(engine),
@@ -127,16 +133,23 @@ pub fn init(
 
 #[no_mangle]
 pub fn update(game: &mut GameState, engine: &mut Engine, imgui: &mut ImGui) {
-    if engine.input.keyboard.is_pressed_now(Keycode::F3) {
+    if engine
+        .input
+        .is_hotkey_pressed_now(engine.hotkeys().debug_ui)
+    {
         game.show_debug_ui = !game.show_debug_ui;
     }
-    if engine.input.keyboard.is_pressed_now(Keycode::F2) {
+    engine.debug_draw().set_enabled(game.show_debug_ui);
+    if engine
+        .input
+        .is_hotkey_pressed_now(engine.hotkeys().editor_ui)
+    {
         game.show_editor_ui = !game.show_editor_ui;
     }
-
-    if engine.input.keyboard.is_pressed(Keycode::Escape) {
-        engine.request_quit();
-    }
+    // The editor shows the canvas in its own window
+    engine
+        .renderer
+        .set_canvas_drawn_to_window(!game.show_editor_ui);
 
     game.ui.draw_centered();
     game.ui.set_cursor(
@@ -144,8 +157,9 @@ pub fn update(game: &mut GameState, engine: &mut Engine, imgui: &mut ImGui) {
         (engine.renderer.canvas().size.height / 2) as i32,
     );
 
+    let game_world_has_input = engine.input.active_context().is_none();
     for (keycode, direction) in &game.smiley_input_mappings {
-        if engine.input.keyboard.is_pressed_now(*keycode) {
+        if game_world_has_input && engine.input.keyboard.is_pressed_now(*keycode) {
             game.smiley_direction = *direction;
             if game.smiley_is_animating {
                 let animation_id = game.smiley_animations[direction];
@@ -159,7 +173,7 @@ pub fn update(game: &mut GameState, engine: &mut Engine, imgui: &mut ImGui) {
     } else {
         "Pause"
     };
-    if game.ui.button(play_label) {
+    if game.ui.button(engine, play_label) {
         game.smiley_is_animating = !game.smiley_is_animating;
 
         let animation_id = game.smiley_animations[&game.smiley_direction];
@@ -200,24 +214,21 @@ pub fn render(game: &mut GameState, engine: &mut Engine) {
     game.ui.render(engine);
 
     // draw smiley
-    {
-        let smiley_frame = engine
-            .animation
-            .current_frame(game.smiley_animations[&game.smiley_direction]);
-        let canvas = engine.renderer.canvas().size;
-        let (smiley_width, smiley_height) = (16, 16);
-        let (smiley_x, smiley_y) = (
-            (canvas.width - smiley_width) / 2,
-            (canvas.height - smiley_height) / 2 - (canvas.height as f32 * 0.1) as u32,
-        );
-        engine.sprites.draw_sprite(
-            &mut engine.renderer,
-            game.smiley_sprite_sheet_id,
-            smiley_frame,
-            smiley_x as _,
-            smiley_y as _,
-        );
-    }
+    let canvas = engine.renderer.canvas().size;
+    let (smiley_width, smiley_height) = (16, 16);
+    let (smiley_x, smiley_y) = (
+        (canvas.width - smiley_width) / 2,
+        (canvas.height - smiley_height) / 2 - (canvas.height as f32 * 0.1) as u32,
+    );
+    let smiley = Sprite::new(
+        game.smiley_sprite_sheet_id,
+        0,
+        point(smiley_x as _, smiley_y as _),
+    )
+    .with_animation(game.smiley_animations[&game.smiley_direction]);
+    engine
+        .sprites
+        .draw_instance(&mut engine.renderer, &engine.animation, &smiley);
 }
 
 #[no_mangle]
@@ -226,7 +237,7 @@ pub fn write_to_config(config: &mut ProgramConfig, game: &GameState) {
 }
 
 fn draw_background(renderer: &mut Renderer) {
-    renderer.set_draw_color(0, 128, 128, 255);
+    renderer.set_draw_color(Color::rgb(0, 128, 128));
     renderer.draw_rect_fill(Rect {
         x: 0,
         y: 0,
@@ -4,14 +4,21 @@ use std::{collections::HashMap, path::PathBuf};
 
 use engine::{
     audio::SoundID,
-    geometry::{intersection::point_is_inside_rect, point, Point, Rect},
+    geometry::{intersection::point_is_inside_rect, point, Dimension, Point, Rect},
+    graphics::color::Color,
+    input::{button::Button as MouseButton, keyboard::Keyboard, InputDevices},
     Engine,
 };
+use sdl2::keyboard::Keycode;
 
 pub struct GameUi {
-    cursor: Point,
-    cursor_alignment: CursorAlignment,
+    layout: Layout,
     buttons: HashMap<String, Button>,
+    checkboxes: HashMap<String, Checkbox>,
+    sliders: HashMap<String, Slider>,
+    labels: Vec<Label>,
+    focus: Focus,
+    modal_open: bool,
     click_sound: SoundID,
 }
 
@@ -19,22 +26,102 @@ pub struct GameUi {
 struct Button {
     is_hot: bool,
     rect: Rect,
+    press: PressState,
+    text: String,
+}
+
+#[derive(Debug)]
+struct Checkbox {
+    is_hot: bool,
+    rect: Rect,
+    press: PressState,
+    checked: bool,
+    text: String,
+}
+
+#[derive(Debug)]
+struct Slider {
+    is_hot: bool,
+    /// The track, excluding the label
+    rect: Rect,
+    press: PressState,
+    /// Mouse x position while the handle is being dragged
+    drag_x: i32,
+    value: f32,
+    min: f32,
+    max: f32,
+    label_width: u32,
+    text: String,
+}
+
+/// Mouse interaction state of a clickable component
+#[derive(Debug, Default)]
+struct PressState {
     is_hovered: bool,
     is_pressed: bool,
     was_pressed: bool,
+}
+
+/// Keyboard focus, moved between widgets in the order they were added
+#[derive(Debug, Default)]
+struct Focus {
+    focused: Option<String>,
+    /// Ids of the widgets added this frame
+    order: Vec<String>,
+    activated: bool,
+}
+
+/// Static text, only kept until the end of the frame
+#[derive(Debug)]
+struct Label {
+    pos: Point,
     text: String,
 }
 
 /// Determines how component will be positioned relative to cursor
+#[derive(Debug, Clone, Copy)]
 enum CursorAlignment {
     TopLeft,
     Centered,
 }
 
+/// Keeps track of where to place the next component
+#[derive(Debug)]
+struct Layout {
+    cursor: Point,
+    cursor_alignment: CursorAlignment,
+    horizontal_group: Option<HorizontalGroup>,
+}
+
+/// Components placed left-to-right instead of top-to-bottom
+#[derive(Debug)]
+struct HorizontalGroup {
+    start: Point,
+    height: u32,
+}
+
 pub const BUTTON_WIDTH: u32 = 75;
 pub const BUTTON_HEIGHT: u32 = 23;
+/// Horizontal space between text and button edge
+const BUTTON_PADDING: u32 = 8;
+const CHECKBOX_SIZE: u32 = 13;
+const CHECKBOX_LABEL_GAP: u32 = 5;
+const SLIDER_WIDTH: u32 = 100;
+const SLIDER_HEIGHT: u32 = 13;
+const SLIDER_HANDLE_WIDTH: u32 = 7;
+const SLIDER_LABEL_GAP: u32 = 5;
 const SPACING: u32 = 10;
 
+/// Draw layer of all components, so they're drawn on top of the game
+const UI_LAYER: i32 = 100;
+
+/// Input context active while a modal dialog is open
+pub const MODAL_INPUT_CONTEXT: &str = "GameUi::modal";
+
+/// Input context active while a widget has keyboard focus, so that the arrow
+/// keys move focus instead of reaching the game world
+pub const FOCUS_INPUT_CONTEXT: &str = "GameUi::focus";
+
 impl Button {
     fn new() -> Self {
         Button {
@@ -45,117 +132,427 @@ impl Button {
                 w: BUTTON_WIDTH,
                 h: BUTTON_HEIGHT,
             },
-            is_hovered: false,
-            is_pressed: false,
-            was_pressed: false,
+            press: PressState::default(),
+            text: String::new(),
+        }
+    }
+}
+
+impl Checkbox {
+    fn new() -> Self {
+        Checkbox {
+            is_hot: true,
+            rect: Rect {
+                x: 0,
+                y: 0,
+                w: CHECKBOX_SIZE,
+                h: CHECKBOX_SIZE,
+            },
+            press: PressState::default(),
+            checked: false,
+            text: String::new(),
+        }
+    }
+}
+
+impl Slider {
+    fn new() -> Self {
+        Slider {
+            is_hot: true,
+            rect: Rect {
+                x: 0,
+                y: 0,
+                w: SLIDER_WIDTH,
+                h: SLIDER_HEIGHT,
+            },
+            press: PressState::default(),
+            drag_x: 0,
+            value: 0.0,
+            min: 0.0,
+            max: 1.0,
+            label_width: 0,
             text: String::new(),
         }
     }
 }
 
+impl Focus {
+    fn register(&mut self, id: &str) {
+        self.order.push(id.to_owned());
+    }
+
+    fn focus_next(&mut self) {
+        self.move_focus(1);
+    }
+
+    fn focus_previous(&mut self) {
+        self.move_focus(-1);
+    }
+
+    fn move_focus(&mut self, step: isize) {
+        if self.order.is_empty() {
+            self.focused = None;
+            return;
+        }
+
+        let len = self.order.len() as isize;
+        let current = self
+            .focused
+            .as_ref()
+            .and_then(|focused| self.order.iter().position(|id| id == focused));
+        let next = match current {
+            Some(index) => (index as isize + step).rem_euclid(len),
+            None if step >= 0 => 0,
+            None => len - 1,
+        };
+        self.focused = Some(self.order[next as usize].clone());
+    }
+
+    /// Tab moves focus, the arrow keys only once a widget has focus so that
+    /// they otherwise reach the game world
+    fn handle_keys(&mut self, keyboard: &Keyboard<Keycode>) {
+        let shift_held =
+            keyboard.is_pressed(Keycode::LShift) || keyboard.is_pressed(Keycode::RShift);
+        let tab_pressed = keyboard.is_pressed_now(Keycode::Tab);
+        let has_focus = self.focused.is_some();
+        let arrow_next =
+            keyboard.is_pressed_now(Keycode::Down) || keyboard.is_pressed_now(Keycode::Right);
+        let arrow_previous =
+            keyboard.is_pressed_now(Keycode::Up) || keyboard.is_pressed_now(Keycode::Left);
+        if (tab_pressed && !shift_held) || (has_focus && arrow_next) {
+            self.focus_next();
+        }
+        if (tab_pressed && shift_held) || (has_focus && arrow_previous) {
+            self.focus_previous();
+        }
+        self.activated = keyboard.is_pressed_now(Keycode::Return);
+    }
+
+    fn is_focused(&self, id: &str) -> bool {
+        self.focused.as_deref() == Some(id)
+    }
+
+    fn is_activated(&self, id: &str) -> bool {
+        self.activated && self.is_focused(id)
+    }
+}
+
+impl PressState {
+    /// Returns true if the component was pressed this frame
+    fn update(&mut self, rect: Rect, mouse_pos: Point, left_button: &MouseButton) -> bool {
+        let mouse_intersects = point_is_inside_rect(mouse_pos, rect);
+        self.is_hovered = mouse_intersects && left_button.is_released();
+
+        self.was_pressed = self.is_pressed;
+        let pressed_now = mouse_intersects && left_button.is_pressed_now();
+        if pressed_now {
+            self.is_pressed = true;
+        }
+        if !mouse_intersects || left_button.is_released() {
+            self.is_pressed = false;
+        }
+
+        pressed_now
+    }
+
+    fn released_now(&self) -> bool {
+        self.is_hovered && self.was_pressed && !self.is_pressed
+    }
+}
+
+impl Layout {
+    fn new() -> Self {
+        Layout {
+            cursor: point(0, 0),
+            cursor_alignment: CursorAlignment::TopLeft,
+            horizontal_group: None,
+        }
+    }
+
+    /// Returns the top left position of a component of the given size, and
+    /// advances the cursor past it.
+    fn place(&mut self, size: Dimension) -> Point {
+        let pos = match self.cursor_alignment {
+            CursorAlignment::TopLeft => self.cursor,
+            CursorAlignment::Centered => {
+                self.cursor - point(size.width as i32 / 2, size.height as i32 / 2)
+            }
+        };
+
+        if let Some(group) = &mut self.horizontal_group {
+            group.height = u32::max(group.height, size.height);
+            self.cursor += point((size.width + SPACING) as i32, 0);
+        } else {
+            self.cursor += point(0, (size.height + SPACING) as i32);
+        }
+
+        pos
+    }
+
+    fn begin_horizontal(&mut self) {
+        self.horizontal_group = Some(HorizontalGroup {
+            start: self.cursor,
+            height: 0,
+        });
+    }
+
+    fn end_horizontal(&mut self) {
+        if let Some(group) = self.horizontal_group.take() {
+            self.cursor = group.start + point(0, (group.height + SPACING) as i32);
+        }
+    }
+}
+
 impl GameUi {
     pub fn new(engine: &mut Engine) -> Self {
         GameUi {
-            cursor: point(0, 0),
-            cursor_alignment: CursorAlignment::TopLeft,
+            layout: Layout::new(),
             buttons: HashMap::new(),
+            checkboxes: HashMap::new(),
+            sliders: HashMap::new(),
+            labels: Vec::new(),
+            focus: Focus::default(),
+            modal_open: false,
             click_sound: engine
                 .audio
-                .add_sound(&PathBuf::from("./resources/audio/click.wav")),
+                .add_sound(&PathBuf::from("./resources/audio/click.wav"))
+                .unwrap(),
+        }
+    }
+
+    /// Captures input until `close_modal` is called, so game world input
+    /// handlers can check if they're the active input context.
+    pub fn open_modal(&mut self, engine: &mut Engine) {
+        if !self.modal_open {
+            self.modal_open = true;
+            engine.input.push_context(MODAL_INPUT_CONTEXT);
         }
     }
 
+    pub fn close_modal(&mut self, engine: &mut Engine) {
+        if self.modal_open {
+            self.modal_open = false;
+            engine.input.pop_context(MODAL_INPUT_CONTEXT);
+        }
+    }
+
+    pub fn is_modal_open(&self) -> bool {
+        self.modal_open
+    }
+
     pub fn set_cursor(&mut self, x: i32, y: i32) {
-        self.cursor.x = x;
-        self.cursor.y = y;
+        self.layout.cursor.x = x;
+        self.layout.cursor.y = y;
+    }
+
+    /// Lay out the following components left-to-right, until `end_horizontal`
+    pub fn begin_horizontal(&mut self) {
+        self.layout.begin_horizontal();
+    }
+
+    /// Continue laying out components top-to-bottom, below the horizontal group
+    pub fn end_horizontal(&mut self) {
+        self.layout.end_horizontal();
     }
 
-    pub fn button(&mut self, label: &str) -> bool {
+    pub fn button(&mut self, engine: &Engine, label: &str) -> bool {
         let (id, text) = parse_label(label);
+        let text = text.unwrap_or(id);
         let button = self.buttons.entry(id.to_string()).or_insert(Button::new());
-        let (button_x, button_y) = match self.cursor_alignment {
-            CursorAlignment::TopLeft => (self.cursor.x, self.cursor.y),
-            CursorAlignment::Centered => (
-                self.cursor.x - button.rect.w as i32 / 2,
-                self.cursor.y - button.rect.h as i32 / 2,
-            ),
-        };
-
-        self.cursor += point(0, (BUTTON_HEIGHT + SPACING) as i32);
+        let (text_width, text_height) = engine.text.text_dimensions(engine.fonts.arial_16, text);
+        let button_size = button_size(text_width, text_height);
+        button.rect.w = button_size.width;
+        button.rect.h = button_size.height;
+        let button_pos = self.layout.place(Dimension {
+            width: button.rect.w,
+            height: button.rect.h,
+        });
 
-        button.rect.x = button_x;
-        button.rect.y = button_y;
+        button.rect.x = button_pos.x;
+        button.rect.y = button_pos.y;
         button.is_hot = true;
-        button.text = text.unwrap_or(id).to_owned();
+        button.text = text.to_owned();
+        self.focus.register(id);
 
-        let released_now = button.is_hovered && button.was_pressed && !button.is_pressed;
-        released_now
+        button.press.released_now() || self.focus.is_activated(id)
     }
 
-    pub fn update(&mut self, engine: &Engine) {
-        for (_, button) in &mut self.buttons {
-            let mouse_intersects_button = point_is_inside_rect(engine.input.mouse.pos, button.rect);
-            if mouse_intersects_button && engine.input.mouse.left_button.is_released() {
-                button.is_hovered = true;
-            } else {
-                button.is_hovered = false;
-            }
+    /// Returns true if `checked` was toggled this frame
+    pub fn checkbox(&mut self, label: &str, checked: &mut bool) -> bool {
+        let (id, text) = parse_label(label);
+        let checkbox = self
+            .checkboxes
+            .entry(id.to_string())
+            .or_insert(Checkbox::new());
+        let checkbox_pos = self.layout.place(Dimension {
+            width: checkbox.rect.w,
+            height: checkbox.rect.h,
+        });
+
+        checkbox.rect.x = checkbox_pos.x;
+        checkbox.rect.y = checkbox_pos.y;
+        checkbox.is_hot = true;
+        checkbox.text = text.unwrap_or(id).to_owned();
+        self.focus.register(id);
+
+        let changed = toggle_on_release(&checkbox.press, checked)
+            || toggle_on_activation(&self.focus, id, checked);
+        checkbox.checked = *checked;
+        changed
+    }
+
+    /// Returns true if `value` was changed this frame
+    pub fn slider(&mut self, label: &str, value: &mut f32, min: f32, max: f32) -> bool {
+        let (id, text) = parse_label(label);
+        let slider = self.sliders.entry(id.to_string()).or_insert(Slider::new());
+        let slider_pos = self.layout.place(Dimension {
+            width: slider.rect.w + SLIDER_LABEL_GAP + slider.label_width,
+            height: slider.rect.h,
+        });
+
+        slider.rect.x = slider_pos.x;
+        slider.rect.y = slider_pos.y;
+        slider.is_hot = true;
+        slider.text = text.unwrap_or(id).to_owned();
+        self.focus.register(id);
+
+        let old_value = *value;
+        if slider.press.is_pressed {
+            *value = slider_value_from_position(slider.rect, slider.drag_x, min, max);
+        }
+        *value = value.clamp(min, max);
+
+        slider.value = *value;
+        slider.min = min;
+        slider.max = max;
+        *value != old_value
+    }
+
+    pub fn label(&mut self, engine: &Engine, text: &str) {
+        let (width, height) = engine.text.text_dimensions(engine.fonts.arial_16, text);
+        let label = place_label(&mut self.layout, text, Dimension { width, height });
+        self.labels.push(label);
+    }
+
+    pub fn update(&mut self, engine: &mut Engine) {
+        // the engine closes the active context on Escape
+        self.modal_open = is_modal_still_open(self.modal_open, &engine.input);
+        if !engine.input.has_context(FOCUS_INPUT_CONTEXT) {
+            self.focus.focused = None;
+        }
+
+        self.focus.handle_keys(&engine.input.keyboard);
+        set_focus_input_context(self.focus.focused.is_some(), &mut engine.input);
 
-            button.was_pressed = button.is_pressed;
-            if mouse_intersects_button && engine.input.mouse.left_button.is_pressed_now() {
-                button.is_pressed = true;
+        let mouse = &engine.input.mouse;
+        let rects_and_presses = self
+            .buttons
+            .values_mut()
+            .map(|button| (button.rect, &mut button.press))
+            .chain(
+                self.checkboxes
+                    .values_mut()
+                    .map(|checkbox| (checkbox.rect, &mut checkbox.press)),
+            );
+        for (rect, press) in rects_and_presses {
+            if press.update(rect, mouse.pos, &mouse.left_button) {
                 engine.audio.play_sound(self.click_sound);
             }
-            if !mouse_intersects_button || engine.input.mouse.left_button.is_released() {
-                button.is_pressed = false;
+        }
+
+        for (_, slider) in &mut self.sliders {
+            slider
+                .press
+                .update(slider.rect, mouse.pos, &mouse.left_button);
+            if slider.press.is_pressed {
+                slider.drag_x = mouse.pos.x;
             }
         }
     }
 
     pub fn render(&mut self, engine: &mut Engine) {
+        engine.renderer.set_layer(UI_LAYER);
+
         for (_, button) in &self.buttons {
             draw_button(engine, &button);
         }
 
+        for (_, checkbox) in &mut self.checkboxes {
+            draw_checkbox(engine, checkbox);
+        }
+
+        for (_, slider) in &mut self.sliders {
+            draw_slider(engine, slider);
+        }
+
+        if let Some(focused_rect) = self.focused_rect() {
+            draw_focus_outline(engine, focused_rect);
+        }
+
+        for label in self.labels.drain(..) {
+            draw_label(engine, &label);
+        }
+
+        engine.renderer.reset_layer();
         self.remove_cold_components();
     }
 
+    fn focused_rect(&self) -> Option<Rect> {
+        let id = self.focus.focused.as_ref()?;
+        self.buttons
+            .get(id)
+            .map(|button| button.rect)
+            .or_else(|| self.checkboxes.get(id).map(|checkbox| checkbox.rect))
+            .or_else(|| self.sliders.get(id).map(|slider| slider.rect))
+    }
+
     fn remove_cold_components(&mut self) {
         // remove cold components
         self.buttons.retain(|_, button| button.is_hot);
+        self.checkboxes.retain(|_, checkbox| checkbox.is_hot);
+        self.sliders.retain(|_, slider| slider.is_hot);
 
         // mark hot components as cold
         for (_, button) in &mut self.buttons {
             button.is_hot = false;
         }
+        for (_, checkbox) in &mut self.checkboxes {
+            checkbox.is_hot = false;
+        }
+        for (_, slider) in &mut self.sliders {
+            slider.is_hot = false;
+        }
+
+        // widgets are registered for focus again next frame
+        self.focus.order.clear();
     }
 
     pub fn draw_centered(&mut self) {
-        self.cursor_alignment = CursorAlignment::Centered;
+        self.layout.cursor_alignment = CursorAlignment::Centered;
     }
 
     pub fn _draw_left_aligned(&mut self) {
-        self.cursor_alignment = CursorAlignment::TopLeft;
+        self.layout.cursor_alignment = CursorAlignment::TopLeft;
     }
 }
 
 #[rustfmt::skip]
 fn draw_button(engine: &mut Engine, button: &Button) {
     let rect = button.rect;
-    let draw_pressed = button.is_pressed;
-    let draw_hovered = button.is_hovered && !button.is_pressed;
+    let draw_pressed = button.press.is_pressed;
+    let draw_hovered = button.press.is_hovered && !button.press.is_pressed;
 
-    let white = (255, 255, 255);
-    let light_grey = (223, 223, 223);
-    let hovered_grey = (204, 204, 204);
-    let non_hovered_grey = (194, 194, 194);
+    let white = Color::WHITE;
+    let light_grey = Color::rgb(223, 223, 223);
+    let hovered_grey = Color::rgb(204, 204, 204);
+    let non_hovered_grey = Color::rgb(194, 194, 194);
     let grey = if draw_hovered {
         hovered_grey
     } else {
         non_hovered_grey
     };
-    let dark_grey = (129, 129, 129);
-    let black = (0, 0, 0);
+    let dark_grey = Color::rgb(129, 129, 129);
+    let black = Color::BLACK;
 
     let (rect_w, rect_h) = (rect.w as i32, rect.h as i32);
 
@@ -167,40 +564,214 @@ fn draw_button(engine: &mut Engine, button: &Button) {
     let renderer = &mut engine.renderer;
 
     // button body
-    renderer.set_draw_color(grey.0, grey.1, grey.2, 255);
+    renderer.set_draw_color(grey);
     renderer.draw_rect_fill(rect);
 
     // top outline
-    renderer.set_draw_color(top_outline.0, top_outline.1, top_outline.2, 255);
+    renderer.set_draw_color(top_outline);
     renderer.draw_line(rect.x, rect.y, rect.x, rect.y + rect_h);
     renderer.draw_line(rect.x, rect.y, rect.x + rect_w, rect.y);
 
     // top highlight
-    renderer.set_draw_color(top_highlight.0, top_highlight.1, top_highlight.2, 255);
+    renderer.set_draw_color(top_highlight);
     renderer.draw_line(rect.x + 1, rect.y + 1, rect.x + 1, rect.y + rect_h - 1);
     renderer.draw_line(rect.x + 1, rect.y + 1, rect.x + rect_w - 1, rect.y);
 
     // bottom outline
-    renderer.set_draw_color(bottom_outline.0, bottom_outline.1, bottom_outline.2, 255);
+    renderer.set_draw_color(bottom_outline);
     renderer.draw_line(rect.x + rect_w, rect.y, rect.x + rect_w, rect.y + rect_h);
     renderer.draw_line(rect.x, rect.y + rect_h, rect.x + rect_w, rect.y + rect_h);
 
     // bottom highlight
-    renderer.set_draw_color(bottom_highlight.0, bottom_highlight.1, bottom_highlight.2, 255);
+    renderer.set_draw_color(bottom_highlight);
     renderer.draw_line(rect.x + rect_w - 1, rect.y + 1, rect.x + rect_w - 1, rect.y + rect_h - 1);
     renderer.draw_line(rect.x + 1, rect.y + rect_h - 1, rect.x + rect_w - 1, rect.y + rect_h - 1);
 
     // draw text
     if !button.text.is_empty() {
-        engine.text.set_text_color(0, 0, 0, 255);
+        engine.text.set_text_color(Color::BLACK);
         let offset = if draw_pressed { 1 } else { 0 };
         let (text_width, text_height) = engine.text.text_dimensions(engine.fonts.arial_16, &button.text);
-        let text_x = rect.x + (BUTTON_WIDTH as i32 - text_width as i32) / 2 + offset;
-        let text_y = rect.y + (BUTTON_HEIGHT as i32 - text_height as i32) / 2 + offset;
+        let text_x = rect.x + (rect_w - text_width as i32) / 2 + offset;
+        let text_y = rect.y + (rect_h - text_height as i32) / 2 + offset;
         engine.text.draw_text(renderer, engine.fonts.arial_16, text_x, text_y, &button.text);
     }
 }
 
+/// Buttons grow to fit their text, but are never smaller than the default size
+fn button_size(text_width: u32, text_height: u32) -> Dimension {
+    Dimension {
+        width: u32::max(BUTTON_WIDTH, text_width + 2 * BUTTON_PADDING),
+        height: u32::max(BUTTON_HEIGHT, text_height),
+    }
+}
+
+/// Draws the box and label, and updates the clickable area to cover both
+fn draw_checkbox(engine: &mut Engine, checkbox: &mut Checkbox) {
+    let box_rect = Rect {
+        x: checkbox.rect.x,
+        y: checkbox.rect.y,
+        w: CHECKBOX_SIZE,
+        h: CHECKBOX_SIZE,
+    };
+    let renderer = &mut engine.renderer;
+
+    // box
+    if checkbox.press.is_pressed {
+        renderer.set_draw_color(Color::rgb(194, 194, 194));
+    } else {
+        renderer.set_draw_color(Color::WHITE);
+    }
+    renderer.draw_rect_fill(box_rect);
+    renderer.set_draw_color(Color::BLACK);
+    renderer.draw_rect(box_rect);
+
+    // check mark
+    if checkbox.checked {
+        let inner_rect = Rect {
+            x: box_rect.x + 3,
+            y: box_rect.y + 3,
+            w: CHECKBOX_SIZE - 6,
+            h: CHECKBOX_SIZE - 6,
+        };
+        renderer.draw_rect_fill(inner_rect);
+    }
+
+    // label
+    let (text_width, text_height) = engine
+        .text
+        .text_dimensions(engine.fonts.arial_16, &checkbox.text);
+    let text_x = box_rect.x + (CHECKBOX_SIZE + CHECKBOX_LABEL_GAP) as i32;
+    let text_y = box_rect.y + (CHECKBOX_SIZE as i32 - text_height as i32) / 2;
+    engine.text.set_text_color(Color::BLACK);
+    engine.text.draw_text(
+        renderer,
+        engine.fonts.arial_16,
+        text_x,
+        text_y,
+        &checkbox.text,
+    );
+
+    checkbox.rect.w = CHECKBOX_SIZE + CHECKBOX_LABEL_GAP + text_width;
+    checkbox.rect.h = u32::max(CHECKBOX_SIZE, text_height);
+}
+
+/// Draws the track, handle and label, and updates the measured label width
+fn draw_slider(engine: &mut Engine, slider: &mut Slider) {
+    let track = slider.rect;
+    let renderer = &mut engine.renderer;
+
+    // track
+    let track_line_y = track.y + track.h as i32 / 2;
+    renderer.set_draw_color(Color::rgb(129, 129, 129));
+    renderer.draw_line(
+        track.x,
+        track_line_y,
+        track.x + track.w as i32,
+        track_line_y,
+    );
+
+    // handle
+    let range = slider.max - slider.min;
+    let t = if range > 0.0 {
+        (slider.value - slider.min) / range
+    } else {
+        0.0
+    };
+    let handle_center_x = track.x + f32::round(t * track.w as f32) as i32;
+    let handle_rect = Rect {
+        x: handle_center_x - SLIDER_HANDLE_WIDTH as i32 / 2,
+        y: track.y,
+        w: SLIDER_HANDLE_WIDTH,
+        h: track.h,
+    };
+    if slider.press.is_pressed {
+        renderer.set_draw_color(Color::rgb(204, 204, 204));
+    } else {
+        renderer.set_draw_color(Color::rgb(194, 194, 194));
+    }
+    renderer.draw_rect_fill(handle_rect);
+    renderer.set_draw_color(Color::BLACK);
+    renderer.draw_rect(handle_rect);
+
+    // label
+    let (text_width, text_height) = engine
+        .text
+        .text_dimensions(engine.fonts.arial_16, &slider.text);
+    let text_x = track.x + (track.w + SLIDER_LABEL_GAP) as i32;
+    let text_y = track.y + (track.h as i32 - text_height as i32) / 2;
+    engine.text.set_text_color(Color::BLACK);
+    engine.text.draw_text(
+        renderer,
+        engine.fonts.arial_16,
+        text_x,
+        text_y,
+        &slider.text,
+    );
+
+    slider.label_width = text_width;
+}
+
+/// Maps a mouse x position on the slider track to a value in `[min, max]`.
+/// The last pixel of the track is `max`, so that it's reached by dragging to
+/// the edge.
+fn slider_value_from_position(track: Rect, x: i32, min: f32, max: f32) -> f32 {
+    if x >= track.x + track.w as i32 - 1 {
+        return max;
+    }
+    let t = (x - track.x) as f32 / track.w as f32;
+    min + t.clamp(0.0, 1.0) * (max - min)
+}
+
+fn draw_focus_outline(engine: &mut Engine, rect: Rect) {
+    let outline = Rect {
+        x: rect.x - 2,
+        y: rect.y - 2,
+        w: rect.w + 4,
+        h: rect.h + 4,
+    };
+    engine.renderer.set_draw_color(Color::BLACK);
+    engine.renderer.draw_rect(outline);
+}
+
+/// Flips `checked` if the focused component was activated, returns true if it changed
+fn toggle_on_activation(focus: &Focus, id: &str, checked: &mut bool) -> bool {
+    if focus.is_activated(id) {
+        *checked = !*checked;
+        true
+    } else {
+        false
+    }
+}
+
+/// Flips `checked` if the component was clicked, returns true if it changed
+fn toggle_on_release(press: &PressState, checked: &mut bool) -> bool {
+    if press.released_now() {
+        *checked = !*checked;
+        true
+    } else {
+        false
+    }
+}
+
+fn place_label(layout: &mut Layout, text: &str, size: Dimension) -> Label {
+    Label {
+        pos: layout.place(size),
+        text: text.to_owned(),
+    }
+}
+
+fn draw_label(engine: &mut Engine, label: &Label) {
+    engine.text.set_text_color(Color::BLACK);
+    engine.text.draw_text(
+        &mut engine.renderer,
+        engine.fonts.arial_16,
+        label.pos.x,
+        label.pos.y,
+        &label.text,
+    );
+}
+
 fn parse_label(label: &str) -> (&str, Option<&str>) {
     if let Some((id, text)) = label.split_once("##") {
         (id, Some(text))
@@ -208,3 +779,268 @@ fn parse_label(label: &str) -> (&str, Option<&str>) {
         (label, None)
     }
 }
+
+/// Whether an open modal's input context is still on the stack
+fn is_modal_still_open(modal_open: bool, input: &InputDevices) -> bool {
+    modal_open && input.has_context(MODAL_INPUT_CONTEXT)
+}
+
+/// Keeps the focus input context on the stack while a widget has focus
+fn set_focus_input_context(has_focus: bool, input: &mut InputDevices) {
+    let has_context = input.has_context(FOCUS_INPUT_CONTEXT);
+    if has_focus && !has_context {
+        input.push_context(FOCUS_INPUT_CONTEXT);
+    } else if !has_focus && has_context {
+        input.pop_context(FOCUS_INPUT_CONTEXT);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engine::input::button::ButtonEvent;
+
+    #[test]
+    fn modal_closed_by_engine_is_no_longer_open() {
+        let mut input = InputDevices::new();
+        input.push_context(MODAL_INPUT_CONTEXT);
+        assert!(is_modal_still_open(true, &input));
+
+        input.close_active_context();
+
+        assert!(!is_modal_still_open(true, &input));
+    }
+
+    const BUTTON_SIZE: Dimension = Dimension {
+        width: BUTTON_WIDTH,
+        height: BUTTON_HEIGHT,
+    };
+
+    #[test]
+    fn components_are_stacked_downwards_by_default() {
+        let mut layout = Layout::new();
+
+        let first = layout.place(BUTTON_SIZE);
+        let second = layout.place(BUTTON_SIZE);
+
+        assert_eq!(first, point(0, 0));
+        assert_eq!(second, point(0, (BUTTON_HEIGHT + SPACING) as i32));
+    }
+
+    #[test]
+    fn components_in_horizontal_group_are_placed_side_by_side() {
+        let mut layout = Layout::new();
+
+        layout.begin_horizontal();
+        let first = layout.place(BUTTON_SIZE);
+        let second = layout.place(BUTTON_SIZE);
+        layout.end_horizontal();
+
+        assert_eq!(first, point(0, 0));
+        assert_eq!(second, point((BUTTON_WIDTH + SPACING) as i32, 0));
+    }
+
+    #[test]
+    fn components_after_horizontal_group_are_placed_below_it() {
+        let mut layout = Layout::new();
+
+        layout.begin_horizontal();
+        layout.place(BUTTON_SIZE);
+        layout.place(BUTTON_SIZE);
+        layout.end_horizontal();
+        let below = layout.place(BUTTON_SIZE);
+
+        assert_eq!(below, point(0, (BUTTON_HEIGHT + SPACING) as i32));
+    }
+
+    #[test]
+    fn label_advances_cursor_by_text_height() {
+        let mut layout = Layout::new();
+        let text_size = Dimension {
+            width: 40,
+            height: 16,
+        };
+
+        let label = place_label(&mut layout, "Score", text_size);
+
+        assert_eq!(label.pos, point(0, 0));
+        assert_eq!(layout.cursor, point(0, (16 + SPACING) as i32));
+    }
+
+    fn click(press: &mut PressState, rect: Rect, mouse_pos: Point) {
+        let mut left_button = MouseButton::new();
+        left_button.register_event(ButtonEvent::Down);
+        left_button.update(0);
+        press.update(rect, mouse_pos, &left_button);
+        left_button.register_event(ButtonEvent::Up);
+        left_button.update(0);
+        press.update(rect, mouse_pos, &left_button);
+    }
+
+    #[test]
+    fn clicking_checkbox_toggles_it() {
+        let mut checkbox = Checkbox::new();
+        let mut checked = false;
+
+        click(&mut checkbox.press, checkbox.rect, point(5, 5));
+        let changed = toggle_on_release(&checkbox.press, &mut checked);
+
+        assert!(changed);
+        assert!(checked);
+    }
+
+    #[test]
+    fn clicking_outside_checkbox_does_not_toggle_it() {
+        let mut checkbox = Checkbox::new();
+        let mut checked = false;
+
+        click(&mut checkbox.press, checkbox.rect, point(50, 50));
+        let changed = toggle_on_release(&checkbox.press, &mut checked);
+
+        assert!(!changed);
+        assert!(!checked);
+    }
+
+    const SLIDER_TRACK: Rect = Rect {
+        x: 20,
+        y: 10,
+        w: 100,
+        h: SLIDER_HEIGHT,
+    };
+
+    #[test]
+    fn slider_value_at_start_of_track_is_min() {
+        let value = slider_value_from_position(SLIDER_TRACK, 20, -1.0, 1.0);
+
+        assert_eq!(value, -1.0);
+    }
+
+    #[test]
+    fn slider_value_at_end_of_track_is_max() {
+        let value = slider_value_from_position(SLIDER_TRACK, 120, -1.0, 1.0);
+
+        assert_eq!(value, 1.0);
+    }
+
+    #[test]
+    fn slider_value_at_last_pixel_of_track_is_max() {
+        let value = slider_value_from_position(SLIDER_TRACK, 119, 0.1, 0.7);
+
+        assert_eq!(value, 0.7);
+    }
+
+    #[test]
+    fn slider_value_at_middle_of_track_is_halfway() {
+        let value = slider_value_from_position(SLIDER_TRACK, 70, -1.0, 1.0);
+
+        assert_eq!(value, 0.0);
+    }
+
+    #[test]
+    fn slider_value_outside_of_track_is_clamped() {
+        let before = slider_value_from_position(SLIDER_TRACK, 0, -1.0, 1.0);
+        let after = slider_value_from_position(SLIDER_TRACK, 200, -1.0, 1.0);
+
+        assert_eq!(before, -1.0);
+        assert_eq!(after, 1.0);
+    }
+
+    #[test]
+    fn button_with_short_text_has_default_size() {
+        let size = button_size(30, 16);
+
+        assert_eq!(size, BUTTON_SIZE);
+    }
+
+    #[test]
+    fn button_with_long_text_is_wider_than_button_with_short_text() {
+        let short_size = button_size(30, 16);
+        let long_size = button_size(200, 16);
+
+        assert!(long_size.width > short_size.width);
+        assert_eq!(long_size.width, 200 + 2 * BUTTON_PADDING);
+    }
+
+    fn focus_with_three_buttons() -> Focus {
+        let mut focus = Focus::default();
+        focus.register("first");
+        focus.register("second");
+        focus.register("third");
+        focus
+    }
+
+    #[test]
+    fn focus_moves_between_widgets_in_insertion_order() {
+        let mut focus = focus_with_three_buttons();
+
+        focus.focus_next();
+        assert!(focus.is_focused("first"));
+        focus.focus_next();
+        assert!(focus.is_focused("second"));
+        focus.focus_next();
+        assert!(focus.is_focused("third"));
+        focus.focus_previous();
+        assert!(focus.is_focused("second"));
+    }
+
+    #[test]
+    fn focus_wraps_around_at_the_ends() {
+        let mut focus = focus_with_three_buttons();
+
+        focus.focus_previous();
+        assert!(focus.is_focused("third"));
+        focus.focus_next();
+        assert!(focus.is_focused("first"));
+    }
+
+    fn keyboard_after_pressing(key: Keycode) -> Keyboard<Keycode> {
+        let mut keyboard = InputDevices::new().keyboard;
+        keyboard.register_event(key, ButtonEvent::Down);
+        keyboard.update(0);
+        keyboard
+    }
+
+    #[test]
+    fn arrow_keys_do_not_take_focus() {
+        let mut focus = focus_with_three_buttons();
+
+        focus.handle_keys(&keyboard_after_pressing(Keycode::Right));
+
+        assert_eq!(focus.focused, None);
+    }
+
+    #[test]
+    fn arrow_keys_move_focus_after_tab() {
+        let mut focus = focus_with_three_buttons();
+        focus.handle_keys(&keyboard_after_pressing(Keycode::Tab));
+        assert!(focus.is_focused("first"));
+
+        focus.handle_keys(&keyboard_after_pressing(Keycode::Right));
+
+        assert!(focus.is_focused("second"));
+    }
+
+    #[test]
+    fn focused_ui_is_active_input_context() {
+        let mut input = InputDevices::new();
+
+        set_focus_input_context(true, &mut input);
+        assert_eq!(input.active_context(), Some(FOCUS_INPUT_CONTEXT));
+
+        set_focus_input_context(false, &mut input);
+        assert_eq!(input.active_context(), None);
+    }
+
+    #[test]
+    fn only_focused_widget_is_activated() {
+        let mut focus = focus_with_three_buttons();
+
+        focus.focus_next();
+        focus.focus_next();
+        focus.activated = true;
+
+        assert!(!focus.is_activated("first"));
+        assert!(focus.is_activated("second"));
+        assert!(!focus.is_activated("third"));
+    }
+}
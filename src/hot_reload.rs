@@ -1,5 +1,4 @@
 use engine::Engine;
-use sdl2::keyboard::Keycode;
 use std::process::Child;
 use std::process::Command;
 
@@ -41,7 +40,10 @@ impl HotReloader {
 
             match self.command_status() {
                 CommandStatus::Idle => {
-                    if engine.input.keyboard.is_pressed_now(Keycode::F5) {
+                    if engine
+                        .input
+                        .is_hotkey_pressed_now(engine.hotkeys().rebuild_game)
+                    {
                         self.rebuild_game_lib();
                     }
                 }
@@ -45,7 +45,9 @@ fn init_game(engine: &mut Engine, config: &ProgramConfig) -> game::GameState {
     }
 }
 
-fn serialize_config(config: &mut ProgramConfig, game: &GameState) {
+fn serialize_config(config: &mut ProgramConfig, engine: &Engine, game: &GameState) {
+    config.fullscreen_mode = engine.fullscreen_system.fullscreen_mode();
+    config.audio_volumes = engine.audio.volumes();
     game::write_to_config(config, game);
     config.write_to_disk();
 }
@@ -62,11 +64,21 @@ fn main() {
     let mut hot_reloader = hot_reload::HotReloader::new();
 
     engine.renderer.set_resolution(400, 300);
+    engine
+        .fullscreen_system
+        .set_fullscreen_mode(config.fullscreen_mode);
+    engine.set_hotkeys(config.hotkeys);
+    engine.set_screenshot_dir(&config.screenshot_dir);
+    engine.audio.set_volumes(config.audio_volumes);
+    engine
+        .resource_reloader()
+        .set_debounce_time(config.hot_reload_debounce);
 
     /* Main loop */
     while !engine.should_quit() {
         /* Input */
         let sdl_events = engine.begin_frame();
+        engine.set_input_capture(imgui.input_capture());
         engine.handle_input(&sdl_events);
         imgui.handle_input(&sdl_events);
 
@@ -83,5 +95,5 @@ fn main() {
         engine.end_frame(&open_gl);
     }
 
-    serialize_config(&mut config, &game);
+    serialize_config(&mut config, &engine, &game);
 }